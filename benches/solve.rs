@@ -0,0 +1,35 @@
+// solve's reductions mod q: powm(v, 1, q), as solve used to reduce, against a plain v % q, and
+// solve itself, in the 160-bit q of the 1024-bit group and the 256-bit q of the 2048-bit one
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use num_bigint::BigUint;
+use std::hint::black_box;
+use zkp_chaum_pedersen::{ZKP, powm};
+
+fn solve(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("solve");
+    for (bits, (alpha, beta, p, q)) in
+        [(1024, ZKP::get_constants()), (2048, ZKP::get_constants_2048())]
+    {
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        // what solve reduces: q + k - c * x mod q, somewhere in (0, 2q)
+        let value = &q + &k - (&c * &x) % &q;
+        let one = BigUint::from(1u32);
+
+        group.bench_with_input(BenchmarkId::new("powm(v, 1, q)", bits), &value, |b, value| {
+            b.iter(|| powm(black_box(value), &one, &q))
+        });
+        group.bench_with_input(BenchmarkId::new("v % q", bits), &value, |b, value| {
+            b.iter(|| black_box(value) % &q)
+        });
+        group.bench_function(BenchmarkId::new("solve", bits), |b| {
+            b.iter(|| zkp.solve(&k, black_box(&c), &x).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, solve);
+criterion_main!(benches);
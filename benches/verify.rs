@@ -0,0 +1,32 @@
+// full verification vs the early rejection of out-of-range answers (s >= q), and the hot path
+// of verify_authentication once y1^c, y2^c were computed with the challenge
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use zkp_chaum_pedersen::ZKP;
+
+fn verify(criterion: &mut Criterion) {
+    let (alpha, beta, p, q) = ZKP::get_constants();
+    let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+    let x = ZKP::generate_random_number_below(&q);
+    let k = ZKP::generate_random_number_below(&q);
+    let c = ZKP::generate_random_number_below(&q);
+    let (y1, y2) = zkp.compute_pair(&x);
+    let (r1, r2) = zkp.compute_pair(&k);
+    let s = zkp.solve(&k, &c, &x).unwrap();
+    let out_of_range = &s + &q;
+    let terms = zkp.challenge_terms(&y1, &y2, &c);
+
+    criterion.bench_function("verify valid proof", |b| {
+        b.iter(|| black_box(zkp.verify(&r1, &r2, &y1, &y2, &c, black_box(&s))))
+    });
+    criterion.bench_function("verify valid proof with precomputed y^c", |b| {
+        b.iter(|| black_box(zkp.verify_precomputed(&r1, &r2, black_box(&s), &terms)))
+    });
+    criterion.bench_function("verify rejects s >= q", |b| {
+        b.iter(|| black_box(zkp.verify(&r1, &r2, &y1, &y2, &c, black_box(&out_of_range))))
+    });
+}
+
+criterion_group!(benches, verify);
+criterion_main!(benches);
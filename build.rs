@@ -1,6 +1,9 @@
 fn main() {
     tonic_build::configure()
         .build_server(true)
+        // the generated client and server need tonic, which verifier-only builds leave out
+        .client_mod_attribute("zkp_auth", "#[cfg(feature = \"prover\")]")
+        .server_mod_attribute("zkp_auth", "#[cfg(feature = \"prover\")]")
         .out_dir("src/") // you can change the generated code's location
         .compile(
             &["proto/zkp_auth.proto"],
@@ -0,0 +1,183 @@
+// Append-only JSONL record of the authentication events (ZKP_AUDIT_LOG=<path>), tamper-evident:
+// every line carries hash = H(prev_hash || entry), entry being the JSON of its timestamp and
+// event and prev_hash the hash of the line before (32 zero bytes for the first line), so editing,
+// dropping or reordering lines breaks the chain from there on
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zkp_chaum_pedersen::ZKP;
+
+use crate::SessionRecord;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthEvent {
+    Registered { user: String },
+    LoginSucceeded { user: String },
+    LoginFailed { user: String },
+    // a whole login attempt, only with ZKP_SESSION_RECORDS=1
+    Session(Box<SessionRecord>),
+}
+
+impl AuthEvent {
+    // the "type" of its JSON (WatchedEvent.kind)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuthEvent::Registered { .. } => "registered",
+            AuthEvent::LoginSucceeded { .. } => "login_succeeded",
+            AuthEvent::LoginFailed { .. } => "login_failed",
+            AuthEvent::Session(_) => "session",
+        }
+    }
+
+    pub fn user(&self) -> &str {
+        match self {
+            AuthEvent::Registered { user }
+            | AuthEvent::LoginSucceeded { user }
+            | AuthEvent::LoginFailed { user } => user,
+            AuthEvent::Session(record) => &record.user,
+        }
+    }
+}
+
+// what is hashed, the line is this plus the hash
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    timestamp: u64,
+    event: AuthEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Line {
+    #[serde(flatten)]
+    entry: Entry,
+    hash: String,
+}
+
+fn chain(prev_hash: &[u8; 32], entry: &Entry) -> [u8; 32] {
+    let entry = serde_json::to_vec(entry).expect("an entry always serializes");
+    Sha256::new().chain_update(prev_hash).chain_update(entry).finalize().into()
+}
+
+// hash of the last line of a log, or the 1-based number of the first line breaking the chain
+// (unparsable, or a hash that does not follow from the lines before)
+pub fn verify_chain(log: &str) -> Result<[u8; 32], usize> {
+    let mut prev_hash = [0u8; 32];
+    for (number, line) in log.lines().enumerate() {
+        let line: Line = serde_json::from_str(line).map_err(|_| number + 1)?;
+        let hash = chain(&prev_hash, &line.entry);
+        if hex::encode(hash) != line.hash {
+            return Err(number + 1);
+        }
+        prev_hash = hash;
+    }
+    Ok(prev_hash)
+}
+
+#[derive(Debug)]
+pub struct AuditLog {
+    // the file (opened for appending) and the hash of its last line
+    file: Mutex<(File, [u8; 32])>,
+}
+
+impl AuditLog {
+    // opens (or creates) the log and checks its chain, a broken one is an InvalidData error
+    // unless allow_broken, then the new lines are chained to the last one as it is
+    pub fn open(path: impl AsRef<Path>, allow_broken: bool) -> io::Result<Self> {
+        let path = path.as_ref();
+        let log = match std::fs::read_to_string(path) {
+            Ok(log) => log,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let last_hash = match verify_chain(&log) {
+            Ok(hash) => hash,
+            Err(line) if allow_broken => {
+                tracing::warn!("⚠️  Audit log {path:?} broken at line {line}, appending anyway");
+                last_line_hash(&log)
+            }
+            Err(line) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("audit log {path:?} broken at line {line}"),
+                ));
+            }
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(AuditLog { file: Mutex::new((file, last_hash)) })
+    }
+
+    // one line per event, written (and its hash kept) only if the whole line went out
+    pub fn append(&self, event: AuthEvent) -> io::Result<()> {
+        let (file, last_hash) = &mut *self.file.lock().unwrap();
+        let entry = Entry { timestamp: ZKP::unix_timestamp(), event };
+        let hash = chain(last_hash, &entry);
+        let line = Line { entry, hash: hex::encode(hash) };
+        let mut bytes = serde_json::to_vec(&line).expect("a line always serializes");
+        bytes.push(b'\n');
+        file.write_all(&bytes)?;
+        file.flush()?;
+        *last_hash = hash;
+        Ok(())
+    }
+}
+
+// the hash the last line claims, whether or not it is right (zeros if there is none)
+fn last_line_hash(log: &str) -> [u8; 32] {
+    log.lines()
+        .rev()
+        .find_map(|line| {
+            let line: Line = serde_json::from_str(line).ok()?;
+            hex::decode(line.hash).ok()?.try_into().ok()
+        })
+        .unwrap_or([0u8; 32])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_chain() {
+        let path = std::env::temp_dir().join(format!("zkp_audit_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let log = AuditLog::open(&path, false).unwrap();
+        log.append(AuthEvent::Registered { user: "alice".to_string() }).unwrap();
+        log.append(AuthEvent::LoginFailed { user: "alice".to_string() }).unwrap();
+        drop(log);
+
+        // reopened, the new line follows on from the old ones
+        let log = AuditLog::open(&path, false).unwrap();
+        log.append(AuthEvent::LoginSucceeded { user: "alice".to_string() }).unwrap();
+        drop(log);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert!(verify_chain(&contents).is_ok());
+        // the names WatchEvents sends are the ones in the log
+        for line in contents.lines() {
+            let line: Line = serde_json::from_str(line).unwrap();
+            assert!(contents.contains(&format!("\"type\":\"{}\"", line.entry.event.kind())));
+            assert_eq!(line.entry.event.user(), "alice");
+        }
+
+        // a failed login turned into a successful one
+        let tampered = contents.replacen("login_failed", "login_succeeded", 1);
+        assert_eq!(verify_chain(&tampered), Err(2));
+        // a line dropped
+        let dropped: String = contents.lines().skip(1).map(|line| format!("{line}\n")).collect();
+        assert_eq!(verify_chain(&dropped), Err(1));
+
+        std::fs::write(&path, &tampered).unwrap();
+        assert_eq!(AuditLog::open(&path, false).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(AuditLog::open(&path, true).is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,489 @@
+use std::{future::Future, time::Duration};
+
+use num_bigint::BigUint;
+use prost::Message;
+use tonic::{Code, Status, transport::Channel};
+
+use crate::zkp_auth::{
+    AnswerFailure, AuthenticationAnswerRequest, AuthenticationAnswerResponse,
+    AuthenticationChallengeRequest, AuthenticationChallengeResponse, RegisterRequest,
+    RegisterResponse, auth_client,
+};
+use crate::{Secret, ZKP};
+
+// The RPCs AuthClient drives, implemented by the generated tonic client
+// (tests plug in a mock to simulate an unreliable server)
+// Clone must be cheap: every attempt runs on its own clone, as tonic clients are meant to be used
+pub trait AuthTransport: Clone {
+    fn register(
+        &mut self,
+        request: RegisterRequest,
+    ) -> impl Future<Output = Result<RegisterResponse, Status>> + Send;
+
+    fn create_authentication_challenge(
+        &mut self,
+        request: AuthenticationChallengeRequest,
+    ) -> impl Future<Output = Result<AuthenticationChallengeResponse, Status>> + Send;
+
+    fn verify_authentication(
+        &mut self,
+        request: AuthenticationAnswerRequest,
+    ) -> impl Future<Output = Result<AuthenticationAnswerResponse, Status>> + Send;
+}
+
+impl AuthTransport for auth_client::AuthClient<Channel> {
+    async fn register(&mut self, request: RegisterRequest) -> Result<RegisterResponse, Status> {
+        Ok(auth_client::AuthClient::register(self, request).await?.into_inner())
+    }
+
+    async fn create_authentication_challenge(
+        &mut self,
+        request: AuthenticationChallengeRequest,
+    ) -> Result<AuthenticationChallengeResponse, Status> {
+        Ok(auth_client::AuthClient::create_authentication_challenge(self, request)
+            .await?
+            .into_inner())
+    }
+
+    async fn verify_authentication(
+        &mut self,
+        request: AuthenticationAnswerRequest,
+    ) -> Result<AuthenticationAnswerResponse, Status> {
+        Ok(auth_client::AuthClient::verify_authentication(self, request).await?.into_inner())
+    }
+}
+
+// How AuthClient retries a call that failed with a transient error:
+// up to max_retries more attempts, waiting initial_backoff, then twice as long each time
+// (capped at max_backoff)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // only failures of the connection are worth retrying, a rejected proof
+    // (PermissionDenied) or any other answer of the server is returned as is, even an expired
+    // challenge (the server's DeadlineExceeded, told apart by its AnswerFailure details)
+    pub fn is_transient(status: &Status) -> bool {
+        matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded)
+            && Self::answer_failure(status).is_none()
+    }
+
+    // whether the whole login (a new challenge) may be tried again after a refused answer:
+    // the retryable flag of the server, false when the status says nothing about it
+    pub fn login_is_retryable(status: &Status) -> bool {
+        Self::answer_failure(status).is_some_and(|failure| failure.retryable)
+    }
+
+    fn answer_failure(status: &Status) -> Option<AnswerFailure> {
+        if status.details().is_empty() {
+            return None;
+        }
+        AnswerFailure::decode(status.details()).ok()
+    }
+}
+
+// Connection settings of the channel AuthClientBuilder::connect builds: how long to wait for the
+// connection and for each call, and the largest response accepted. The defaults fit the messages
+// of any group up to 8192 bits many times over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientConfig {
+    pub connect_timeout: Duration,
+    // per attempt, a retried call gets this much again
+    pub request_timeout: Duration,
+    pub max_decoding_message_size: usize,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            max_decoding_message_size: 64 * 1024,
+        }
+    }
+}
+
+// Client of the Auth service that rides out short server outages (see RetryPolicy)
+#[derive(Debug, Clone)]
+pub struct AuthClient<T = auth_client::AuthClient<Channel>> {
+    transport: T,
+    retry: RetryPolicy,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuthClientBuilder {
+    retry: RetryPolicy,
+    config: ClientConfig,
+}
+
+impl AuthClientBuilder {
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.retry.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.retry.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.config.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.config.request_timeout = request_timeout;
+        self
+    }
+
+    pub fn max_decoding_message_size(mut self, max_decoding_message_size: usize) -> Self {
+        self.config.max_decoding_message_size = max_decoding_message_size;
+        self
+    }
+
+    pub fn config(mut self, config: ClientConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    //the connection is established on the first call, so a server that is not up yet
+    //is just one more transient error to retry
+    pub fn connect(self, dst: &str) -> Result<AuthClient, tonic::transport::Error> {
+        let channel = tonic::transport::Endpoint::new(dst.to_string())?
+            .connect_timeout(self.config.connect_timeout)
+            .timeout(self.config.request_timeout)
+            .connect_lazy();
+        let client = auth_client::AuthClient::new(channel)
+            .max_decoding_message_size(self.config.max_decoding_message_size);
+        Ok(self.transport(client))
+    }
+
+    pub fn transport<T: AuthTransport>(self, transport: T) -> AuthClient<T> {
+        AuthClient { transport, retry: self.retry }
+    }
+}
+
+impl AuthClient {
+    pub fn builder() -> AuthClientBuilder {
+        AuthClientBuilder::default()
+    }
+}
+
+impl<T: AuthTransport> AuthClient<T> {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    pub async fn register(&self, request: RegisterRequest) -> Result<RegisterResponse, Status> {
+        self.with_retry(|mut transport| {
+            let request = request.clone();
+            async move { transport.register(request).await }
+        })
+        .await
+    }
+
+    pub async fn create_authentication_challenge(
+        &self,
+        request: AuthenticationChallengeRequest,
+    ) -> Result<AuthenticationChallengeResponse, Status> {
+        self.with_retry(|mut transport| {
+            let request = request.clone();
+            async move { transport.create_authentication_challenge(request).await }
+        })
+        .await
+    }
+
+    pub async fn verify_authentication(
+        &self,
+        request: AuthenticationAnswerRequest,
+    ) -> Result<AuthenticationAnswerResponse, Status> {
+        self.with_retry(|mut transport| {
+            let request = request.clone();
+            async move { transport.verify_authentication(request).await }
+        })
+        .await
+    }
+
+    //a whole login with the secret x (challenge for a fresh k, then the answer), returns the
+    //session_id; x and k are Secrets, so both are scrubbed by the time this returns
+    pub async fn authenticate(&self, zkp: &ZKP, user: &str, x: Secret) -> Result<String, Status> {
+        self.authenticate_with_nonce(zkp, user, x, zkp.generate_nonce()).await
+    }
+
+    //authenticate with the nonce k supplied (e.g. generated inside a secure element), in [1, q)
+    //and used for this login only: answering two challenges with one k gives x away
+    pub async fn authenticate_with_nonce(
+        &self,
+        zkp: &ZKP,
+        user: &str,
+        x: Secret,
+        k: Secret,
+    ) -> Result<String, Status> {
+        if *k.expose() == BigUint::from(0u32) || !zkp.is_scalar(k.expose()) {
+            return Err(Status::invalid_argument("the nonce must be in [1, q)"));
+        }
+        let (r1, r2) = zkp.compute_pair(k.expose());
+        let challenge = self
+            .create_authentication_challenge(AuthenticationChallengeRequest {
+                user: user.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            })
+            .await?;
+        let c = zkp.challenge_from_bytes(&challenge.c).map_err(|e| {
+            Status::invalid_argument(format!("server sent an invalid challenge: {e}"))
+        })?;
+
+        // a password read as bytes (Secret::from_bytes_be) is wider than q from about 21 bytes
+        // on in the 1024-bit group: alpha^x only depends on x mod q, so the reduced x answers
+        // for the same registration
+        let x_mod_q = Secret::new(x.expose() % &*zkp.q);
+        drop(x);
+        let s = zkp
+            .solve(k.expose(), &c, x_mod_q.expose())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        drop((k, x_mod_q));
+
+        let response = self
+            .verify_authentication(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s: s.to_bytes_be(),
+                binding: Vec::new(),
+            })
+            .await?;
+        Ok(response.session_id)
+    }
+
+    async fn with_retry<R, F, Fut>(&self, mut call: F) -> Result<R, Status>
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = Result<R, Status>>,
+    {
+        let mut backoff = self.retry.initial_backoff;
+        let mut retries = 0;
+
+        loop {
+            match call(self.transport.clone()).await {
+                Err(status)
+                    if RetryPolicy::is_transient(&status) && retries < self.retry.max_retries =>
+                {
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.retry.max_backoff);
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    // answers every RPC with the next scripted failure, then succeeds
+    #[derive(Clone)]
+    struct MockTransport {
+        failures: Arc<Vec<Code>>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl MockTransport {
+        fn new(failures: &[Code]) -> Self {
+            MockTransport { failures: Arc::new(failures.to_vec()), calls: Arc::default() }
+        }
+
+        fn calls(&self) -> u32 {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        // Status is what the transport returns, so the mock fails with it as is
+        #[allow(clippy::result_large_err)]
+        fn next(&self) -> Result<(), Status> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) as usize;
+            match self.failures.get(call) {
+                Some(code) => Err(Status::new(*code, "scripted failure")),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl AuthTransport for MockTransport {
+        async fn register(&mut self, _: RegisterRequest) -> Result<RegisterResponse, Status> {
+            self.next().map(|_| RegisterResponse {})
+        }
+
+        async fn create_authentication_challenge(
+            &mut self,
+            _: AuthenticationChallengeRequest,
+        ) -> Result<AuthenticationChallengeResponse, Status> {
+            self.next().map(|_| AuthenticationChallengeResponse {
+                auth_id: "auth".to_string(),
+                c: vec![4],
+                group_id: Vec::new(),
+                binding: Vec::new(),
+            })
+        }
+
+        async fn verify_authentication(
+            &mut self,
+            _: AuthenticationAnswerRequest,
+        ) -> Result<AuthenticationAnswerResponse, Status> {
+            self.next().map(|_| AuthenticationAnswerResponse {
+                session_id: "session".to_string(),
+                receipt: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_answer_failure_is_not_transient() {
+        let failure = |code, retryable| {
+            let details = AnswerFailure { code: "challenge_expired".to_string(), retryable };
+            Status::with_details(code, "refused", details.encode_to_vec().into())
+        };
+
+        // an expired challenge is no timeout of the connection, the login starts over instead
+        let expired = failure(Code::DeadlineExceeded, true);
+        assert!(!RetryPolicy::is_transient(&expired));
+        assert!(RetryPolicy::login_is_retryable(&expired));
+        assert!(!RetryPolicy::login_is_retryable(&failure(Code::PermissionDenied, false)));
+
+        let timeout = Status::new(Code::DeadlineExceeded, "timed out");
+        assert!(RetryPolicy::is_transient(&timeout));
+        assert!(!RetryPolicy::login_is_retryable(&timeout));
+    }
+
+    // the largest messages a login decodes, every value as wide as an 8192-bit group makes it
+    #[test]
+    fn test_default_config_fits_large_groups() {
+        use crate::zkp_auth::VerificationReceipt;
+
+        let (element, scalar) = (vec![0xFF; 8192 / 8], vec![0xFF; 64]);
+        let challenge = AuthenticationChallengeResponse {
+            auth_id: "A".repeat(64),
+            c: scalar.clone(),
+            group_id: vec![0xFF; 32],
+            binding: vec![0xFF; 32],
+        };
+        let answer = AuthenticationAnswerResponse {
+            session_id: "S".repeat(64),
+            receipt: Some(VerificationReceipt {
+                auth_id: "A".repeat(64),
+                user: "U".repeat(256),
+                c: scalar.clone(),
+                s: scalar.clone(),
+                valid: true,
+                timestamp: u64::MAX,
+                sig_r1: element.clone(),
+                sig_r2: element.clone(),
+                sig_c: scalar.clone(),
+                sig_s: scalar.clone(),
+            }),
+        };
+
+        let limit = ClientConfig::default().max_decoding_message_size;
+        assert!(challenge.encoded_len() < limit);
+        // with room to spare for fields added later
+        assert!(answer.encoded_len() * 8 < limit, "{} bytes", answer.encoded_len());
+
+        let config = AuthClient::builder().max_decoding_message_size(1 << 20).config;
+        assert_eq!(config.max_decoding_message_size, 1 << 20);
+        assert_eq!(config.request_timeout, ClientConfig::default().request_timeout);
+    }
+
+    fn client(mock: &MockTransport, max_retries: u32) -> AuthClient<MockTransport> {
+        AuthClient::builder()
+            .max_retries(max_retries)
+            .initial_backoff(Duration::from_millis(1))
+            .max_backoff(Duration::from_millis(4))
+            .transport(mock.clone())
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let mock = MockTransport::new(&[Code::Unavailable, Code::DeadlineExceeded]);
+
+        let response = client(&mock, 3).register(RegisterRequest::default()).await;
+        assert!(response.is_ok());
+        assert_eq!(mock.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let mock = MockTransport::new(&[Code::Unavailable; 5]);
+
+        let request = AuthenticationChallengeRequest::default();
+        let status = client(&mock, 2).create_authentication_challenge(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(mock.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_consumes_the_secret() {
+        let mock = MockTransport::new(&[Code::Unavailable]);
+        let zkp = ZKP::default();
+
+        // the Secret is moved in and dropped (scrubbed) inside: the caller keeps no copy,
+        // which the borrow checker enforces, and only the session comes back
+        let x = Secret::from_bytes_be(b"password");
+        let session_id = client(&mock, 3).authenticate(&zkp, "alice", x).await.unwrap();
+        assert_eq!(session_id, "session");
+        assert_eq!(mock.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_with_a_password_wider_than_q() {
+        let mock = MockTransport::new(&[]);
+        let zkp = ZKP::default();
+
+        // 40 bytes, twice as wide as the 160-bit q
+        let password = b"correct horse battery staple, but longer";
+        assert!(!zkp.is_scalar(&BigUint::from_bytes_be(password)));
+        let x = Secret::from_bytes_be(password);
+        let session_id = client(&mock, 0).authenticate(&zkp, "alice", x).await.unwrap();
+        assert_eq!(session_id, "session");
+    }
+
+    #[tokio::test]
+    async fn test_permission_denied_is_not_retried() {
+        let mock = MockTransport::new(&[Code::PermissionDenied]);
+
+        let request = AuthenticationAnswerRequest::default();
+        let status = client(&mock, 3).verify_authentication(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(mock.calls(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use crate::{GroupEncoding, ZKP, ZKPError};
+
+// One authentication protocol behind the gRPC service, on the wire encodings of its values.
+// The server keeps the bookkeeping (users, challenges, sessions, TTLs, replays) and picks the
+// group, a backend only decides what a valid registration, challenge and answer are in it.
+// Object safe, the server holds an Arc<dyn AuthBackend> chosen at startup
+pub trait AuthBackend: fmt::Debug + Send + Sync {
+    // checks the public values of a registration
+    fn register(&self, group: &ZKP, y1: &[u8], y2: &[u8]) -> Result<(), ZKPError>;
+
+    // checks the prover's commitment and returns a fresh challenge c
+    fn challenge(&self, group: &ZKP, r1: &[u8], r2: &[u8]) -> Result<Vec<u8>, ZKPError>;
+
+    // whether s answers c for the registration (y1, y2) and the commitment (r1, r2)
+    #[allow(clippy::too_many_arguments)]
+    fn verify(
+        &self,
+        group: &ZKP,
+        y1: &[u8],
+        y2: &[u8],
+        r1: &[u8],
+        r2: &[u8],
+        c: &[u8],
+        s: &[u8],
+    ) -> Result<bool, ZKPError>;
+
+    // true only when verify is ZKP::verify, so the server may precompute (y1^c, y2^c) when it
+    // issues c (ZKP::challenge_terms) and answer with ZKP::verify_precomputed
+    fn is_chaum_pedersen(&self) -> bool {
+        false
+    }
+}
+
+// the protocol of this crate, the default backend of the server
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChaumPedersen;
+
+fn subgroup_pair(group: &ZKP, a: &[u8], b: &[u8], names: &'static str) -> Result<(), ZKPError> {
+    let (a, b) = (group.decode_element(a)?, group.decode_element(b)?);
+    if group.is_in_subgroup(&a) && group.is_in_subgroup(&b) {
+        Ok(())
+    } else {
+        Err(ZKPError::NotInSubgroup(names))
+    }
+}
+
+impl AuthBackend for ChaumPedersen {
+    fn register(&self, group: &ZKP, y1: &[u8], y2: &[u8]) -> Result<(), ZKPError> {
+        subgroup_pair(group, y1, y2, "y1/y2")?;
+        group.check_commitment_strength(&group.decode_element(y1)?, &group.decode_element(y2)?)
+    }
+
+    fn challenge(&self, group: &ZKP, r1: &[u8], r2: &[u8]) -> Result<Vec<u8>, ZKPError> {
+        subgroup_pair(group, r1, r2, "r1/r2")?;
+        Ok(group.encode_scalar(&group.generate_challenge()))
+    }
+
+    fn verify(
+        &self,
+        group: &ZKP,
+        y1: &[u8],
+        y2: &[u8],
+        r1: &[u8],
+        r2: &[u8],
+        c: &[u8],
+        s: &[u8],
+    ) -> Result<bool, ZKPError> {
+        Ok(group.verify(
+            &group.decode_element(r1)?,
+            &group.decode_element(r2)?,
+            &group.decode_element(y1)?,
+            &group.decode_element(y2)?,
+            &group.decode_scalar(c)?,
+            &group.decode_scalar(s)?,
+        ))
+    }
+
+    fn is_chaum_pedersen(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chaum_pedersen_backend() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p.clone(), q.clone());
+        let backend: Box<dyn AuthBackend> = Box::new(ChaumPedersen);
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let (y1, y2, r1, r2) =
+            (y1.to_bytes_be(), y2.to_bytes_be(), r1.to_bytes_be(), r2.to_bytes_be());
+
+        assert_eq!(backend.register(&zkp, &y1, &y2), Ok(()));
+        let p_minus_1 = (&p - 1u32).to_bytes_be();
+        assert_eq!(backend.register(&zkp, &y1, &p_minus_1), Err(ZKPError::NotInSubgroup("y1/y2")));
+
+        let c = backend.challenge(&zkp, &r1, &r2).unwrap();
+        let s = zkp.solve(&k, &zkp.decode_scalar(&c).unwrap(), &x).unwrap().to_bytes_be();
+        assert_eq!(backend.verify(&zkp, &y1, &y2, &r1, &r2, &c, &s), Ok(true));
+        assert_eq!(backend.verify(&zkp, &y2, &y1, &r1, &r2, &c, &s), Ok(false));
+    }
+}
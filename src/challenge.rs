@@ -0,0 +1,173 @@
+use num_bigint::BigUint;
+use std::fmt;
+
+use crate::{ChallengeSource, Commitment, Proof, ZKP};
+
+// What a challenge may be derived from: the group, the registration and the prover's (r1, r2)
+#[derive(Debug, Clone, Copy)]
+pub struct ChallengeTranscript<'a> {
+    pub zkp: &'a ZKP,
+    pub commitment: &'a Commitment,
+    pub r1: &'a BigUint,
+    pub r2: &'a BigUint,
+}
+
+impl<'a> ChallengeTranscript<'a> {
+    pub fn new(zkp: &'a ZKP, commitment: &'a Commitment, r1: &'a BigUint, r2: &'a BigUint) -> Self {
+        ChallengeTranscript { zkp, commitment, r1, r2 }
+    }
+}
+
+// Where c comes from, apart from what is then done with it: a verifier issuing random
+// challenges, Fiat-Shamir for non-interactive proofs, a fixed c for tests all go through the
+// same solve and verify. Object safe, the server holds a Box<dyn ChallengeProvider>
+pub trait ChallengeProvider: fmt::Debug + Send + Sync {
+    // c in [0, q) for this transcript
+    fn challenge(&self, transcript: &ChallengeTranscript) -> BigUint;
+}
+
+// interactive verifier: a fresh c in [1, q) every time, whatever the transcript
+#[cfg(feature = "prover")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomChallenge;
+
+#[cfg(feature = "prover")]
+impl ChallengeProvider for RandomChallenge {
+    fn challenge(&self, transcript: &ChallengeTranscript) -> BigUint {
+        transcript.zkp.generate_challenge()
+    }
+}
+
+// non-interactive: c hashed from the transcript and a context (ZKP::fiat_shamir_challenge), or
+// under a domain tag too (ZKP::fiat_shamir_challenge_in)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FiatShamirChallenge {
+    pub domain: Option<Vec<u8>>,
+    pub context: Vec<u8>,
+}
+
+impl FiatShamirChallenge {
+    pub fn new(context: impl Into<Vec<u8>>) -> Self {
+        FiatShamirChallenge { domain: None, context: context.into() }
+    }
+
+    pub fn in_domain(domain: impl Into<Vec<u8>>, context: impl Into<Vec<u8>>) -> Self {
+        FiatShamirChallenge { domain: Some(domain.into()), context: context.into() }
+    }
+}
+
+impl ChallengeProvider for FiatShamirChallenge {
+    fn challenge(&self, transcript: &ChallengeTranscript) -> BigUint {
+        let ChallengeTranscript { zkp, commitment, r1, r2 } = *transcript;
+        match &self.domain {
+            None => zkp.fiat_shamir_challenge(commitment, r1, r2, &self.context),
+            Some(domain) => zkp.fiat_shamir_challenge_in(domain, commitment, r1, r2, &self.context),
+        }
+    }
+}
+
+// always the same c: tests that predict the whole transcript. Known in advance, it lets anyone
+// through (r1 = alpha^s * y1^c for any s), never use it outside of tests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedChallenge(pub BigUint);
+
+impl FixedChallenge {
+    pub fn new(c: BigUint) -> Self {
+        FixedChallenge(c)
+    }
+}
+
+impl ChallengeProvider for FixedChallenge {
+    fn challenge(&self, _: &ChallengeTranscript) -> BigUint {
+        self.0.clone()
+    }
+}
+
+impl ZKP {
+    //proof of knowledge of x with a fresh k and c from the provider
+    #[cfg(feature = "prover")]
+    pub fn prove_with(&self, x: &BigUint, provider: &dyn ChallengeProvider) -> Proof {
+        let (y1, y2) = self.compute_pair(x);
+        let commitment = Commitment::new(y1, y2);
+        let k = self.generate_nonce();
+        let (r1, r2) = self.compute_pair(k.expose());
+        let c = provider.challenge(&ChallengeTranscript::new(self, &commitment, &r1, &r2));
+        let s = self.respond(k.expose(), &c, x);
+        Proof::new(r1, r2, c, s)
+    }
+
+    //derives c again and checks the proof against it (verify_proof with ChallengeSource::Given)
+    //deterministic providers only: against RandomChallenge, check the c that was issued instead
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_with(
+        &self,
+        commitment: &Commitment,
+        proof: &Proof,
+        provider: &dyn ChallengeProvider,
+    ) -> bool {
+        let c =
+            provider.challenge(&ChallengeTranscript::new(self, commitment, &proof.r1, &proof.r2));
+        self.verify_proof(commitment, proof, ChallengeSource::Given(&c))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn toy() -> ZKP {
+        ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into())
+    }
+
+    #[test]
+    fn test_fixed_challenge() {
+        // x = 6, k = 7: y1 = 2, y2 = 3, r1 = 8, r2 = 4, and c = 4 gives s = 5
+        let zkp = toy();
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        let provider = FixedChallenge::new(4u32.into());
+        let proof = Proof::new(8u32.into(), 4u32.into(), 4u32.into(), 5u32.into());
+        assert!(zkp.verify_with(&commitment, &proof, &provider));
+        assert!(!zkp.verify_with(&commitment, &proof, &FixedChallenge::new(5u32.into())));
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_providers_share_one_verify_path() {
+        let zkp = toy();
+        let x = BigUint::from(6u32);
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        let providers: [Box<dyn ChallengeProvider>; 4] = [
+            Box::new(RandomChallenge),
+            Box::new(FiatShamirChallenge::new(b"session 42".to_vec())),
+            Box::new(FiatShamirChallenge::in_domain(b"test/v1/login".to_vec(), b"one".to_vec())),
+            Box::new(FixedChallenge::new(4u32.into())),
+        ];
+        for provider in &providers {
+            for _ in 0..20 {
+                // the verifier issues c from the prover's (r1, r2), the prover answers it
+                let k = zkp.generate_nonce();
+                let (r1, r2) = zkp.compute_pair(k.expose());
+                let c = provider.challenge(&ChallengeTranscript::new(&zkp, &commitment, &r1, &r2));
+                let s = zkp.solve(k.expose(), &c, &x).unwrap();
+                let proof = Proof::new(r1, r2, c.clone(), s);
+                assert!(zkp.verify_proof(&commitment, &proof, ChallengeSource::Given(&c)));
+
+                let wrong = Proof { s: (&proof.s + 1u32) % 11u32, ..proof };
+                assert!(!zkp.verify_proof(&commitment, &wrong, ChallengeSource::Given(&c)));
+            }
+        }
+
+        // the deterministic ones derive the same c on both sides
+        for provider in &providers[1..] {
+            let proof = zkp.prove_with(&x, provider.as_ref());
+            assert!(zkp.verify_with(&commitment, &proof, provider.as_ref()));
+        }
+
+        // and Fiat-Shamir is the c of the non-interactive proofs
+        let proof = zkp.prove_with(&x, &FiatShamirChallenge::new(b"session 42".to_vec()));
+        assert!(zkp.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(b"session 42")));
+        let proof = zkp.prove_non_interactive_in(&x, Some(b"test/v1/login"), b"one");
+        let provider = FiatShamirChallenge::in_domain(b"test/v1/login".to_vec(), b"one".to_vec());
+        assert!(zkp.verify_with(&commitment, &proof, &provider));
+    }
+}
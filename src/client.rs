@@ -1,31 +1,26 @@
 //mod lib;
 //use zkp_chaum_pedersen::*;
-use zkp_chaum_pedersen::ZKP;
+use zkp_chaum_pedersen::{AuthClient, Secret, ZKP};
 
-use num_bigint::BigUint;
 use std::io::stdin;
+use tonic::Code;
+use zeroize::{Zeroize, Zeroizing};
 
-pub mod zkp_auth {
-    include!("./zkp_auth.rs"); // Include the generated gRPC code
-}
-
-use zkp_auth::{
-    AuthenticationAnswerRequest, AuthenticationChallengeRequest, RegisterRequest,
-    auth_client::AuthClient,
-};
+use zkp_chaum_pedersen::zkp_auth::RegisterRequest;
 
 #[tokio::main]
 async fn main() {
-    let mut buf = String::new();
-    let (alpha, beta, p, q) = ZKP::get_constants();
+    // the passwords go through this buffer: scrubbed after each use and when dropped
+    let mut buf = Zeroizing::new(String::new());
+    let (alpha, beta, p, q) = ZKP::default_constants();
     //let zkp = ZKP { alpha: alpha.clone(), beta: beta.clone(), p: p.clone(), q: q.clone() }; // private field error
 
     let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone()); // Create a new instance of ZKP 
 
-    let mut client = AuthClient::connect("http://127.0.0.1:50051")
-        .await
+    // Connect to the gRPC server, retrying with the default backoff while it is unavailable
+    let client = AuthClient::builder()
+        .connect("http://127.0.0.1:50051")
         .expect("could not connect to the server");
-    // Connect to the gRPC server
 
     println!("✅ Connected to the server");
 
@@ -36,54 +31,47 @@ async fn main() {
 
     println!("Please provide the password:");
     stdin().read_line(&mut buf).expect("Could not get the username from stdin");
-    let password = BigUint::from_bytes_be(buf.trim().as_bytes());
-    buf.clear(); // Clear the buffer
-
-    let (y1, y2) = zkp.compute_pair(&password);
-
-    let request =
-        RegisterRequest { user: username.clone(), y1: y1.to_bytes_be(), y2: y2.to_bytes_be() };
-
-    let _response = client.register(request).await.expect("Could not register in server");
-    println!("Response from server during registration: {_response:?}");
-
-    println!("Registration successful for user: {username}");
+    let password = Secret::from_bytes_be(buf.trim().as_bytes());
+    buf.zeroize(); // Scrub the buffer
+
+    let (y1, y2) = zkp.compute_pair(password.expose());
+    drop(password);
+
+    // same variable as the server: how many zero bits the registration proof of work needs
+    let pow_bits = std::env::var("ZKP_REGISTRATION_POW_BITS")
+        .map(|bits| bits.parse().expect("ZKP_REGISTRATION_POW_BITS must be a number"))
+        .unwrap_or(0);
+
+    let request = RegisterRequest {
+        user: username.clone(),
+        y1: y1.to_bytes_be(),
+        y2: y2.to_bytes_be(),
+        group_id: zkp.fingerprint().to_vec(),
+        pow_nonce: zkp_chaum_pedersen::solve_registration_pow(&username, pow_bits),
+    };
+
+    match client.register(request).await {
+        Ok(_response) => {
+            println!("Response from server during registration: {_response:?}");
+            println!("Registration successful for user: {username}");
+        }
+        // registered by an earlier run, go on with the login
+        Err(status) if status.code() == Code::AlreadyExists => {
+            println!("User {username} is already registered");
+        }
+        Err(status) => panic!("Could not register in server: {status}"),
+    }
 
     println!("Please provide the password (to login):");
     stdin().read_line(&mut buf).expect("Could not get the username from stdin");
-    let password = BigUint::from_bytes_be(buf.trim().as_bytes());
-    buf.clear();
-
-    let k = ZKP::generate_random_number_below(&q); // Generate a random number below q
-    let (r1, r2) = zkp.compute_pair(&k); // Compute the pair (r1, r2) using the random number k
-
-    let request = AuthenticationChallengeRequest {
-        user: username,
-        r1: r1.to_bytes_be(),
-        r2: r2.to_bytes_be(),
-    }; // Create the authentication challenge request
-
-    let response = client
-        .create_authentication_challenge(request)
-        .await
-        .expect("Could not request challenge to server")
-        .into_inner();
-    let auth_id = response.auth_id; // Extract the auth_id from the response
-    let c = BigUint::from_bytes_be(&response.c); // Extract the challenge from the response
-
-    println!("Challenge received: auth_id = {auth_id}, challenge = {c:?}"); // Print the challenge received from the server
-
-    let c = BigUint::from_bytes_be(&response.c); // Convert the challenge to BigUint
-
-    let s = zkp.solve(&k, &c, &password); // Solve the challenge using the ZKP instance
-
-    let request = AuthenticationAnswerRequest { auth_id, s: s.to_bytes_be() }; // Create the authentication answer request 
+    let password = Secret::from_bytes_be(buf.trim().as_bytes());
+    buf.zeroize();
 
-    let response = client
-        .verify_authentication(request)
+    // the password and the nonce k are scrubbed inside authenticate, never printed
+    let session_id = client
+        .authenticate(&zkp, &username, password)
         .await
-        .expect("Could not verify authentication in server")
-        .into_inner();
+        .expect("Could not authenticate in server");
 
-    println!("✅Logging successful! session_id: {}", response.session_id);
+    println!("✅Logging successful! session_id: {session_id}");
 }
@@ -0,0 +1,147 @@
+// Equality of discrete logs across two different groups: log_g1(y1) = log_g2(y2) where g1 lives
+// in Z_p1* and g2 in Z_p2*, with subgroup orders q1 != q2. There is no common modulus to reduce
+// the answer by, so s = k - c * x is computed over the integers: k is drawn far wider than any
+// c * x so that s stays positive and says nothing about x (statistically, STATISTICAL_BITS)
+use num_bigint::BigUint;
+
+#[cfg(feature = "prover")]
+use crate::ZKP;
+use crate::powm;
+
+// how much wider than c * x the nonce is: s is within 2^-STATISTICAL_BITS of uniform
+#[cfg(feature = "prover")]
+pub const STATISTICAL_BITS: usize = 128;
+
+// one of the two groups: generator of the order-q subgroup of Z_p*
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupContext {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub generator: BigUint,
+}
+
+impl GroupContext {
+    pub fn new(p: BigUint, q: BigUint, generator: BigUint) -> Self {
+        GroupContext { p, q, generator }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossGroupZKP {
+    pub first: GroupContext,
+    pub second: GroupContext,
+}
+
+impl CrossGroupZKP {
+    pub fn new(first: GroupContext, second: GroupContext) -> Self {
+        CrossGroupZKP { first, second }
+    }
+
+    // x and c must be below both orders: min(q1, q2)
+    pub fn bound(&self) -> BigUint {
+        self.first.q.clone().min(self.second.q.clone())
+    }
+
+    // (g1^exp mod p1, g2^exp mod p2): (y1, y2) for x, (r1, r2) for k
+    pub fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
+        (
+            powm(&self.first.generator, exp, &self.first.p),
+            powm(&self.second.generator, exp, &self.second.p),
+        )
+    }
+
+    // s = k - c * x over the integers, panics if that is negative (k not from generate_nonce)
+    pub fn solve(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
+        let cx = c * x;
+        assert!(*k >= cx, "the nonce must be at least c * x, see generate_nonce");
+        k - cx
+    }
+
+    // r1 = g1^s * y1^c mod p1 and r2 = g2^s * y2^c mod p2, for a c below the bound
+    pub fn verify(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        let (first, second) = (&self.first, &self.second);
+        *c < self.bound()
+            && *r1 == powm(&first.generator, s, &first.p) * powm(y1, c, &first.p) % &first.p
+            && *r2 == powm(&second.generator, s, &second.p) * powm(y2, c, &second.p) % &second.p
+    }
+
+    // a secret x that fits both groups, in [1, bound)
+    #[cfg(feature = "prover")]
+    pub fn generate_secret(&self) -> BigUint {
+        ZKP::generate_nonzero_below(&self.bound())
+    }
+
+    // k in [bound^2, bound^2 * 2^STATISTICAL_BITS): above any c * x, so s >= 0
+    #[cfg(feature = "prover")]
+    pub fn generate_nonce(&self) -> BigUint {
+        let floor = self.bound().pow(2);
+        let width = (&floor << STATISTICAL_BITS) - &floor;
+        floor + ZKP::sample_below(&width)
+    }
+
+    // c in [1, bound)
+    #[cfg(feature = "prover")]
+    pub fn generate_challenge(&self) -> BigUint {
+        ZKP::generate_nonzero_below(&self.bound())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // g1 = 4 of order 11 mod 23, g2 = 2 of order 23 mod 47
+    fn small_groups() -> CrossGroupZKP {
+        CrossGroupZKP::new(
+            GroupContext::new(23u32.into(), 11u32.into(), 4u32.into()),
+            GroupContext::new(47u32.into(), 23u32.into(), 2u32.into()),
+        )
+    }
+
+    #[test]
+    fn test_cross_group_fixed_run() {
+        // x = 6: y1 = 4^6 mod 23 = 2, y2 = 2^6 mod 47 = 17
+        let zkp = small_groups();
+        assert_eq!(zkp.bound(), BigUint::from(11u32));
+        let x = BigUint::from(6u32);
+        let (y1, y2) = zkp.compute_pair(&x);
+        assert_eq!((y1.clone(), y2.clone()), (2u32.into(), 17u32.into()));
+
+        let (k, c) = (BigUint::from(200u32), BigUint::from(4u32));
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x);
+        assert_eq!(s, BigUint::from(176u32));
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+
+        // another s, or a c not below both orders
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &(&s + 1u32)));
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &11u32.into(), &s));
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_cross_group_random_runs() {
+        let zkp = small_groups();
+        for _ in 0..50 {
+            let x = zkp.generate_secret();
+            let (y1, y2) = zkp.compute_pair(&x);
+            let k = zkp.generate_nonce();
+            let (r1, r2) = zkp.compute_pair(&k);
+            let c = zkp.generate_challenge();
+            assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &zkp.solve(&k, &c, &x)));
+
+            // y2 of another secret: the two logs differ, no s fits both
+            let other = &x % 10u32 + 1u32;
+            let (_, y2_other) = zkp.compute_pair(&other);
+            assert!(!zkp.verify(&r1, &r2, &y1, &y2_other, &c, &zkp.solve(&k, &c, &x)));
+        }
+    }
+}
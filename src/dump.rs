@@ -0,0 +1,164 @@
+// Portable dump of the registrations (not challenges or sessions) for backups and migrations:
+// a JSON array of {"user", "user_id", "y1", "y2", "group_id"}, every byte string in hex
+// (users stored hashed have an empty y1, y2 and a "commitment_digest" instead)
+use std::collections::hash_map::Entry;
+
+use serde::{Deserialize, Serialize};
+use tonic::{Code, Request, Status};
+
+use crate::{AuthImpl, UserInfo};
+use zkp_chaum_pedersen::{
+    GroupEncoding, ZKPError,
+    zkp_auth::{ListUsersRequest, RegisteredUser, auth_client::AuthClient},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserRecord {
+    pub user: String,
+    pub user_id: String,
+    pub y1: String,
+    pub y2: String,
+    pub group_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub commitment_digest: String,
+}
+
+impl From<&RegisteredUser> for UserRecord {
+    fn from(user: &RegisteredUser) -> Self {
+        UserRecord {
+            user: user.user.clone(),
+            user_id: user.user_id.clone(),
+            y1: hex::encode_upper(&user.y1),
+            y2: hex::encode_upper(&user.y2),
+            group_id: hex::encode_upper(&user.group_id),
+            commitment_digest: hex::encode_upper(&user.commitment_digest),
+        }
+    }
+}
+
+// sorted by username so two dumps of the same users are identical
+pub fn to_json(users: &[RegisteredUser]) -> String {
+    let mut records: Vec<UserRecord> = users.iter().map(UserRecord::from).collect();
+    records.sort_by(|a, b| a.user.cmp(&b.user));
+    serde_json::to_string_pretty(&records).expect("records are plain strings")
+}
+
+fn decode_hex(user: &str, field: &str, value: &str) -> Result<Vec<u8>, ZKPError> {
+    hex::decode(value)
+        .map_err(|_| ZKPError::MalformedEncoding(format!("{user}: {field} is not valid hex")))
+}
+
+impl AuthImpl {
+    // the same checks as register (known group, order-q y1/y2, a free name and handle) but the
+    // user_id is kept, a bad dump imports nothing: an invalid record is INVALID_ARGUMENT, a name
+    // or handle already registered (or twice in the dump) ALREADY_EXISTS
+    pub fn import_users(&self, json: &str) -> Result<usize, Status> {
+        let records: Vec<UserRecord> = serde_json::from_str(json)
+            .map_err(|e| Status::new(Code::InvalidArgument, e.to_string()))?;
+
+        let imported = records
+            .into_iter()
+            .map(|record| self.checked_record(record))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Status::new(Code::InvalidArgument, e.to_string()))?;
+
+        // inserted under one lock, like a registration: a name already registered (or twice in
+        // the dump) is not overwritten and, with commitment_handles, a user_id must be a free
+        // handle; the users of the dump inserted so far (and their handles) are taken out again
+        let user_info_hashmap = &mut self.user_info.lock().unwrap();
+        let mut inserted = Vec::with_capacity(imported.len());
+        for user_info in imported {
+            let user_name = user_info.user_name.clone();
+            let refused = match user_info_hashmap.entry(user_name.clone()) {
+                Entry::Occupied(_) => Some(Status::new(
+                    Code::AlreadyExists,
+                    format!("User: {user_name} is already registered"),
+                )),
+                Entry::Vacant(entry) => match self.claim_handle(&user_info) {
+                    Ok(()) => {
+                        entry.insert(user_info);
+                        inserted.push(user_name);
+                        None
+                    }
+                    Err(status) => Some(status),
+                },
+            };
+            if let Some(status) = refused {
+                let handles = &mut self.handles.lock().unwrap();
+                for user_name in &inserted {
+                    if let Some(user_info) = user_info_hashmap.remove(user_name) {
+                        handles.remove(&user_info.user_id);
+                    }
+                }
+                return Err(status);
+            }
+        }
+        Ok(inserted.len())
+    }
+
+    // a record as a UserInfo, checked before anything is stored
+    fn checked_record(&self, record: UserRecord) -> Result<UserInfo, ZKPError> {
+        let group_id = decode_hex(&record.user, "group_id", &record.group_id)?;
+        let Some((group_id, zkp)) = self.groups.resolve(&group_id) else {
+            return Err(ZKPError::GroupMismatch);
+        };
+
+        // nothing left to check of a digest but its length
+        if !record.commitment_digest.is_empty() {
+            let digest = decode_hex(&record.user, "commitment_digest", &record.commitment_digest)?;
+            let digest = digest.try_into().map_err(|_| {
+                ZKPError::MalformedEncoding(format!(
+                    "{}: commitment_digest is not 32 bytes",
+                    record.user
+                ))
+            })?;
+            return Ok(UserInfo {
+                user_name: record.user,
+                user_id: record.user_id,
+                group_id,
+                commitment_digest: Some(digest),
+                ..Default::default()
+            });
+        }
+
+        let y1 = zkp.decode_element(&decode_hex(&record.user, "y1", &record.y1)?)?;
+        let y2 = zkp.decode_element(&decode_hex(&record.user, "y2", &record.y2)?)?;
+        if !zkp.is_in_subgroup(&y1) || !zkp.is_in_subgroup(&y2) {
+            return Err(ZKPError::MalformedEncoding(format!(
+                "{}: y1/y2 are not in the order-q subgroup",
+                record.user
+            )));
+        }
+        zkp.check_commitment_strength(&y1, &y2)?;
+
+        Ok(self.stored(UserInfo {
+            user_name: record.user,
+            user_id: record.user_id,
+            group_id,
+            y1,
+            y2,
+            ..Default::default()
+        }))
+    }
+}
+
+// server export <file>: the state lives in the running server, so the users are fetched
+// through ListUsers (ZKP_ADDR, ZKP_ADMIN_TOKEN as for the server itself)
+pub async fn export(args: &[String]) {
+    let [path] = args else {
+        panic!("usage: server export <file>");
+    };
+    let addr = std::env::var("ZKP_ADDR").unwrap_or_else(|_| "127.0.0.1:50051".to_string());
+    let token = std::env::var("ZKP_ADMIN_TOKEN").expect("ZKP_ADMIN_TOKEN is needed to list users");
+
+    let mut client =
+        AuthClient::connect(format!("http://{addr}")).await.expect("could not connect to server");
+    let mut request = Request::new(ListUsersRequest {});
+    request
+        .metadata_mut()
+        .insert("x-admin-token", token.parse().expect("ZKP_ADMIN_TOKEN must be ASCII"));
+    let users = client.list_users(request).await.expect("could not list users").into_inner().users;
+
+    std::fs::write(path, to_json(&users)).expect("could not write the dump");
+    println!("✅ Exported {} users to {path}", users.len());
+}
@@ -0,0 +1,333 @@
+use num_bigint::BigUint;
+
+use crate::{Commitment, Proof, ZKP, ZKPError};
+
+// Scalars as exact-width big-endian arrays, without the Vec of to_bytes_be / from_bytes_be:
+// 20, 28 and 32 bytes cover the q of the usual groups (160, 224 and 256 bits), any N works
+
+// writes value zero-padded on the left into N bytes, Overflow if it needs more
+pub fn to_fixed_bytes<const N: usize>(value: &BigUint) -> Result<[u8; N], ZKPError> {
+    if value.bits() > 8 * N as u64 {
+        return Err(ZKPError::Overflow(N));
+    }
+    let mut bytes = [0u8; N];
+    // least significant digit first, so filled from the end of the array
+    let mut end = N;
+    for digit in value.iter_u64_digits() {
+        for byte in digit.to_le_bytes() {
+            if end == 0 {
+                break;
+            }
+            end -= 1;
+            bytes[end] = byte;
+        }
+    }
+    Ok(bytes)
+}
+
+pub fn from_fixed_bytes<const N: usize>(bytes: &[u8; N]) -> BigUint {
+    BigUint::from_bytes_be(bytes)
+}
+
+impl ZKP {
+    // a scalar (c, s, x...) of this group as N bytes, refusing anything >= q or wider than N
+    pub fn scalar_to_array<const N: usize>(&self, scalar: &BigUint) -> Result<[u8; N], ZKPError> {
+        if !self.is_scalar(scalar) {
+            return Err(ZKPError::ScalarOutOfRange);
+        }
+        to_fixed_bytes(scalar)
+    }
+
+    pub fn scalar_from_array<const N: usize>(&self, bytes: &[u8; N]) -> Result<BigUint, ZKPError> {
+        let scalar = from_fixed_bytes(bytes);
+        if self.is_scalar(&scalar) { Ok(scalar) } else { Err(ZKPError::ScalarOutOfRange) }
+    }
+}
+
+// Commitments and proofs as one fixed-width blob in a group: y1 || y2, or r1 || r2 || c || s,
+// each element zero-padded to element_byte_len and each scalar to scalar_byte_len. Decoding
+// checks the length and the ranges (elements in [1, p), scalars below q), not the subgroup
+impl ZKP {
+    pub fn commitment_to_bytes(&self, commitment: &Commitment) -> Result<Vec<u8>, ZKPError> {
+        let mut bytes = Vec::with_capacity(2 * self.element_byte_len());
+        self.write_element(&mut bytes, &commitment.y1)?;
+        self.write_element(&mut bytes, &commitment.y2)?;
+        Ok(bytes)
+    }
+
+    pub fn commitment_from_bytes(&self, bytes: &[u8]) -> Result<Commitment, ZKPError> {
+        let element = self.element_byte_len();
+        self.check_len(bytes, 2 * element)?;
+        let (y1, y2) = bytes.split_at(element);
+        Ok(Commitment::new(self.read_element(y1)?, self.read_element(y2)?))
+    }
+
+    pub fn proof_to_bytes(&self, proof: &Proof) -> Result<Vec<u8>, ZKPError> {
+        let mut bytes =
+            Vec::with_capacity(2 * self.element_byte_len() + 2 * self.scalar_byte_len());
+        self.write_element(&mut bytes, &proof.r1)?;
+        self.write_element(&mut bytes, &proof.r2)?;
+        self.write_scalar(&mut bytes, &proof.c)?;
+        self.write_scalar(&mut bytes, &proof.s)?;
+        Ok(bytes)
+    }
+
+    pub fn proof_from_bytes(&self, bytes: &[u8]) -> Result<Proof, ZKPError> {
+        let (element, scalar) = (self.element_byte_len(), self.scalar_byte_len());
+        self.check_len(bytes, 2 * element + 2 * scalar)?;
+        let (r1, rest) = bytes.split_at(element);
+        let (r2, rest) = rest.split_at(element);
+        let (c, s) = rest.split_at(scalar);
+        Ok(Proof::new(
+            self.read_element(r1)?,
+            self.read_element(r2)?,
+            self.read_scalar(c)?,
+            self.read_scalar(s)?,
+        ))
+    }
+
+    fn check_len(&self, bytes: &[u8], expected: usize) -> Result<(), ZKPError> {
+        if bytes.len() != expected {
+            return Err(ZKPError::MalformedEncoding(format!(
+                "expected {expected} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        Ok(())
+    }
+
+    fn is_element(&self, value: &BigUint) -> bool {
+        *value > BigUint::from(0u32) && *value < *self.p
+    }
+
+    fn read_element(&self, bytes: &[u8]) -> Result<BigUint, ZKPError> {
+        let element = BigUint::from_bytes_be(bytes);
+        if self.is_element(&element) { Ok(element) } else { Err(ZKPError::ElementOutOfRange) }
+    }
+
+    fn read_scalar(&self, bytes: &[u8]) -> Result<BigUint, ZKPError> {
+        let scalar = BigUint::from_bytes_be(bytes);
+        if self.is_scalar(&scalar) { Ok(scalar) } else { Err(ZKPError::ScalarOutOfRange) }
+    }
+
+    fn write_element(&self, out: &mut Vec<u8>, element: &BigUint) -> Result<(), ZKPError> {
+        if !self.is_element(element) {
+            return Err(ZKPError::ElementOutOfRange);
+        }
+        write_padded(out, element, self.element_byte_len());
+        Ok(())
+    }
+
+    fn write_scalar(&self, out: &mut Vec<u8>, scalar: &BigUint) -> Result<(), ZKPError> {
+        if !self.is_scalar(scalar) {
+            return Err(ZKPError::ScalarOutOfRange);
+        }
+        write_padded(out, scalar, self.scalar_byte_len());
+        Ok(())
+    }
+}
+
+// A whole proof in one self-describing blob, for clients that send a single opaque value:
+// version (1 byte) || fingerprint of the group (32) || element length (u16 BE) || scalar length
+// (u16 BE), then y1 || y2 || r1 || r2 || c || s in the fixed widths above
+pub const PROOF_BLOB_VERSION: u8 = 1;
+// domain tag of the Fiat-Shamir challenge of a blob's proof: c must be the one derived from the
+// blob's own y1, y2, r1, r2 (prove_non_interactive_in with an empty context), a c picked by the
+// caller lets anyone build r1, r2 for any s without knowing x
+pub const PROOF_BLOB_DOMAIN: &[u8] = b"zkp_auth/v1/blob";
+const PROOF_BLOB_HEADER_LEN: usize = 1 + 32 + 2 + 2;
+
+// the group a blob says it belongs to, to pick the ZKP that decodes it
+pub fn proof_blob_fingerprint(blob: &[u8]) -> Result<[u8; 32], ZKPError> {
+    if blob.len() < PROOF_BLOB_HEADER_LEN {
+        return Err(ZKPError::MalformedEncoding(format!(
+            "proof blob header needs {PROOF_BLOB_HEADER_LEN} bytes, got {}",
+            blob.len()
+        )));
+    }
+    if blob[0] != PROOF_BLOB_VERSION {
+        return Err(ZKPError::MalformedEncoding(format!("unknown proof blob version {}", blob[0])));
+    }
+    Ok(blob[1..33].try_into().expect("32 bytes"))
+}
+
+impl ZKP {
+    pub fn proof_blob(&self, commitment: &Commitment, proof: &Proof) -> Result<Vec<u8>, ZKPError> {
+        let (element, scalar) = (self.element_byte_len(), self.scalar_byte_len());
+        let lengths = (u16::try_from(element), u16::try_from(scalar));
+        let (Ok(element_len), Ok(scalar_len)) = lengths else {
+            return Err(ZKPError::Overflow(2));
+        };
+        let mut blob = Vec::with_capacity(PROOF_BLOB_HEADER_LEN + 4 * element + 2 * scalar);
+        blob.push(PROOF_BLOB_VERSION);
+        blob.extend_from_slice(&self.fingerprint());
+        blob.extend_from_slice(&element_len.to_be_bytes());
+        blob.extend_from_slice(&scalar_len.to_be_bytes());
+        blob.extend(self.commitment_to_bytes(commitment)?);
+        blob.extend(self.proof_to_bytes(proof)?);
+        Ok(blob)
+    }
+
+    // GroupMismatch for a blob of another group, MalformedEncoding for anything that is not a
+    // whole blob of this one; ranges are checked as in proof_from_bytes, not the subgroup
+    pub fn decode_proof_blob(&self, blob: &[u8]) -> Result<(Commitment, Proof), ZKPError> {
+        if proof_blob_fingerprint(blob)? != self.fingerprint() {
+            return Err(ZKPError::GroupMismatch);
+        }
+        let (element, scalar) = (self.element_byte_len(), self.scalar_byte_len());
+        let element_len = u16::from_be_bytes([blob[33], blob[34]]) as usize;
+        let scalar_len = u16::from_be_bytes([blob[35], blob[36]]) as usize;
+        if (element_len, scalar_len) != (element, scalar) {
+            return Err(ZKPError::MalformedEncoding(format!(
+                "proof blob field lengths {element_len}/{scalar_len}, the group needs \
+                 {element}/{scalar}"
+            )));
+        }
+        let body = &blob[PROOF_BLOB_HEADER_LEN..];
+        self.check_len(body, 4 * element + 2 * scalar)?;
+        let (commitment, proof) = body.split_at(2 * element);
+        Ok((self.commitment_from_bytes(commitment)?, self.proof_from_bytes(proof)?))
+    }
+}
+
+// value is known to fit in width bytes (it is below p or q)
+fn write_padded(out: &mut Vec<u8>, value: &BigUint, width: usize) {
+    let bytes = value.to_bytes_be();
+    out.resize(out.len() + width - bytes.len(), 0);
+    out.extend_from_slice(&bytes);
+}
+
+// The blobs of the default group (ZKP::default()), for any other group go through
+// ZKP::commitment_from_bytes / proof_from_bytes
+impl TryFrom<&[u8]> for Commitment {
+    type Error = ZKPError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, ZKPError> {
+        ZKP::default().commitment_from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Proof {
+    type Error = ZKPError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, ZKPError> {
+        ZKP::default().proof_from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip<const N: usize>() {
+        let small: [u8; N] = to_fixed_bytes(&BigUint::from(0x0102u32)).unwrap();
+        assert_eq!(small[N - 2..], [1, 2]);
+        assert!(small[..N - 2].iter().all(|byte| *byte == 0));
+
+        let max = (BigUint::from(1u32) << (8 * N)) - 1u32;
+        for value in [BigUint::from(0u32), BigUint::from(0x0102u32), max.clone()] {
+            let bytes: [u8; N] = to_fixed_bytes(&value).unwrap();
+            assert_eq!(from_fixed_bytes(&bytes), value);
+        }
+        assert_eq!(to_fixed_bytes::<N>(&max), Ok([0xFF; N]));
+        // one bit too many
+        assert_eq!(to_fixed_bytes::<N>(&(max + 1u32)), Err(ZKPError::Overflow(N)));
+    }
+
+    #[test]
+    fn test_fixed_bytes_20() {
+        round_trip::<20>();
+    }
+
+    #[test]
+    fn test_fixed_bytes_28() {
+        round_trip::<28>();
+    }
+
+    #[test]
+    fn test_fixed_bytes_32() {
+        round_trip::<32>();
+    }
+
+    #[test]
+    fn test_scalar_arrays() {
+        // q = 11: 1 byte is enough, but only below q
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        assert_eq!(zkp.scalar_to_array::<20>(&10u32.into()).unwrap()[19], 10);
+        assert_eq!(zkp.scalar_to_array::<20>(&11u32.into()), Err(ZKPError::ScalarOutOfRange));
+
+        let mut bytes = [0u8; 32];
+        bytes[31] = 7;
+        assert_eq!(zkp.scalar_from_array(&bytes), Ok(7u32.into()));
+        bytes[31] = 11;
+        assert_eq!(zkp.scalar_from_array(&bytes), Err(ZKPError::ScalarOutOfRange));
+    }
+
+    #[test]
+    fn test_commitment_and_proof_blobs() {
+        // toy run x = 6, k = 7, c = 4: one byte per element (p = 23) and per scalar (q = 11)
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        let proof = Proof::new(8u32.into(), 4u32.into(), 4u32.into(), 5u32.into());
+        assert_eq!(toy.commitment_to_bytes(&commitment), Ok(vec![2, 3]));
+        assert_eq!(toy.proof_to_bytes(&proof), Ok(vec![8, 4, 4, 5]));
+        assert_eq!(toy.commitment_from_bytes(&[2, 3]), Ok(commitment));
+        assert_eq!(toy.proof_from_bytes(&[8, 4, 4, 5]), Ok(proof.clone()));
+
+        // wrong lengths, an element 0 or >= p, a scalar >= q
+        assert!(matches!(toy.proof_from_bytes(&[8, 4, 4]), Err(ZKPError::MalformedEncoding(_))));
+        assert!(matches!(toy.commitment_from_bytes(&[]), Err(ZKPError::MalformedEncoding(_))));
+        assert_eq!(toy.commitment_from_bytes(&[0, 3]), Err(ZKPError::ElementOutOfRange));
+        assert_eq!(toy.proof_from_bytes(&[8, 23, 4, 5]), Err(ZKPError::ElementOutOfRange));
+        assert_eq!(toy.proof_from_bytes(&[8, 4, 11, 5]), Err(ZKPError::ScalarOutOfRange));
+        let bad = Proof::new(8u32.into(), 4u32.into(), 4u32.into(), 11u32.into());
+        assert_eq!(toy.proof_to_bytes(&bad), Err(ZKPError::ScalarOutOfRange));
+
+        // TryFrom is the default group: small values are padded to the full widths
+        let zkp = ZKP::default();
+        let bytes = zkp.proof_to_bytes(&proof).unwrap();
+        assert_eq!(bytes.len(), 2 * zkp.element_byte_len() + 2 * zkp.scalar_byte_len());
+        assert_eq!(Proof::try_from(&bytes[..]), Ok(proof));
+        assert!(Proof::try_from(&bytes[1..]).is_err());
+        let bytes = zkp.commitment_to_bytes(&Commitment::new(2u32.into(), 3u32.into())).unwrap();
+        assert_eq!(bytes.len(), 2 * zkp.element_byte_len());
+        assert_eq!(Commitment::try_from(&bytes[..]), Ok(Commitment::new(2u32.into(), 3u32.into())));
+        let all_ones = vec![0xFF; 2 * zkp.element_byte_len()];
+        assert_eq!(Commitment::try_from(&all_ones[..]), Err(ZKPError::ElementOutOfRange));
+    }
+
+    #[test]
+    fn test_proof_blob() {
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        let proof = Proof::new(8u32.into(), 4u32.into(), 4u32.into(), 5u32.into());
+        let blob = toy.proof_blob(&commitment, &proof).unwrap();
+        assert_eq!(blob[0], PROOF_BLOB_VERSION);
+        assert_eq!(blob[1..33], toy.fingerprint());
+        assert_eq!(blob[33..], [0, 1, 0, 1, 2, 3, 8, 4, 4, 5]);
+        assert_eq!(proof_blob_fingerprint(&blob), Ok(toy.fingerprint()));
+        assert_eq!(toy.decode_proof_blob(&blob), Ok((commitment.clone(), proof.clone())));
+
+        // a blob of the toy group is not one of the default group
+        assert_eq!(ZKP::default().decode_proof_blob(&blob), Err(ZKPError::GroupMismatch));
+
+        // cut anywhere, in the header or the fields
+        for len in [0, 1, 36, 37, blob.len() - 1] {
+            let result = toy.decode_proof_blob(&blob[..len]);
+            assert!(matches!(result, Err(ZKPError::MalformedEncoding(_))), "{len}");
+        }
+        // another version, or field lengths that are not the group's
+        let mut other = blob.clone();
+        other[0] = 2;
+        assert!(matches!(toy.decode_proof_blob(&other), Err(ZKPError::MalformedEncoding(_))));
+        let mut other = blob.clone();
+        other[34] = 2;
+        assert!(matches!(toy.decode_proof_blob(&other), Err(ZKPError::MalformedEncoding(_))));
+
+        // full widths in the default group
+        let zkp = ZKP::default();
+        let blob = zkp.proof_blob(&commitment, &proof).unwrap();
+        assert_eq!(blob.len(), 37 + 4 * zkp.element_byte_len() + 2 * zkp.scalar_byte_len());
+        assert_eq!(zkp.decode_proof_blob(&blob), Ok((commitment, proof)));
+    }
+}
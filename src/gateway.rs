@@ -0,0 +1,165 @@
+// HTTP+JSON front of the Auth service for clients that cannot speak gRPC (browsers)
+// every big number travels as a hex string, the handlers forward to the same AuthImpl as gRPC
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use tonic::{Code, Request, Status};
+
+use crate::AuthImpl;
+use zkp_chaum_pedersen::zkp_auth::{
+    AuthenticationAnswerRequest, AuthenticationChallengeRequest, RegisterRequest, auth_server::Auth,
+};
+
+#[derive(Deserialize)]
+pub struct RegisterBody {
+    pub user: String,
+    pub y1: String,
+    pub y2: String,
+    // hex fingerprint of the group, empty or missing for the default group
+    #[serde(default)]
+    pub group_id: String,
+    // registration proof of work, see ParametersResponse.registration_pow_bits
+    #[serde(default)]
+    pub pow_nonce: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeBody {
+    pub user: String,
+    pub r1: String,
+    pub r2: String,
+    // hex, optional (see the binding of AuthenticationChallengeRequest)
+    #[serde(default)]
+    pub binding: String,
+    // hex, only for a server storing commitments hashed (see ZKP_HASHED_COMMITMENTS)
+    #[serde(default)]
+    pub y1: String,
+    #[serde(default)]
+    pub y2: String,
+}
+
+#[derive(Serialize)]
+pub struct ChallengeReply {
+    pub auth_id: String,
+    pub c: String,
+    pub group_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyBody {
+    pub auth_id: String,
+    pub s: String,
+    #[serde(default)]
+    pub binding: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyReply {
+    pub session_id: String,
+}
+
+#[derive(Serialize)]
+pub struct ErrorReply {
+    pub error: String,
+}
+
+// a gRPC error as an HTTP status and a {"error": message} body
+pub struct GatewayError(Status);
+
+impl From<Status> for GatewayError {
+    fn from(status: Status) -> Self {
+        GatewayError(status)
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let status = match self.0.code() {
+            Code::InvalidArgument => StatusCode::BAD_REQUEST,
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::DeadlineExceeded => StatusCode::GONE,
+            Code::AlreadyExists | Code::FailedPrecondition => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(ErrorReply { error: self.0.message().to_string() })).into_response()
+    }
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, GatewayError> {
+    hex::decode(value).map_err(|_| {
+        GatewayError(Status::new(Code::InvalidArgument, format!("{field} is not valid hex")))
+    })
+}
+
+async fn register(
+    State(auth): State<Arc<AuthImpl>>,
+    Json(body): Json<RegisterBody>,
+) -> Result<StatusCode, GatewayError> {
+    let request = RegisterRequest {
+        user: body.user,
+        y1: decode_hex("y1", &body.y1)?,
+        y2: decode_hex("y2", &body.y2)?,
+        group_id: decode_hex("group_id", &body.group_id)?,
+        pow_nonce: body.pow_nonce,
+    };
+    auth.register(Request::new(request)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn challenge(
+    State(auth): State<Arc<AuthImpl>>,
+    Json(body): Json<ChallengeBody>,
+) -> Result<Json<ChallengeReply>, GatewayError> {
+    let request = AuthenticationChallengeRequest {
+        user: body.user,
+        r1: decode_hex("r1", &body.r1)?,
+        r2: decode_hex("r2", &body.r2)?,
+        binding: decode_hex("binding", &body.binding)?,
+        y1: decode_hex("y1", &body.y1)?,
+        y2: decode_hex("y2", &body.y2)?,
+    };
+    let response = auth.create_authentication_challenge(Request::new(request)).await?.into_inner();
+
+    Ok(Json(ChallengeReply {
+        auth_id: response.auth_id,
+        c: hex::encode_upper(response.c),
+        group_id: hex::encode_upper(response.group_id),
+    }))
+}
+
+async fn verify(
+    State(auth): State<Arc<AuthImpl>>,
+    Json(body): Json<VerifyBody>,
+) -> Result<Json<VerifyReply>, GatewayError> {
+    let request = AuthenticationAnswerRequest {
+        auth_id: body.auth_id,
+        s: decode_hex("s", &body.s)?,
+        binding: decode_hex("binding", &body.binding)?,
+    };
+    let response = auth.verify_authentication(Request::new(request)).await?.into_inner();
+
+    Ok(Json(VerifyReply { session_id: response.session_id }))
+}
+
+pub fn router(auth: Arc<AuthImpl>) -> Router {
+    Router::new()
+        .route("/register", post(register))
+        .route("/challenge", post(challenge))
+        .route("/verify", post(verify))
+        .with_state(auth)
+}
+
+pub async fn serve(addr: SocketAddr, auth: Arc<AuthImpl>) {
+    println!("✅ Running the HTTP gateway in {addr}");
+
+    axum::Server::bind(&addr).serve(router(auth).into_make_service()).await.unwrap();
+}
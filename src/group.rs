@@ -0,0 +1,114 @@
+use num_bigint::BigUint;
+
+use crate::{ZKP, ZKPError};
+
+// How the elements and scalars of a group travel on the wire.
+// The mod-p group of ZKP sends both as big-endian integers, an elliptic-curve group would send
+// compressed points and fixed-size scalars: the gRPC handlers only go through these methods,
+// so they do not depend on which one the server runs
+pub trait GroupEncoding {
+    type Element;
+    type Scalar;
+
+    fn encode_element(&self, element: &Self::Element) -> Vec<u8>;
+    fn decode_element(&self, bytes: &[u8]) -> Result<Self::Element, ZKPError>;
+    fn encode_scalar(&self, scalar: &Self::Scalar) -> Vec<u8>;
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Self::Scalar, ZKPError>;
+}
+
+// any byte string is some integer, so decoding cannot fail here:
+// whether it is in range (is_in_subgroup, is_scalar) is for the protocol to check
+impl GroupEncoding for ZKP {
+    type Element = BigUint;
+    type Scalar = BigUint;
+
+    fn encode_element(&self, element: &BigUint) -> Vec<u8> {
+        element.to_bytes_be()
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<BigUint, ZKPError> {
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+
+    fn encode_scalar(&self, scalar: &BigUint) -> Vec<u8> {
+        scalar.to_bytes_be()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<BigUint, ZKPError> {
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // stand-in for an EC backend: a point is its x coordinate and the parity of y,
+    // encoded SEC1-style as 0x02/0x03 || x, scalars are fixed 8-byte big-endian
+    struct StubCurve;
+
+    #[derive(Debug, PartialEq)]
+    struct StubPoint {
+        x: u64,
+        y_is_odd: bool,
+    }
+
+    impl GroupEncoding for StubCurve {
+        type Element = StubPoint;
+        type Scalar = u64;
+
+        fn encode_element(&self, point: &StubPoint) -> Vec<u8> {
+            let mut bytes = vec![if point.y_is_odd { 0x03 } else { 0x02 }];
+            bytes.extend_from_slice(&point.x.to_be_bytes());
+            bytes
+        }
+
+        fn decode_element(&self, bytes: &[u8]) -> Result<StubPoint, ZKPError> {
+            let (tag, x) =
+                bytes.split_first().ok_or(ZKPError::MalformedEncoding("empty".into()))?;
+            let x = x.try_into().map_err(|_| ZKPError::MalformedEncoding("x length".into()))?;
+            match tag {
+                0x02 | 0x03 => Ok(StubPoint { x: u64::from_be_bytes(x), y_is_odd: *tag == 0x03 }),
+                _ => Err(ZKPError::MalformedEncoding("not a compressed point".into())),
+            }
+        }
+
+        fn encode_scalar(&self, scalar: &u64) -> Vec<u8> {
+            scalar.to_be_bytes().to_vec()
+        }
+
+        fn decode_scalar(&self, bytes: &[u8]) -> Result<u64, ZKPError> {
+            let bytes = bytes.try_into().map_err(|_| ZKPError::MalformedEncoding("len".into()))?;
+            Ok(u64::from_be_bytes(bytes))
+        }
+    }
+
+    #[test]
+    fn test_mod_p_encoding() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha.clone(), beta, p, q);
+
+        assert_eq!(zkp.encode_element(&alpha), alpha.to_bytes_be());
+        assert_eq!(zkp.decode_element(&zkp.encode_element(&alpha)), Ok(alpha));
+        assert_eq!(zkp.decode_scalar(&zkp.encode_scalar(&BigUint::from(7u32))), Ok(7u32.into()));
+        // leading zeros do not change the integer
+        assert_eq!(zkp.decode_scalar(&[0, 0, 7]), Ok(7u32.into()));
+    }
+
+    #[test]
+    fn test_stub_ec_encoding() {
+        let curve = StubCurve;
+        let point = StubPoint { x: 42, y_is_odd: true };
+
+        let bytes = curve.encode_element(&point);
+        assert_eq!(bytes.len(), 9);
+        assert_eq!(bytes[0], 0x03);
+        assert_eq!(curve.decode_element(&bytes), Ok(point));
+        assert_eq!(curve.decode_scalar(&curve.encode_scalar(&5)), Ok(5));
+
+        // unlike mod p, some byte strings are not group elements at all
+        assert!(curve.decode_element(&[0x04; 9]).is_err());
+        assert!(curve.decode_element(&[0x02, 1]).is_err());
+        assert!(curve.decode_scalar(&[1, 2, 3]).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use num_bigint::BigUint;
+
+use crate::{ZKP, ZKPError};
+
+// Scalars of this crate to and from the big integers of other crypto crates, through big-endian
+// bytes: crypto-bigint's fixed-size Uint (feature "crypto-bigint") and num-bigint-dig's BigUint
+// (feature "num-bigint-dig"). Both ways refuse a scalar that is not below q, like scalar_to_array
+
+#[cfg(feature = "crypto-bigint")]
+impl ZKP {
+    // a scalar of this group as a Uint, Overflow if q does not fit in LIMBS
+    pub fn scalar_to_uint<const LIMBS: usize>(
+        &self,
+        scalar: &BigUint,
+    ) -> Result<crypto_bigint::Uint<LIMBS>, ZKPError> {
+        if !self.is_scalar(scalar) {
+            return Err(ZKPError::ScalarOutOfRange);
+        }
+        let width = crypto_bigint::Uint::<LIMBS>::BYTES;
+        let bytes = scalar.to_bytes_be();
+        if bytes.len() > width {
+            return Err(ZKPError::Overflow(width));
+        }
+        // from_be_slice wants exactly BYTES bytes
+        let mut padded = vec![0u8; width - bytes.len()];
+        padded.extend_from_slice(&bytes);
+        Ok(crypto_bigint::Uint::from_be_slice(&padded))
+    }
+
+    pub fn scalar_from_uint<const LIMBS: usize>(
+        &self,
+        uint: &crypto_bigint::Uint<LIMBS>,
+    ) -> Result<BigUint, ZKPError> {
+        // the limbs are least significant first
+        let bytes: Vec<u8> =
+            uint.as_words().iter().rev().flat_map(|word| word.to_be_bytes()).collect();
+        let scalar = BigUint::from_bytes_be(&bytes);
+        if !self.is_scalar(&scalar) {
+            return Err(ZKPError::ScalarOutOfRange);
+        }
+        Ok(scalar)
+    }
+}
+
+#[cfg(feature = "num-bigint-dig")]
+impl ZKP {
+    pub fn scalar_to_dig(&self, scalar: &BigUint) -> Result<num_bigint_dig::BigUint, ZKPError> {
+        if !self.is_scalar(scalar) {
+            return Err(ZKPError::ScalarOutOfRange);
+        }
+        Ok(num_bigint_dig::BigUint::from_bytes_be(&scalar.to_bytes_be()))
+    }
+
+    pub fn scalar_from_dig(&self, dig: &num_bigint_dig::BigUint) -> Result<BigUint, ZKPError> {
+        let scalar = BigUint::from_bytes_be(&dig.to_bytes_be());
+        if !self.is_scalar(&scalar) {
+            return Err(ZKPError::ScalarOutOfRange);
+        }
+        Ok(scalar)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // x = 6 below q = 11 of the toy group, q itself is out of range
+    fn toy() -> ZKP {
+        ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into())
+    }
+
+    #[cfg(feature = "crypto-bigint")]
+    #[test]
+    fn test_crypto_bigint_round_trip() {
+        use crypto_bigint::{U64, U256};
+
+        let zkp = toy();
+        let uint: U64 = zkp.scalar_to_uint(&BigUint::from(6u32)).unwrap();
+        assert_eq!(uint, U64::from_u64(6));
+        assert_eq!(zkp.scalar_from_uint(&uint), Ok(BigUint::from(6u32)));
+        assert_eq!(
+            zkp.scalar_to_uint::<{ U64::LIMBS }>(&BigUint::from(11u32)),
+            Err(ZKPError::ScalarOutOfRange)
+        );
+        assert_eq!(zkp.scalar_from_uint(&U64::from_u64(11)), Err(ZKPError::ScalarOutOfRange));
+
+        // a 160-bit scalar of the 1024-bit group, and one that does not fit in 64 bits
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let scalar = &q - 1u32;
+        let uint: U256 = zkp.scalar_to_uint(&scalar).unwrap();
+        assert_eq!(zkp.scalar_from_uint(&uint), Ok(scalar.clone()));
+        assert_eq!(zkp.scalar_to_uint::<{ U64::LIMBS }>(&scalar), Err(ZKPError::Overflow(8)));
+    }
+
+    #[cfg(feature = "num-bigint-dig")]
+    #[test]
+    fn test_num_bigint_dig_round_trip() {
+        let zkp = toy();
+        let dig = zkp.scalar_to_dig(&BigUint::from(6u32)).unwrap();
+        assert_eq!(dig, num_bigint_dig::BigUint::from(6u32));
+        assert_eq!(zkp.scalar_from_dig(&dig), Ok(BigUint::from(6u32)));
+        assert_eq!(zkp.scalar_to_dig(&BigUint::from(11u32)), Err(ZKPError::ScalarOutOfRange));
+        assert_eq!(
+            zkp.scalar_from_dig(&num_bigint_dig::BigUint::from(11u32)),
+            Err(ZKPError::ScalarOutOfRange)
+        );
+
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let scalar = &q - 1u32;
+        let dig = zkp.scalar_to_dig(&scalar).unwrap();
+        assert_eq!(zkp.scalar_from_dig(&dig), Ok(scalar));
+    }
+}
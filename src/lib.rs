@@ -1,177 +1,2440 @@
 //use hex;
-use num_bigint::{BigUint, RandBigInt};
+use num_bigint::BigUint;
+#[cfg(feature = "prover")]
+use num_bigint::RandBigInt;
+#[cfg(feature = "prover")]
 use rand::Rng; // For random number generation
+use sha2::{Digest, Sha256};
+#[cfg(feature = "prover")]
+use std::time::Instant;
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+pub mod zkp_auth {
+    include!("./zkp_auth.rs"); // generated gRPC code, shared by the server, the client and AuthClient
+}
+
+// default-features = false, features = ["verifier-only"] leaves out everything that needs
+// randomness or a secret (feature "prover", on by default): see "Verifier-only Builds" in the README
+#[cfg(not(any(feature = "prover", feature = "verifier-only")))]
+compile_error!("enable the prover feature (default) or build with features = [\"verifier-only\"]");
+
+#[cfg(feature = "prover")]
+mod auth_client;
+#[cfg(feature = "prover")]
+pub use auth_client::{AuthClient, AuthClientBuilder, AuthTransport, ClientConfig, RetryPolicy};
+#[cfg(feature = "prover")]
+mod backend;
+#[cfg(feature = "prover")]
+pub use backend::{AuthBackend, ChaumPedersen};
+mod challenge;
+#[cfg(feature = "prover")]
+pub use challenge::RandomChallenge;
+pub use challenge::{ChallengeProvider, ChallengeTranscript, FiatShamirChallenge, FixedChallenge};
+mod cross_group;
+pub use cross_group::{CrossGroupZKP, GroupContext};
+mod fixed;
+pub use fixed::{
+    PROOF_BLOB_DOMAIN, PROOF_BLOB_VERSION, from_fixed_bytes, proof_blob_fingerprint, to_fixed_bytes,
+};
+mod group;
+pub use group::GroupEncoding;
+#[cfg(any(feature = "crypto-bigint", feature = "num-bigint-dig"))]
+mod interop;
+#[cfg(feature = "prover")]
+mod local;
+#[cfg(feature = "prover")]
+pub use local::LocalSession;
+#[cfg(feature = "prover")]
+mod pow;
+#[cfg(feature = "prover")]
+pub use pow::{check_registration_pow, registration_pow_hash, solve_registration_pow};
+mod proof;
+pub use proof::{ChallengeSource, Commitment, Proof, WithGroup};
+mod receipt;
+#[cfg(feature = "prover")]
+pub use receipt::ReceiptSigner;
+pub use receipt::{RECEIPT_DOMAIN, receipt_message};
+#[cfg(feature = "prover")]
+mod secret;
+#[cfg(feature = "prover")]
+pub use secret::Secret;
+mod streaming;
+pub use streaming::{StreamVerdict, StreamingVerifier, TranscriptMessage};
+#[cfg(feature = "audit")]
+mod transcript;
+#[cfg(feature = "audit")]
+pub use transcript::{AuditReport, Group, Transcript, audit_transcript};
+
+// Errors returned by the fallible parts of the library (decoding, validation)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZKPError {
+    // the bytes/text could not be decoded into the expected structure
+    MalformedEncoding(String),
+    // the value was produced for another group than the one it is checked against
+    GroupMismatch,
+    // a challenge received from the verifier is not < q
+    ChallengeOutOfRange,
+    // p is not a safe prime: p or q = (p-1)/2 is not (probably) prime
+    NotASafePrime,
+    // alpha or beta does not generate the order-q subgroup
+    GeneratorNotInSubgroup,
+    // the named values (e.g. "y1/y2") are not in the order-q subgroup
+    NotInSubgroup(&'static str),
+    // a value does not fit in the fixed number of bytes it is encoded into
+    Overflow(usize),
+    // a scalar (c, s, ...) is not below q
+    ScalarOutOfRange,
+    // k, c or x handed to solve is not below q, refused before any multiplication
+    ValueOutOfRange,
+    // a group element (y1, r1, ...) is not in [1, p)
+    ElementOutOfRange,
+    // the commitment gives x away (x = 0 or x = 1), see ZKP::check_commitment_strength
+    WeakCommitment,
+    // the verifier kept drawing c = 0, a challenge that proves nothing
+    ZeroChallenge,
+    // p, q do not make a group to work in (a prime q dividing p - 1, p prime), with the reason
+    InvalidGroupParameters(&'static str),
+    // an id that must not be guessed was asked for with fewer than MIN_ID_LENGTH characters
+    IdTooShort(usize),
+}
+
+impl fmt::Display for ZKPError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZKPError::MalformedEncoding(reason) => write!(f, "malformed encoding: {reason}"),
+            ZKPError::GroupMismatch => write!(f, "value belongs to a different group"),
+            ZKPError::ChallengeOutOfRange => write!(f, "challenge is not below q"),
+            ZKPError::NotASafePrime => write!(f, "p is not a safe prime (p = 2q + 1, q prime)"),
+            ZKPError::GeneratorNotInSubgroup => {
+                write!(f, "alpha/beta do not generate the order-q subgroup")
+            }
+            ZKPError::NotInSubgroup(names) => write!(f, "{names} are not in the order-q subgroup"),
+            ZKPError::Overflow(width) => write!(f, "value does not fit in {width} bytes"),
+            ZKPError::ScalarOutOfRange => write!(f, "scalar is not below q"),
+            ZKPError::ValueOutOfRange => write!(f, "k, c or x is not below q"),
+            ZKPError::ElementOutOfRange => write!(f, "group element is not in [1, p)"),
+            ZKPError::WeakCommitment => write!(f, "y1/y2 come from a trivial secret (x = 0 or 1)"),
+            ZKPError::ZeroChallenge => write!(f, "no nonzero challenge could be drawn"),
+            ZKPError::InvalidGroupParameters(reason) => {
+                write!(f, "invalid group parameters: {reason}")
+            }
+            ZKPError::IdTooShort(length) => {
+                write!(f, "an id of {length} characters is below the minimum of {MIN_ID_LENGTH}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ZKPError {}
+
+// Result of ZKP::verify_detailed
+#[must_use = "a failed verification is only caught by checking the result"]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Valid,
+    // r1 != alpha^s * y1^c mod p
+    R1Mismatch,
+    // r2 != beta^s * y2^c mod p
+    R2Mismatch,
+    // c is not below q
+    ChallengeOutOfRange,
+    // s is not below q
+    ResponseOutOfRange,
+}
+
+impl VerifyOutcome {
+    pub fn is_valid(self) -> bool {
+        self == VerifyOutcome::Valid
+    }
+
+    //stable identifier for API responses (the server sends it as the details of a wrong
+    //answer's status): clients can match on it, these strings never change
+    pub fn as_code(&self) -> &'static str {
+        match self {
+            VerifyOutcome::Valid => "ok",
+            VerifyOutcome::R1Mismatch => "cond1_failed",
+            VerifyOutcome::R2Mismatch => "cond2_failed",
+            VerifyOutcome::ChallengeOutOfRange => "challenge_out_of_range",
+            VerifyOutcome::ResponseOutOfRange => "response_out_of_range",
+        }
+    }
+
+    //whether a client should start the login over: only when the challenge was at fault, a
+    //wrong answer stays wrong with the same secret (AnswerFailure.retryable of the server)
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, VerifyOutcome::ChallengeOutOfRange)
+    }
+}
+
+// Rules a verifier enforces, published to clients alongside the group parameters
+// so they know how fast to answer a challenge, when to authenticate again
+// and how much work a registration costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolPolicy {
+    // time allowed between issuing a challenge and receiving its answer
+    pub challenge_ttl: Duration,
+    // lifetime of a session minted by a successful authentication
+    pub session_ttl: Duration,
+    // leading zero bits of the registration proof of work (see check_registration_pow), 0 = none
+    pub registration_pow_bits: u32,
+    // how long the outcome of an answer is kept, so a client retrying the very same answer
+    // (same auth_id and s) gets the same result back instead of a replay error
+    pub answer_retry_ttl: Duration,
+    // how far apart the clocks of a client and the server may be: a timestamp (stateless
+    // challenge, SubmitProof) is fresh within [now - ttl - skew, now + skew]
+    pub clock_skew: Duration,
+}
+
+impl Default for ProtocolPolicy {
+    fn default() -> Self {
+        ProtocolPolicy {
+            challenge_ttl: Duration::from_secs(60),
+            session_ttl: Duration::from_secs(3600),
+            registration_pow_bits: 0,
+            answer_retry_ttl: Duration::from_secs(5),
+            clock_skew: Duration::from_secs(5),
+        }
+    }
+}
+
+// Crockford's base32: digits and upper-case letters without I, L, O and U,
+// so no 0/O or 1/I/l look-alikes in ids people read or type
+pub const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// shortest id generate_secure_id gives out: 16 alphanumeric characters are about 95 bits
+pub const MIN_ID_LENGTH: usize = 16;
+
+// the parameters are immutable and shared: cloning a ZKP (to hand it to another task, or out of
+// DEFAULT_GROUP) bumps reference counts instead of copying the moduli
+#[derive(Debug, Clone)]
 pub struct ZKP {
-    p: BigUint,
-    q: BigUint,
-    alpha: BigUint,
-    beta: BigUint,
+    p: Arc<BigUint>,
+    q: Arc<BigUint>,
+    alpha: Arc<BigUint>,
+    beta: Arc<BigUint>,
+    // beta = alpha^ZKP::beta_exponent(), checked on the first beta_is_derived (a modpow every
+    // ZKP::new would be wasted on the groups nobody asks about) and shared by the clones
+    beta_derived: Arc<OnceLock<bool>>,
+}
+
+//fingerprint of the group ZKP::default() returns, chosen when the crate is compiled:
+//the RFC 5114 1024-bit group, or the 2048-bit one with the group-rfc5114-2048 feature
+#[cfg(not(feature = "group-rfc5114-2048"))]
+pub const DEFAULT_GROUP_FINGERPRINT: &str =
+    "1D803DBF19B14EAC182C1DDBC90E1E2C59607DD1B7631FD4A8387C4D93C57264";
+#[cfg(feature = "group-rfc5114-2048")]
+pub const DEFAULT_GROUP_FINGERPRINT: &str =
+    "585EEE9196C00910FBE5E8145AA6E2A1CE37B3DCBF0225EA5EF6246CD76A6594";
+
+//decoded on the first ZKP::default() and cloned from then on
+static DEFAULT_GROUP: OnceLock<ZKP> = OnceLock::new();
+
+//the reference group, instead of ZKP::new over ZKP::get_constants: the RFC 5114 1024-bit group
+//(get_constants, beta = alpha^beta_exponent), the 2048-bit one with group-rfc5114-2048
+impl Default for ZKP {
+    fn default() -> Self {
+        DEFAULT_GROUP
+            .get_or_init(|| {
+                let (alpha, beta, p, q) = ZKP::default_constants();
+                ZKP::new(alpha, beta, p, q)
+            })
+            .clone()
+    }
+}
+
+//output = base^exp mod modulus
+//every exponentiation of the protocol goes through here, so the arithmetic backend can be
+//swapped in one place: num-bigint by default, GMP (via rug) with the "rug" feature
+#[cfg(not(feature = "rug"))]
+pub fn powm(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+    base.modpow(exp, modulus)
+}
+
+#[cfg(feature = "rug")]
+pub fn powm(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+    use rug::{Integer, integer::Order};
+
+    let base = Integer::from_digits(&base.to_bytes_be(), Order::Msf);
+    let exp = Integer::from_digits(&exp.to_bytes_be(), Order::Msf);
+    let modulus = Integer::from_digits(&modulus.to_bytes_be(), Order::Msf);
+
+    // exp is never negative here, so pow_mod cannot fail
+    let result = base.pow_mod(&exp, &modulus).expect("non-negative exponent");
+    BigUint::from_bytes_be(&result.to_digits::<u8>(Order::Msf))
+}
+
+//5 bits per character, most significant first, the last one padded with zero bits
+fn crockford_base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(CROCKFORD_ALPHABET[usize::from((buffer >> bits) & 31)] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(CROCKFORD_ALPHABET[usize::from((buffer << (5 - bits)) & 31)] as char);
+    }
+    out
+}
+
+//(y1, y2) = (alpha^x mod p, beta^x mod p), the public half of the secret x
+//needs no q or ZKP, for key-generation tools and registering offline
+pub fn public_commitment(
+    alpha: &BigUint,
+    beta: &BigUint,
+    p: &BigUint,
+    x: &BigUint,
+) -> (BigUint, BigUint) {
+    (powm(alpha, x, p), powm(beta, x, p))
+}
+
+// Proof that the prover knows the secret behind one of N public pairs (see ZKP::prove_or)
+// branch i holds the commitment (r1_i, r2_i), its share of the challenge c_i and its response s_i
+#[derive(Debug, Clone)]
+pub struct OrProof {
+    pub commitments: Vec<(BigUint, BigUint)>,
+    pub challenges: Vec<BigUint>,
+    pub responses: Vec<BigUint>,
+}
+
+// Proof of knowledge of an opening (x, r) of a Pedersen commitment (see ZKP::prove_pedersen_opening)
+// t = alpha^k1 * beta^k2 mod p is the prover's commitment, s1 and s2 answer the challenge for x and r
+#[derive(Debug, Clone)]
+pub struct PedersenProof {
+    pub t: BigUint,
+    pub s1: BigUint,
+    pub s2: BigUint,
 }
 
-impl ZKP {
-    //output = n^exp mod p
+impl ZKP {
+    //output = n^exp mod p
+
+    pub fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
+        let [y1, y2]: [BigUint; 2] =
+            self.compute_commitments(&[&*self.alpha, &*self.beta], exp).try_into().unwrap();
+        (y1, y2)
+    }
+
+    //output = [base^exp mod p] for every base, in order
+    //compute_pair is the [alpha, beta] case, more generators give multi-generator commitments
+    pub fn compute_commitments(&self, bases: &[&BigUint], exp: &BigUint) -> Vec<BigUint> {
+        bases.iter().map(|base| powm(base, exp, &self.p)).collect()
+    }
+
+    //output = s = k - c*x mod q
+    //k is the prover's random number, c is the challenge, x is the secret, q is the modulus
+    //returns s as BigUint, in a Result: solve used to return the BigUint itself, callers from
+    //before that add ? (or .unwrap() where the inputs are known to be below q)
+
+    //k, c and x must be below q, anything else is refused with ValueOutOfRange before any
+    //arithmetic is done: a cheap guard for a server solving on a client's behalf
+    #[cfg(feature = "prover")]
+    pub fn solve(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> Result<BigUint, ZKPError> {
+        if ![k, c, x].into_iter().all(|value| self.is_scalar(value)) {
+            return Err(ZKPError::ValueOutOfRange);
+        }
+        Ok(self.respond(k, c, x))
+    }
+
+    //solve for the crate's own provers, which never fail: k, c and x are reduced mod q first
+    //(an x given to prove_non_interactive... may be as wide as the password it was read from,
+    //alpha^x only depends on x mod q)
+    #[cfg(feature = "prover")]
+    fn respond(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
+        let q = &*self.q;
+        let cx_mod_q = (c % q) * (x % q) % q;
+
+        // q + k - cx is in (0, 2q), one % brings it into [0, q). A plain % rather than
+        // powm(v, 1, q), which sets up a whole exponentiation for the same value: from about
+        // 70 times slower (160-bit q) to over 1000 (2048-bit), see benches/solve.rs
+        (q + k % q - cx_mod_q) % q
+    }
+
+    //cond1: r1 =alpha^s *y1^c  mod p
+    //cond2: r2 =beta^s *y2^c  mod p
+    //returns true if both conditions are satisfied
+    //r1, r2, y1, y2, alpha, beta, c, s, p are BigUint
+    //p is the modulus, c is the challenge, s is the response, alpha and beta are the public keys, y1 and y2 are the commitments
+    //r1 and r2 are the responses to be verified
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+
+        c: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        // cheap rejection of values no honest prover sends, before the four modpows
+        if !self.is_scalar(c) || !self.is_scalar(s) || r1 == &BigUint::ZERO || r2 == &BigUint::ZERO
+        {
+            return false;
+        }
+
+        let cond1: bool = *r1
+            == ((powm(&self.alpha, s, &self.p)) % &*self.p * (powm(y1, c, &self.p)) % &*self.p)
+                % &*self.p;
+
+        let cond2: bool = *r2
+            == ((powm(&self.beta, s, &self.p)) % &*self.p * (powm(y2, c, &self.p)) % &*self.p)
+                % &*self.p;
+
+        cond1 && cond2
+    }
+
+    //(y1^c mod p, y2^c mod p): half of the work of verify, and it only depends on the commitment
+    //and the challenge, so a verifier can compute it as soon as c is issued
+    pub fn challenge_terms(&self, y1: &BigUint, y2: &BigUint, c: &BigUint) -> (BigUint, BigUint) {
+        (powm(y1, c, &self.p), powm(y2, c, &self.p))
+    }
+
+    //verify with the y1^c, y2^c of challenge_terms: only alpha^s and beta^s are left to compute
+    //(c is not checked, it was the verifier's own when the terms were computed)
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_precomputed(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        s: &BigUint,
+        terms: &(BigUint, BigUint),
+    ) -> bool {
+        self.verify_precomputed_detailed(r1, r2, s, terms).is_valid()
+    }
+
+    //the same, telling which check failed (see verify_detailed)
+    pub fn verify_precomputed_detailed(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        s: &BigUint,
+        (y1_c, y2_c): &(BigUint, BigUint),
+    ) -> VerifyOutcome {
+        if !self.is_scalar(s) {
+            VerifyOutcome::ResponseOutOfRange
+        } else if r1 == &BigUint::ZERO || *r1 != powm(&self.alpha, s, &self.p) * y1_c % &*self.p {
+            VerifyOutcome::R1Mismatch
+        } else if r2 == &BigUint::ZERO || *r2 != powm(&self.beta, s, &self.p) * y2_c % &*self.p {
+            VerifyOutcome::R2Mismatch
+        } else {
+            VerifyOutcome::Valid
+        }
+    }
+
+    //cut-and-choose: the verifier committed to several challenges and the prover answered one,
+    //returns the index of the first allowed c that s answers (None if none does).
+    //alpha^s and beta^s are computed once, each candidate then costs y1^c and y2^c
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_against_challenges(
+        &self,
+        commitment: &Commitment,
+        r1: &BigUint,
+        r2: &BigUint,
+        s: &BigUint,
+        allowed: &[BigUint],
+    ) -> Option<usize> {
+        if !self.is_scalar(s) || r1 == &BigUint::ZERO || r2 == &BigUint::ZERO {
+            return None;
+        }
+
+        let (alpha_s, beta_s) = self.compute_pair(s);
+        allowed.iter().position(|c| {
+            self.is_scalar(c) && {
+                let (y1_c, y2_c) = self.challenge_terms(&commitment.y1, &commitment.y2, c);
+                *r1 == &alpha_s * y1_c % &*self.p && *r2 == &beta_s * y2_c % &*self.p
+            }
+        })
+    }
+
+    //same checks as verify, but tells which of the two equations failed
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_detailed(
+        &self,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> VerifyOutcome {
+        if !self.is_scalar(c) {
+            VerifyOutcome::ChallengeOutOfRange
+        } else if !self.is_scalar(s) {
+            VerifyOutcome::ResponseOutOfRange
+        } else if r1 == &BigUint::ZERO
+            || *r1 != powm(&self.alpha, s, &self.p) * powm(y1, c, &self.p) % &*self.p
+        {
+            VerifyOutcome::R1Mismatch
+        } else if r2 == &BigUint::ZERO
+            || *r2 != powm(&self.beta, s, &self.p) * powm(y2, c, &self.p) % &*self.p
+        {
+            VerifyOutcome::R2Mismatch
+        } else {
+            VerifyOutcome::Valid
+        }
+    }
+
+    //verify under caller-supplied generators instead of alpha, beta (same p and q), for provers
+    //still on another generator pair during a migration, e.g. alpha' = alpha^t, beta' = beta^t;
+    //both must be of order q, otherwise GeneratorNotInSubgroup
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_with_generator(
+        &self,
+        alpha_override: &BigUint,
+        beta_override: &BigUint,
+        r1: &BigUint,
+        r2: &BigUint,
+        y1: &BigUint,
+        y2: &BigUint,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> Result<bool, ZKPError> {
+        let one = BigUint::from(1u32);
+        for generator in [alpha_override, beta_override] {
+            if *generator == one || !self.is_in_subgroup(generator) {
+                return Err(ZKPError::GeneratorNotInSubgroup);
+            }
+        }
+
+        let zkp = ZKP::from_shared(
+            Arc::new(alpha_override.clone()),
+            Arc::new(beta_override.clone()),
+            self.p.clone(),
+            self.q.clone(),
+        );
+        Ok(zkp.verify(r1, r2, y1, y2, c, s))
+    }
+
+    //the server never sees x, but some x show in the commitment: y = 1 is x = 0 (mod q) and
+    //y1 = alpha, y2 = beta is x = 1, their discrete log is known to anyone
+    pub fn check_commitment_strength(&self, y1: &BigUint, y2: &BigUint) -> Result<(), ZKPError> {
+        let one = BigUint::from(1u32);
+        if *y1 == one || *y2 == one || *y1 == *self.alpha || *y2 == *self.beta {
+            return Err(ZKPError::WeakCommitment);
+        }
+        Ok(())
+    }
+
+    //true if element is in the order-q subgroup: 0 < element < p and element^q mod p == 1
+    //values received from the network must pass this before being exponentiated, otherwise an
+    //element of small order (like p - 1, of order 2) opens the door to small-subgroup attacks
+    pub fn is_in_subgroup(&self, element: &BigUint) -> bool {
+        *element > BigUint::from(0u32)
+            && *element < *self.p
+            && powm(element, &self.q, &self.p) == BigUint::from(1u32)
+    }
+
+    //output = [(alpha^x_i mod p, beta^x_i mod p)] for every secret x_i in exps
+    //used to register several secrets at once (one commitment pair per secret)
+    #[cfg(feature = "prover")]
+    pub fn compute_pairs(&self, exps: &[BigUint]) -> Vec<(BigUint, BigUint)> {
+        exps.iter().map(|exp| self.compute_pair(exp)).collect()
+    }
+
+    //batched AND-composition: proves knowledge of every x_i behind (y1_i, y2_i) at once
+    //all the proofs share the single challenge c, so one round trip covers every secret
+    //this is NOT a general circuit: it only proves "I know all of them", nothing else
+    //rs, ys and ss must have the same (non-zero) length, the i-th entries belong together
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_many(
+        &self,
+        rs: &[(BigUint, BigUint)],
+        ys: &[(BigUint, BigUint)],
+        c: &BigUint,
+        ss: &[BigUint],
+    ) -> bool {
+        if rs.is_empty() || rs.len() != ys.len() || rs.len() != ss.len() {
+            return false;
+        }
+
+        rs.iter().zip(ys).zip(ss).all(|(((r1, r2), (y1, y2)), s)| self.verify(r1, r2, y1, y2, c, s))
+    }
+
+    //OR-composition: proves "I know the x behind ONE of the pairs in ys" without telling which
+    //secrets has the same length as ys and exactly one slot is Some(x), the others are simulated
+    //challenge receives the commitments (r1_i, r2_i) and returns the verifier's challenge c,
+    //so the commitments are fixed before c is known, exactly like in the interactive protocol
+    //panics if secrets and ys differ in length or if there is not exactly one Some
+    #[cfg(feature = "prover")]
+    pub fn prove_or<F>(
+        &self,
+        secrets: &[Option<BigUint>],
+        ys: &[(BigUint, BigUint)],
+        challenge: F,
+    ) -> OrProof
+    where
+        F: FnOnce(&[(BigUint, BigUint)]) -> BigUint,
+    {
+        self.prove_or_with_nonce(secrets, ys, self.generate_nonce().expose(), challenge)
+    }
+
+    //prove_or with the nonce of the real branch supplied (e.g. by a secure element), see
+    //assert_nonce; the simulated branches still draw their own c_i and s_i
+    #[cfg(feature = "prover")]
+    pub fn prove_or_with_nonce<F>(
+        &self,
+        secrets: &[Option<BigUint>],
+        ys: &[(BigUint, BigUint)],
+        k: &BigUint,
+        challenge: F,
+    ) -> OrProof
+    where
+        F: FnOnce(&[(BigUint, BigUint)]) -> BigUint,
+    {
+        assert_eq!(secrets.len(), ys.len(), "one secret slot is needed per public pair");
+        assert_eq!(secrets.iter().filter(|x| x.is_some()).count(), 1, "exactly one secret");
+        self.assert_nonce(k);
+
+        let real = secrets.iter().position(Option::is_some).unwrap();
+
+        let mut commitments = Vec::with_capacity(ys.len());
+        let mut challenges = Vec::with_capacity(ys.len());
+        let mut responses = Vec::with_capacity(ys.len());
+
+        for (i, (y1, y2)) in ys.iter().enumerate() {
+            if i == real {
+                commitments.push(self.compute_pair(k));
+                challenges.push(BigUint::from(0u32));
+                responses.push(BigUint::from(0u32));
+                continue;
+            }
+            // simulated branch: pick c_i and s_i first, then r_i = alpha^s_i * y_i^c_i mod p
+            let c_i = ZKP::generate_random_number_below(&self.q);
+            let s_i = ZKP::generate_random_number_below(&self.q);
+            let r1 = powm(&self.alpha, &s_i, &self.p) * powm(y1, &c_i, &self.p) % &*self.p;
+            let r2 = powm(&self.beta, &s_i, &self.p) * powm(y2, &c_i, &self.p) % &*self.p;
+            commitments.push((r1, r2));
+            challenges.push(c_i);
+            responses.push(s_i);
+        }
+
+        let c = challenge(&commitments);
+
+        // the real branch gets whatever is left: c_real = c - sum(c_i) mod q
+        let simulated =
+            challenges.iter().fold(BigUint::from(0u32), |acc, c_i| acc + c_i) % &*self.q;
+        let c_real = self.respond(&c, &BigUint::from(1u32), &simulated);
+        responses[real] = self.respond(k, &c_real, secrets[real].as_ref().unwrap());
+        challenges[real] = c_real;
+
+        OrProof { commitments, challenges, responses }
+    }
+
+    //checks an OrProof against the public pairs ys and the challenge c the verifier sent
+    //every branch must verify with its own c_i and the c_i must add up to c mod q
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_or(&self, ys: &[(BigUint, BigUint)], proof: &OrProof, c: &BigUint) -> bool {
+        let n = ys.len();
+        if n == 0
+            || proof.commitments.len() != n
+            || proof.challenges.len() != n
+            || proof.responses.len() != n
+        {
+            return false;
+        }
+
+        let sum = proof.challenges.iter().fold(BigUint::from(0u32), |acc, c_i| acc + c_i);
+        if sum % &*self.q != c % &*self.q {
+            return false;
+        }
+
+        (0..n).all(|i| {
+            let (r1, r2) = &proof.commitments[i];
+            let (y1, y2) = &ys[i];
+            self.verify(r1, r2, y1, y2, &proof.challenges[i], &proof.responses[i])
+        })
+    }
+
+    #[cfg(feature = "prover")]
+    pub fn generate_random_number_below(bound: &BigUint) -> BigUint {
+        ZKP::sample_below(bound)
+    } // Generates a random BigUint below the specified bound
+
+    //uniform in [1, bound): sample_below again while it draws 0, for the values that must not
+    //be zero (challenges, nonces); bound must be at least 2
+    #[cfg(feature = "prover")]
+    pub fn generate_nonzero_below(bound: &BigUint) -> BigUint {
+        assert!(*bound > BigUint::from(1u32), "no nonzero value below {bound}");
+        loop {
+            let candidate = ZKP::sample_below(bound);
+            if candidate != BigUint::ZERO {
+                return candidate;
+            }
+        }
+    }
+
+    //uniform in [0, bound) by rejection sampling: draw bound.bits() random bits and start over
+    //while the draw is >= bound, never reduce mod bound (that favours the small values);
+    //every draw is accepted with probability > 1/2
+    #[cfg(feature = "prover")]
+    pub fn sample_below(bound: &BigUint) -> BigUint {
+        let mut rng = rand::thread_rng();
+        let (len, mask) = range_bytes(bound);
+        let mut bytes = vec![0u8; len];
+        loop {
+            rng.fill(&mut bytes[..]);
+            bytes[0] &= mask;
+            let candidate = BigUint::from_bytes_be(&bytes);
+            if &candidate < bound {
+                return candidate;
+            }
+        }
+    }
+
+    //deterministic version of sample_below for Fiat-Shamir and the other hashed challenges:
+    //the bits come from SHA-256(tag || attempt || block || bytes) and a candidate >= bound is
+    //rejected for the next attempt, so the output is as uniform in [0, bound) as the hash
+    pub fn hash_to_range(bytes: &[u8], bound: &BigUint) -> BigUint {
+        let (len, mask) = range_bytes(bound);
+        for attempt in 0u32.. {
+            let mut candidate = Vec::with_capacity(len + 32);
+            for block in 0u32.. {
+                if candidate.len() >= len {
+                    break;
+                }
+                let mut hasher = Sha256::new();
+                hasher.update(b"zkp_auth hash_to_range");
+                hasher.update(attempt.to_be_bytes());
+                hasher.update(block.to_be_bytes());
+                hasher.update(bytes);
+                candidate.extend_from_slice(&hasher.finalize());
+            }
+            candidate.truncate(len);
+            candidate[0] &= mask;
+            let candidate = BigUint::from_bytes_be(&candidate);
+            if &candidate < bound {
+                return candidate;
+            }
+        }
+        unreachable!("a candidate is accepted with probability > 1/2")
+    }
+
+    #[cfg(feature = "prover")]
+    pub fn generate_random_string(size: usize) -> String {
+        rand::thread_rng() // Create a random number generator
+            .sample_iter(rand::distributions::Alphanumeric) // Sample from alphanumeric characters
+            .take(size) // Take the specified number of characters
+            .map(char::from) // Convert each byte to a char
+            .collect() // Collect into a String
+    }
+    // Generates a random alphanumeric string of the specified size (any size, e.g. for names in
+    // tests: ids that must not be guessed come from generate_secure_id)
+
+    //alphanumeric id for what a guess must never hit (session ids, auth ids): each character
+    //carries about 5.95 bits, fewer than MIN_ID_LENGTH of them is an error
+    #[cfg(feature = "prover")]
+    pub fn generate_secure_id(length: usize) -> Result<String, ZKPError> {
+        if length < MIN_ID_LENGTH {
+            return Err(ZKPError::IdTooShort(length));
+        }
+        Ok(ZKP::generate_random_string(length))
+    }
+
+    //session id (and auth id) of the minimum length
+    #[cfg(feature = "prover")]
+    pub fn generate_session_id() -> String {
+        ZKP::generate_secure_id(MIN_ID_LENGTH).expect("MIN_ID_LENGTH is long enough")
+    }
+
+    //size characters drawn uniformly from alphabet
+    #[cfg(feature = "prover")]
+    pub fn generate_random_string_from(alphabet: &[u8], size: usize) -> String {
+        let mut rng = rand::thread_rng();
+        (0..size).map(|_| char::from(alphabet[rng.gen_range(0..alphabet.len())])).collect()
+    }
+
+    //unambiguous id (CROCKFORD_ALPHABET) with at least entropy_bits bits of randomness,
+    //each character carries 5 bits
+    #[cfg(feature = "prover")]
+    pub fn generate_readable_id(entropy_bits: u32) -> String {
+        ZKP::generate_random_string_from(CROCKFORD_ALPHABET, entropy_bits.div_ceil(5) as usize)
+    }
+
+    //(alpha, beta, p, q) of ZKP::default(), fixed at compile time (see DEFAULT_GROUP_FINGERPRINT)
+    pub fn default_constants() -> (BigUint, BigUint, BigUint, BigUint) {
+        #[cfg(not(feature = "group-rfc5114-2048"))]
+        return ZKP::get_constants();
+        #[cfg(feature = "group-rfc5114-2048")]
+        return ZKP::get_constants_2048();
+    }
+
+    //the public i of beta = alpha^i in get_constants and get_constants_2048, so anyone can check
+    //beta is not an independent generator (see verify_beta_derivation)
+    pub fn beta_exponent() -> BigUint {
+        BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap())
+    }
+
+    pub fn get_constants() -> (BigUint, BigUint, BigUint, BigUint) {
+        let p = BigUint::from_bytes_be(&hex::decode("B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B616073E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACCBDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151AF5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371").unwrap());
+        let q = BigUint::from_bytes_be(
+            &hex::decode("F518AA8781A8DF278ABA4E7D64B7CB9D49462353").unwrap(),
+        );
+
+        let alpha = BigUint::from_bytes_be(
+            &hex::decode("A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5").unwrap(),
+        );
+
+        // beta = alpha^i is also a generator
+        let beta = alpha.modpow(&ZKP::beta_exponent(), &p);
+
+        (alpha, beta, p, q)
+    }
+
+    //RFC 5114 2048-bit MODP group with a 224-bit prime order subgroup, same order as get_constants
+    pub fn get_constants_2048() -> (BigUint, BigUint, BigUint, BigUint) {
+        let p = BigUint::from_bytes_be(&hex::decode("AD107E1E9123A9D0D660FAA79559C51FA20D64E5683B9FD1B54B1597B61D0A75E6FA141DF95A56DBAF9A3C407BA1DF15EB3D688A309C180E1DE6B85A1274A0A66D3F8152AD6AC2129037C9EDEFDA4DF8D91E8FEF55B7394B7AD5B7D0B6C12207C9F98D11ED34DBF6C6BA0B2C8BBC27BE6A00E0A0B9C49708B3BF8A317091883681286130BC8985DB1602E714415D9330278273C7DE31EFDC7310F7121FD5A07415987D9ADC0A486DCDF93ACC44328387315D75E198C641A480CD86A1B9E587E8BE60E69CC928B2B9C52172E413042E9B23F10B0E16E79763C9B53DCF4BA80A29E3FB73C16B8E75B97EF363E2FFA31F71CF9DE5384E71B81C0AC4DFFE0C10E64F").unwrap());
+        let q = BigUint::from_bytes_be(
+            &hex::decode("801C0D34C58D93FE997177101F80535A4738CEBCBF389A99B36371EB").unwrap(),
+        );
+
+        let alpha = BigUint::from_bytes_be(
+            &hex::decode("AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98AE247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D119529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8ACB70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA").unwrap(),
+        );
+
+        // beta = alpha^i is also a generator
+        let beta = alpha.modpow(&ZKP::beta_exponent(), &p);
+
+        (alpha, beta, p, q)
+    }
+
+    //whether beta = alpha^i mod p for this i, e.g. ZKP::beta_exponent() for the built-in groups
+    pub fn verify_beta_derivation(&self, i: &BigUint) -> bool {
+        *self.beta == powm(&self.alpha, i, &self.p)
+    }
+
+    //verify_beta_derivation(&ZKP::beta_exponent()), the modpow done on the first call only
+    pub fn beta_is_derived(&self) -> bool {
+        *self.beta_derived.get_or_init(|| self.verify_beta_derivation(&ZKP::beta_exponent()))
+    }
+
+    //y2 from y1 alone when beta = alpha^i: beta^x = alpha^(i*x) = y1^i mod p, so a store may keep
+    //only y1 (see verify_beta_derivation for checking the i)
+    pub fn derive_y2(&self, y1: &BigUint, i: &BigUint) -> BigUint {
+        powm(y1, i, &self.p)
+    }
+
+    //group of a safe prime p = 2q + 1 without having to pass q (so without passing a wrong one):
+    //q = (p-1)/2, p and q must be probable primes and alpha, beta elements of order q
+    #[cfg(feature = "prover")]
+    pub fn from_safe_prime(alpha: BigUint, beta: BigUint, p: BigUint) -> Result<ZKP, ZKPError> {
+        let one = BigUint::from(1u32);
+        if p < BigUint::from(5u32) || !is_probable_prime(&p, 40) {
+            return Err(ZKPError::NotASafePrime);
+        }
+        let q: BigUint = (&p - &one) >> 1;
+        if !is_probable_prime(&q, 40) {
+            return Err(ZKPError::NotASafePrime);
+        }
+
+        // q = (p-1)/2 is below p and divides p - 1, only the generators are left to check
+        ZKP::try_new(alpha, beta, p, q)
+    }
+
+    //generates a fresh group: a probable safe prime p = 2q + 1 of the given bit size, q = (p-1)/2,
+    //alpha a generator of the order-q subgroup and beta = alpha^i for a random i
+    //returns (alpha, beta, p, q) in the same order as get_constants
+    //SLOW (minutes for 2048 bits) and only meant for experiments: use a standard group in production
+    #[cfg(feature = "prover")]
+    pub fn generate_params(bits: u64) -> (BigUint, BigUint, BigUint, BigUint) {
+        assert!(bits >= 16, "a group needs at least 16 bits");
+
+        let one = BigUint::from(1u32);
+        let two = BigUint::from(2u32);
+        let mut rng = rand::thread_rng();
+
+        let (p, q) = loop {
+            // q has bits - 1 bits, its top bit set so that p = 2q + 1 has exactly `bits` bits
+            let q = rng.gen_biguint(bits - 2) | (&one << (bits - 2)) | &one;
+            let p = &q * &two + &one;
+            if is_probable_prime(&q, 40) && is_probable_prime(&p, 40) {
+                break (p, q);
+            }
+        };
+
+        // squares mod a safe prime have order q (or 1), so h^2 generates the subgroup
+        let alpha = loop {
+            let h = rng.gen_biguint_range(&two, &(&p - &one));
+            let alpha = powm(&h, &two, &p);
+            if alpha != one {
+                break alpha;
+            }
+        };
+
+        // beta = alpha^i is also a generator
+        let i = &one + ZKP::sample_below(&(&q - &one));
+        let beta = powm(&alpha, &i, &p);
+
+        (alpha, beta, p, q)
+    }
+
+    //(alpha, beta, p, q) of this group, in the same order as get_constants
+    pub fn constants(&self) -> (&BigUint, &BigUint, &BigUint, &BigUint) {
+        (&self.alpha, &self.beta, &self.p, &self.q)
+    }
+
+    //SHA-256 over p, q, alpha and beta (each big-endian, prefixed by its 4-byte length)
+    //identifies the group, so encoded proofs can be matched against the right parameters
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for value in [&self.p, &self.q, &self.alpha, &self.beta] {
+            hash_biguint(&mut hasher, value);
+        }
+        hasher.finalize().into()
+    }
+
+    //SHA-256 over the group fingerprint, y1 and y2 (length-prefixed like fingerprint): what a
+    //server storing commitments hashed keeps instead of y1, y2, and compares them against later
+    pub fn commitment_digest(&self, commitment: &Commitment) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.fingerprint());
+        hash_biguint(&mut hasher, &commitment.y1);
+        hash_biguint(&mut hasher, &commitment.y2);
+        hasher.finalize().into()
+    }
+
+    //stable id of a user that does not depend on its name: commitment_digest in Crockford
+    //base32 (52 characters), the same commitment in the same group always gives the same handle
+    pub fn commitment_handle(&self, commitment: &Commitment) -> String {
+        crockford_base32(&self.commitment_digest(commitment))
+    }
+
+    //Pedersen commitment to x with blinding r: alpha^x * beta^r mod p
+    //it hides x, but only binds it while nobody knows log_alpha(beta): the beta of
+    //get_constants is derived from a published exponent, so use a group where it is not
+    pub fn pedersen_commit(&self, x: &BigUint, r: &BigUint) -> BigUint {
+        powm(&self.alpha, x, &self.p) * powm(&self.beta, r, &self.p) % &*self.p
+    }
+
+    //proves knowledge of (x, r) with commitment = alpha^x * beta^r mod p (Okamoto's protocol):
+    //the same commit/challenge/response shape as the main protocol, one response per base
+    //challenge receives t and returns the verifier's c, as in prove_or
+    #[cfg(feature = "prover")]
+    pub fn prove_pedersen_opening<F>(&self, x: &BigUint, r: &BigUint, challenge: F) -> PedersenProof
+    where
+        F: FnOnce(&BigUint) -> BigUint,
+    {
+        let (k1, k2) = (self.generate_nonce(), self.generate_nonce());
+        self.prove_pedersen_opening_with_nonce(x, r, (k1.expose(), k2.expose()), challenge)
+    }
+
+    //the same with the two nonces (k1 for x, k2 for r) supplied
+    #[cfg(feature = "prover")]
+    pub fn prove_pedersen_opening_with_nonce<F>(
+        &self,
+        x: &BigUint,
+        r: &BigUint,
+        (k1, k2): (&BigUint, &BigUint),
+        challenge: F,
+    ) -> PedersenProof
+    where
+        F: FnOnce(&BigUint) -> BigUint,
+    {
+        self.assert_nonce(k1);
+        self.assert_nonce(k2);
+        let t = self.pedersen_commit(k1, k2);
+
+        let c = challenge(&t);
+        PedersenProof { s1: self.respond(k1, &c, x), s2: self.respond(k2, &c, r), t }
+    }
+
+    //t = alpha^s1 * beta^s2 * commitment^c mod p
+    //c, s1 and s2 must be below q and the commitment in the order-q subgroup, checked before any
+    //exponentiation: s1 + q opens the same way, and so does -commitment for an even c
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_pedersen_opening(
+        &self,
+        commitment: &BigUint,
+        proof: &PedersenProof,
+        c: &BigUint,
+    ) -> bool {
+        [c, &proof.s1, &proof.s2].into_iter().all(|value| self.is_scalar(value))
+            && self.is_in_subgroup(commitment)
+            && proof.t
+                == self.pedersen_commit(&proof.s1, &proof.s2) * powm(commitment, c, &self.p)
+                    % &*self.p
+    }
+
+    //Fiat-Shamir: c = H(group fingerprint || y1 || y2 || r1 || r2 || context) mod q
+    //the hash replaces the verifier, so prover and verifier derive the same c from public values
+    //context binds the proof to its use (a session, a message...) so it cannot be replayed elsewhere
+    pub fn fiat_shamir_challenge(
+        &self,
+        commitment: &Commitment,
+        r1: &BigUint,
+        r2: &BigUint,
+        context: &[u8],
+    ) -> BigUint {
+        let mut hasher = Sha256::new();
+        hasher.update(self.fingerprint());
+        for value in [&commitment.y1, &commitment.y2, r1, r2] {
+            hash_biguint(&mut hasher, value);
+        }
+        hasher.update(context);
+        ZKP::hash_to_range(&hasher.finalize(), &self.q)
+    }
+
+    //c = hash_to_range(H(len(domain) || domain || transcript), q): the domain tag keeps a
+    //challenge derived for one protocol from answering for another that hashes the same bytes
+    //(recommended tags in the README, e.g. b"myapp/v1/login")
+    pub fn domain_challenge(&self, domain: &[u8], transcript: &[u8]) -> BigUint {
+        let mut hasher = Sha256::new();
+        hasher.update((domain.len() as u32).to_be_bytes());
+        hasher.update(domain);
+        hasher.update(transcript);
+        ZKP::hash_to_range(&hasher.finalize(), &self.q)
+    }
+
+    //Fiat-Shamir under a domain tag: domain_challenge over the values fiat_shamir_challenge hashes
+    //(group fingerprint, length-prefixed y1, y2, r1, r2, then the context)
+    pub fn fiat_shamir_challenge_in(
+        &self,
+        domain: &[u8],
+        commitment: &Commitment,
+        r1: &BigUint,
+        r2: &BigUint,
+        context: &[u8],
+    ) -> BigUint {
+        let mut transcript = self.fingerprint().to_vec();
+        for value in [&commitment.y1, &commitment.y2, r1, r2] {
+            let bytes = value.to_bytes_be();
+            transcript.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            transcript.extend_from_slice(&bytes);
+        }
+        transcript.extend_from_slice(context);
+        self.domain_challenge(domain, &transcript)
+    }
+
+    //channel binding: c' = H("zkp_auth challenge binding" || c || binding) mod q, so a challenge
+    //only answers for the channel (TLS exporter, client public key...) it was issued to;
+    //an empty binding leaves c as it is
+    pub fn bind_challenge(&self, c: &BigUint, binding: &[u8]) -> BigUint {
+        if binding.is_empty() {
+            return c.clone();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(b"zkp_auth challenge binding");
+        hash_biguint(&mut hasher, c);
+        hasher.update(binding);
+        ZKP::hash_to_range(&hasher.finalize(), &self.q)
+    }
+
+    //Fiat-Shamir context of the RegisterAndLogin RPC: binds the proof to the username, so it
+    //cannot register the same y1, y2 under another name
+    pub fn register_and_login_context(user: &str) -> Vec<u8> {
+        [b"zkp_auth register-and-login:".as_slice(), user.as_bytes()].concat()
+    }
+
+    //Fiat-Shamir context of the SubmitProof RPC: the timestamp (unix seconds) bounds how long a
+    //proof is accepted, the username keeps it from logging in anyone else
+    pub fn submit_proof_context(user: &str, timestamp: u64) -> Vec<u8> {
+        [b"zkp_auth submit-proof:".as_slice(), &timestamp.to_be_bytes(), user.as_bytes()].concat()
+    }
+
+    //stateless challenge: c = H(server secret || y1 || y2 || r1 || r2 || timestamp) mod q
+    //the server recomputes c from what the client sends back instead of storing it, the secret
+    //keeps clients from computing c themselves and the timestamp (unix seconds) bounds its validity
+    pub fn stateless_challenge(
+        &self,
+        secret: &[u8],
+        commitment: &Commitment,
+        r1: &BigUint,
+        r2: &BigUint,
+        timestamp: u64,
+    ) -> BigUint {
+        let mut hasher = Sha256::new();
+        hasher.update((secret.len() as u32).to_be_bytes());
+        hasher.update(secret);
+        for value in [&commitment.y1, &commitment.y2, r1, r2] {
+            hash_biguint(&mut hasher, value);
+        }
+        hasher.update(timestamp.to_be_bytes());
+        ZKP::hash_to_range(&hasher.finalize(), &self.q)
+    }
+
+    //a stateless challenge is good if c is the one derived for these values and was issued at most
+    //ttl ago (a timestamp in the future is refused too)
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_stateless_challenge(
+        &self,
+        secret: &[u8],
+        commitment: &Commitment,
+        r1: &BigUint,
+        r2: &BigUint,
+        timestamp: u64,
+        c: &BigUint,
+        ttl: Duration,
+    ) -> bool {
+        ZKP::timestamp_is_fresh(timestamp, ttl)
+            && *c == self.stateless_challenge(secret, commitment, r1, r2, timestamp)
+    }
+
+    //seconds since the UNIX epoch, the timestamp of stateless challenges
+    pub fn unix_timestamp() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).expect("clock after 1970").as_secs()
+    }
+
+    //issued at most ttl ago, and not in the future
+    pub fn timestamp_is_fresh(timestamp: u64, ttl: Duration) -> bool {
+        ZKP::timestamp_is_fresh_with_skew(timestamp, ttl, Duration::ZERO)
+    }
+
+    //the same for a timestamp from a clock that may be up to skew ahead or behind this one:
+    //fresh within [now - ttl - skew, now + skew]
+    pub fn timestamp_is_fresh_with_skew(timestamp: u64, ttl: Duration, skew: Duration) -> bool {
+        let now = ZKP::unix_timestamp();
+        let skew = skew.as_secs();
+        timestamp <= now.saturating_add(skew)
+            && now.saturating_sub(timestamp) <= ttl.as_secs().saturating_add(skew)
+    }
+
+    //non-interactive proof of knowledge of x: picks a random k and derives c with Fiat-Shamir
+    #[cfg(feature = "prover")]
+    pub fn prove_non_interactive(&self, x: &BigUint, context: &[u8]) -> Proof {
+        self.prove_non_interactive_in(x, None, context)
+    }
+
+    //the same with an optional domain tag (fiat_shamir_challenge_in), checked with
+    //ChallengeSource::FiatShamirIn(domain, context); None is prove_non_interactive
+    #[cfg(feature = "prover")]
+    pub fn prove_non_interactive_in(
+        &self,
+        x: &BigUint,
+        domain: Option<&[u8]>,
+        context: &[u8],
+    ) -> Proof {
+        self.prove_non_interactive_in_with_nonce(x, self.generate_nonce().expose(), domain, context)
+    }
+
+    //prove_non_interactive with k supplied by the caller instead of drawn here
+    #[cfg(feature = "prover")]
+    pub fn prove_non_interactive_with_nonce(
+        &self,
+        x: &BigUint,
+        k: &BigUint,
+        context: &[u8],
+    ) -> Proof {
+        self.prove_non_interactive_in_with_nonce(x, k, None, context)
+    }
+
+    //prove_non_interactive_in with k supplied by the caller, e.g. a secure element that keeps
+    //the nonce generation to itself. k must be fresh and secret: two proofs with the same k and
+    //different challenges give x = (s1 - s2) / (c2 - c1) mod q away, see assert_nonce
+    #[cfg(feature = "prover")]
+    pub fn prove_non_interactive_in_with_nonce(
+        &self,
+        x: &BigUint,
+        k: &BigUint,
+        domain: Option<&[u8]>,
+        context: &[u8],
+    ) -> Proof {
+        self.assert_nonce(k);
+        let commitment = {
+            let (y1, y2) = self.compute_pair(x);
+            Commitment::new(y1, y2)
+        };
+        let (r1, r2) = self.compute_pair(k);
+
+        let c = match domain {
+            None => self.fiat_shamir_challenge(&commitment, &r1, &r2, context),
+            Some(domain) => self.fiat_shamir_challenge_in(domain, &commitment, &r1, &r2, context),
+        };
+        let s = self.respond(k, &c, x);
+        Proof::new(r1, r2, c, s)
+    }
+
+    //a supplied nonce must be in [1, q): k = 0 gives x away with a single proof (r1 = r2 = 1)
+    //nothing here can tell a reused k, that is on whoever supplies it: one k per proof, ever
+    #[cfg(feature = "prover")]
+    fn assert_nonce(&self, k: &BigUint) {
+        assert!(*k != BigUint::from(0u32) && self.is_scalar(k), "a nonce must be in [1, q)");
+    }
+
+    //single verification path for interactive and non-interactive proofs:
+    //the challenge comes from `source`, proof.c must match it and then verify() runs as usual
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_proof(
+        &self,
+        commitment: &Commitment,
+        proof: &Proof,
+        source: ChallengeSource,
+    ) -> bool {
+        let c = match source {
+            ChallengeSource::Given(c) => c.clone(),
+            ChallengeSource::FiatShamir(context) => {
+                self.fiat_shamir_challenge(commitment, &proof.r1, &proof.r2, context)
+            }
+            ChallengeSource::FiatShamirIn(domain, context) => {
+                self.fiat_shamir_challenge_in(domain, commitment, &proof.r1, &proof.r2, context)
+            }
+        };
+
+        proof.c == c
+            && self.verify(&proof.r1, &proof.r2, &commitment.y1, &commitment.y2, &c, &proof.s)
+    }
+
+    //verifications per second this machine manages for this group: runs verify on one honest
+    //run in a loop for (at least) the given wall-clock duration
+    //single-threaded: it measures one core, multiply by the cores the server gets for capacity
+    #[cfg(feature = "prover")]
+    pub fn estimate_verify_throughput(&self, duration: Duration) -> f64 {
+        let x = ZKP::generate_random_number_below(&self.q);
+        let k = ZKP::generate_random_number_below(&self.q);
+        let c = ZKP::generate_random_number_below(&self.q);
+        let (y1, y2) = self.compute_pair(&x);
+        let (r1, r2) = self.compute_pair(&k);
+        let s = self.respond(&k, &c, &x);
+
+        let start = Instant::now();
+        let mut ops = 0u64;
+        while ops == 0 || start.elapsed() < duration {
+            assert!(std::hint::black_box(self.verify(&r1, &r2, &y1, &y2, &c, &s)));
+            ops += 1;
+        }
+
+        ops as f64 / start.elapsed().as_secs_f64()
+    }
+
+    //soundness, stated: the chance a prover who does not know x gets through one login,
+    //1/|challenge space| = 2^-min(challenge_bits, log2 q). None is the full challenge space
+    //(c drawn below q), Some(bits) challenges of only that many bits (see soundness_trial)
+    pub fn soundness_error(&self, challenge_bits: Option<u32>) -> f64 {
+        // log2 q from its top 53 bits, q itself may not fit an f64
+        let shift = self.q.bits().saturating_sub(53);
+        let top = (&*self.q >> shift).iter_u64_digits().next().unwrap_or(0) as f64;
+        let log2_q = top.log2() + shift as f64;
+        let bits = challenge_bits.map_or(log2_q, |bits| f64::from(bits).min(log2_q));
+        (-bits).exp2()
+    }
+
+    //soundness, observed (a classroom demonstration): runs `rounds` logins with challenges of only
+    //`bits` bits and returns the fraction that verified. The honest prover knows x and always
+    //passes. The cheater does not: it bets on a c ahead and answers with a random s and
+    //r1 = alpha^s * y1^c, r2 = beta^s * y2^c, which verify only if the verifier picks that c,
+    //about 2^-bits of the time. Real challenges are as large as q so that chance is nil
+    //panics unless 1 <= bits and 2^bits <= q
+    #[cfg(feature = "prover")]
+    pub fn soundness_trial(&self, bits: u32, rounds: u64, cheat: bool) -> f64 {
+        let bound = BigUint::from(1u32) << bits;
+        assert!(bits >= 1 && bound <= *self.q, "challenges of 1 to log2(q) bits");
+
+        let x = ZKP::generate_nonzero_below(&self.q);
+        let (y1, y2) = self.compute_pair(&x);
+        // in both branches (r1, r2) is fixed before the verifier draws c
+        let passed = (0..rounds)
+            .filter(|_| {
+                if cheat {
+                    let guess = ZKP::sample_below(&bound);
+                    let s = ZKP::sample_below(&self.q);
+                    let r1 = powm(&self.alpha, &s, &self.p) * powm(&y1, &guess, &self.p) % &*self.p;
+                    let r2 = powm(&self.beta, &s, &self.p) * powm(&y2, &guess, &self.p) % &*self.p;
+                    let c = ZKP::sample_below(&bound);
+                    self.verify(&r1, &r2, &y1, &y2, &c, &s)
+                } else {
+                    let k = ZKP::generate_nonzero_below(&self.q);
+                    let (r1, r2) = self.compute_pair(&k);
+                    let c = ZKP::sample_below(&bound);
+                    self.verify(&r1, &r2, &y1, &y2, &c, &self.respond(&k, &c, &x))
+                }
+            })
+            .count();
+        passed as f64 / rounds as f64
+    }
+
+    //verifier side: a fresh random challenge c in [1, q)
+    //c = 0 asks nothing: alpha^s = r1 * y1^0 holds for s = k, whatever x is, so anyone passes
+    #[cfg(feature = "prover")]
+    pub fn generate_challenge(&self) -> BigUint {
+        ZKP::generate_nonzero_below(&self.q)
+    }
+
+    //prover side: a fresh nonce k in [1, q) for one login, scrubbed once dropped
+    //(k = 0 sends r1 = r2 = 1 and then s = -c * x mod q, which gives x away)
+    #[cfg(feature = "prover")]
+    pub fn generate_nonce(&self) -> Secret {
+        Secret::new(ZKP::generate_nonzero_below(&self.q))
+    }
+
+    //c and s live in Z_q: anything >= q is not a value an honest party produces
+    pub fn is_scalar(&self, value: &BigUint) -> bool {
+        value < &*self.q
+    }
+
+    //bytes of the largest scalar (c, s, anything mod q) in big-endian
+    pub fn scalar_byte_len(&self) -> usize {
+        self.q.bits().div_ceil(8) as usize
+    }
+
+    //bytes of the largest group element (y1, y2, r1, r2, anything mod p) in big-endian
+    pub fn element_byte_len(&self) -> usize {
+        self.p.bits().div_ceil(8) as usize
+    }
+
+    //size of the group: bits of p (1024, 2048, 3072...)
+    pub fn group_bits(&self) -> u64 {
+        self.p.bits()
+    }
+
+    //decodes the c sent by the verifier (big-endian bytes), refusing anything >= q instead of
+    //silently reducing it, so prover and verifier cannot disagree on the challenge
+    pub fn challenge_from_bytes(&self, bytes: &[u8]) -> Result<BigUint, ZKPError> {
+        let c = BigUint::from_bytes_be(bytes);
+        if c < *self.q { Ok(c) } else { Err(ZKPError::ChallengeOutOfRange) }
+    }
+
+    //coin-flipping: the verifier sends H(c || nonce) before it sees r1, r2 and reveals c and the
+    //nonce afterwards, so c cannot be chosen depending on the prover's commitment
+    //returns (c, nonce, commitment)
+    #[cfg(feature = "prover")]
+    pub fn commit_challenge(&self) -> (BigUint, [u8; 32], [u8; 32]) {
+        let c = self.generate_challenge();
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill(&mut nonce);
+        let commitment = ZKP::challenge_commitment(&c, &nonce);
+        (c, nonce, commitment)
+    }
+
+    //H(c || nonce), the first move of the coin-flipping variant
+    pub fn challenge_commitment(c: &BigUint, nonce: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hash_biguint(&mut hasher, c);
+        hasher.update(nonce);
+        hasher.finalize().into()
+    }
+
+    //prover side: the revealed (c, nonce) must open the commitment received earlier
+    #[must_use = "a failed verification is only caught by checking the result"]
+    pub fn verify_challenge_commitment(
+        &self,
+        commitment: &[u8],
+        c: &BigUint,
+        nonce: &[u8],
+    ) -> bool {
+        c < &*self.q && ZKP::challenge_commitment(c, nonce).as_slice() == commitment
+    }
+
+    pub fn new(alpha: BigUint, beta: BigUint, p: BigUint, q: BigUint) -> Self {
+        ZKP::from_shared(Arc::new(alpha), Arc::new(beta), Arc::new(p), Arc::new(q))
+    }
+
+    fn from_shared(
+        alpha: Arc<BigUint>,
+        beta: Arc<BigUint>,
+        p: Arc<BigUint>,
+        q: Arc<BigUint>,
+    ) -> Self {
+        ZKP { alpha, beta, p, q, beta_derived: Arc::default() }
+    }
+
+    //new with every check that needs no primality test, for parameters from outside (config,
+    //CLI...): q in [2, p) and dividing p - 1, alpha and beta elements of order q. new takes four
+    //positional BigUints, p and q swapped would otherwise run a verify that means nothing
+    pub fn try_new(alpha: BigUint, beta: BigUint, p: BigUint, q: BigUint) -> Result<ZKP, ZKPError> {
+        let one = BigUint::from(1u32);
+        if q >= p {
+            return Err(ZKPError::InvalidGroupParameters("q is not below p"));
+        }
+        if q <= one {
+            return Err(ZKPError::InvalidGroupParameters("q is below 2"));
+        }
+        if (&p - &one) % &q != BigUint::ZERO {
+            return Err(ZKPError::InvalidGroupParameters("q does not divide p - 1"));
+        }
+        let zkp = ZKP::new(alpha, beta, p, q);
+        // 1 is in the subgroup but generates nothing
+        for generator in [&zkp.alpha, &zkp.beta] {
+            if **generator == one || !zkp.is_in_subgroup(generator) {
+                return Err(ZKPError::GeneratorNotInSubgroup);
+            }
+        }
+        Ok(zkp)
+    }
+}
+
+//"p=<hex>,q=<hex>,alpha=<hex>,beta=<hex>" in any order (e.g. from a --params flag), checked
+//before use: p and q probable primes, q divides p - 1, alpha and beta elements of order q
+#[cfg(feature = "prover")]
+impl std::str::FromStr for ZKP {
+    type Err = ZKPError;
+
+    fn from_str(params: &str) -> Result<ZKP, ZKPError> {
+        const NAMES: [&str; 4] = ["p", "q", "alpha", "beta"];
+        let malformed = |reason: String| ZKPError::MalformedEncoding(reason);
+
+        let mut values: [Option<BigUint>; 4] = Default::default();
+        for pair in params.split(',') {
+            let Some((name, value)) = pair.split_once('=') else {
+                return Err(malformed(format!("{:?} is not name=value", pair.trim())));
+            };
+            let (name, value) = (name.trim(), value.trim());
+            let Some(slot) = NAMES.iter().position(|known| *known == name) else {
+                return Err(malformed(format!("unknown parameter {name:?}")));
+            };
+            // parse_bytes alone would take "+" and "_" as well
+            if value.is_empty() || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(malformed(format!("{name} is not hex")));
+            }
+            let value = BigUint::parse_bytes(value.as_bytes(), 16).expect("checked to be hex");
+            if values[slot].replace(value).is_some() {
+                return Err(malformed(format!("{name} is given twice")));
+            }
+        }
+        let [Some(p), Some(q), Some(alpha), Some(beta)] = values else {
+            let missing = values.iter().position(Option::is_none).expect("one is missing");
+            return Err(malformed(format!("{} is missing", NAMES[missing])));
+        };
+
+        if p < BigUint::from(5u32) || !is_probable_prime(&p, 40) {
+            return Err(ZKPError::InvalidGroupParameters("p is not prime"));
+        }
+        if !is_probable_prime(&q, 40) {
+            return Err(ZKPError::InvalidGroupParameters("q is not prime"));
+        }
+        ZKP::try_new(alpha, beta, p, q)
+    }
+}
+
+//feeds value into the hasher as its 4-byte big-endian length followed by its big-endian bytes,
+//so that consecutive values cannot be re-split into different ones with the same hash
+fn hash_biguint(hasher: &mut Sha256, value: &BigUint) {
+    let bytes = value.to_bytes_be();
+    hasher.update((bytes.len() as u32).to_be_bytes());
+    hasher.update(&bytes);
+}
+
+//bytes needed for a value below bound, and the mask keeping only bound.bits() bits of the first one
+fn range_bytes(bound: &BigUint) -> (usize, u8) {
+    assert!(bound.bits() > 0, "cannot sample below 0");
+    let bits = bound.bits();
+    let len = bits.div_ceil(8) as usize;
+    (len, 0xff >> (len as u64 * 8 - bits))
+}
+
+//Miller-Rabin probabilistic primality test with `rounds` random witnesses
+//a composite passes with probability at most 4^-rounds
+#[cfg(feature = "prover")]
+pub fn is_probable_prime(n: &BigUint, rounds: usize) -> bool {
+    let zero = BigUint::from(0u32);
+    let one = BigUint::from(1u32);
+    let two = BigUint::from(2u32);
+
+    if *n < two {
+        return false;
+    }
+    // trial division rules out most candidates before any exponentiation
+    for small in [2u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47] {
+        let small = BigUint::from(small);
+        if *n == small {
+            return true;
+        }
+        if n % &small == zero {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_1 = n - &one;
+    let s = n_minus_1.trailing_zeros().expect("n - 1 is not zero");
+    let d = &n_minus_1 >> s;
+
+    let mut rng = rand::thread_rng();
+    'witness: for _ in 0..rounds {
+        let a = rng.gen_biguint_range(&two, &n_minus_1);
+        let mut x = powm(&a, &d, n);
+        if x == one || x == n_minus_1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = powm(&x, &two, n);
+            if x == n_minus_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// serde helpers: BigUint <-> upper-case hex string, the format of every text encoding
+#[cfg(feature = "serde")]
+pub(crate) mod hex_biguint {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode_upper(value.to_bytes_be()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let hex_value = String::deserialize(deserializer)?;
+        let bytes = hex::decode(hex_value).map_err(D::Error::custom)?;
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+}
+
+// fixed runs in the toy group (p = 23, q = 11), no prover code: these also run with verifier-only
+#[cfg(test)]
+mod verifier_test {
+    use super::*;
+
+    fn toy() -> ZKP {
+        ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into())
+    }
+
+    #[test]
+    fn test_verify_against_challenges() {
+        // s = 5 answers c = 4 only (x = 6, k = 7)
+        let zkp = toy();
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        let (r1, r2, s) = (BigUint::from(8u32), BigUint::from(4u32), BigUint::from(5u32));
+        let allowed: Vec<BigUint> = [1u32, 9, 4, 7].map(BigUint::from).to_vec();
+        assert_eq!(zkp.verify_against_challenges(&commitment, &r1, &r2, &s, &allowed), Some(2));
+
+        assert_eq!(zkp.verify_against_challenges(&commitment, &r1, &r2, &s, &allowed[..2]), None);
+        assert_eq!(zkp.verify_against_challenges(&commitment, &r1, &r2, &s, &[]), None);
+        // 15 = 4 mod 11 is not a challenge, and s must be below q too
+        let reduced = [BigUint::from(15u32)];
+        assert_eq!(zkp.verify_against_challenges(&commitment, &r1, &r2, &s, &reduced), None);
+        let s = &s + 11u32;
+        assert_eq!(zkp.verify_against_challenges(&commitment, &r1, &r2, &s, &allowed), None);
+    }
+
+    #[test]
+    fn test_commitment_handle() {
+        assert_eq!(crockford_base32(&[]), "");
+        assert_eq!(crockford_base32(&[0xFF]), "ZW");
+        assert_eq!(crockford_base32(&[0x00, 0x44, 0x32, 0x14, 0xC7]), "01234567");
+
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+        let (y1, y2) = zkp.compute_pair(&BigUint::from(0x1234_5678_u64));
+        let handle = zkp.commitment_handle(&Commitment::new(y1.clone(), y2.clone()));
+        assert_eq!(handle.len(), 52);
+        assert!(handle.bytes().all(|b| CROCKFORD_ALPHABET.contains(&b)));
+        // the same commitment, computed again
+        let (y1_again, y2_again) = zkp.compute_pair(&BigUint::from(0x1234_5678_u64));
+        assert_eq!(zkp.commitment_handle(&Commitment::new(y1_again, y2_again)), handle);
+
+        // another secret, swapped y1 and y2, another group: all different
+        let (other1, other2) = zkp.compute_pair(&BigUint::from(0x1234_5679_u64));
+        assert_ne!(zkp.commitment_handle(&Commitment::new(other1, other2)), handle);
+        assert_ne!(zkp.commitment_handle(&Commitment::new(y2.clone(), y1.clone())), handle);
+        assert_ne!(toy().commitment_handle(&Commitment::new(y1, y2)), handle);
+    }
+
+    #[test]
+    fn test_compute_commitments_matches_compute_pair() {
+        let zkp = toy();
+        let (alpha, beta) = (BigUint::from(4u32), BigUint::from(9u32));
+        for x in 0u32..11 {
+            let x = BigUint::from(x);
+            let (y1, y2) = zkp.compute_pair(&x);
+            assert_eq!(zkp.compute_commitments(&[&alpha, &beta], &x), vec![y1, y2]);
+        }
+        // x = 6: alpha^6 = 2, beta^6 = 3, and any other base of the group works the same way
+        let extra = BigUint::from(2u32);
+        let ys = zkp.compute_commitments(&[&alpha, &beta, &extra], &6u32.into());
+        assert_eq!(ys, [2u32, 3, 18].map(BigUint::from).to_vec());
+        assert!(zkp.compute_commitments(&[], &6u32.into()).is_empty());
+    }
+
+    #[test]
+    fn test_soundness_error() {
+        // challenges below q = 11: a cheater has 1 chance in 11
+        let zkp = toy();
+        assert!((zkp.soundness_error(None) - 1.0 / 11.0).abs() < 1e-12);
+        // reduced challenges: 2^-bits, but never below what q allows
+        assert_eq!(zkp.soundness_error(Some(1)), 0.5);
+        assert_eq!(zkp.soundness_error(Some(3)), 0.125);
+        assert_eq!(zkp.soundness_error(Some(0)), 1.0);
+        assert!((zkp.soundness_error(Some(64)) - 1.0 / 11.0).abs() < 1e-12);
+
+        // the 1024-bit group has a 160-bit q
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+        let full = zkp.soundness_error(None);
+        assert!(full > 2f64.powi(-160) && full < 2f64.powi(-159), "{full}");
+        assert_eq!(zkp.soundness_error(Some(80)), 2f64.powi(-80));
+    }
+
+    #[test]
+    fn test_try_new_rejects_inconsistent_groups() {
+        let n = |v: u32| BigUint::from(v);
+        assert!(ZKP::try_new(n(4), n(9), n(23), n(11)).is_ok());
+
+        // p and q swapped, the easy mistake with four positional BigUints
+        assert_eq!(
+            ZKP::try_new(n(4), n(9), n(11), n(23)).unwrap_err(),
+            ZKPError::InvalidGroupParameters("q is not below p")
+        );
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        assert_eq!(
+            ZKP::try_new(alpha.clone(), beta.clone(), q.clone(), p.clone()).unwrap_err(),
+            ZKPError::InvalidGroupParameters("q is not below p")
+        );
+        assert!(ZKP::try_new(alpha, beta, p, q).is_ok());
+
+        assert_eq!(
+            ZKP::try_new(n(4), n(9), n(23), n(1)).unwrap_err(),
+            ZKPError::InvalidGroupParameters("q is below 2")
+        );
+        assert_eq!(
+            ZKP::try_new(n(4), n(9), n(23), n(7)).unwrap_err(),
+            ZKPError::InvalidGroupParameters("q does not divide p - 1")
+        );
+        // 5 has order 22, 1 generates nothing
+        assert_eq!(
+            ZKP::try_new(n(5), n(9), n(23), n(11)).unwrap_err(),
+            ZKPError::GeneratorNotInSubgroup
+        );
+        assert_eq!(
+            ZKP::try_new(n(4), n(1), n(23), n(11)).unwrap_err(),
+            ZKPError::GeneratorNotInSubgroup
+        );
+    }
+
+    #[test]
+    fn test_verify_beta_derivation() {
+        // 4^8 = 9 mod 23
+        assert!(toy().verify_beta_derivation(&8u32.into()));
+        assert!(!toy().verify_beta_derivation(&7u32.into()));
+
+        let i = ZKP::beta_exponent();
+        for (alpha, beta, p, q) in [ZKP::get_constants(), ZKP::get_constants_2048()] {
+            let zkp = ZKP::new(alpha, beta, p, q);
+            assert!(zkp.verify_beta_derivation(&i));
+            assert!(!zkp.verify_beta_derivation(&(&i + 1u32)));
+        }
+    }
+
+    #[test]
+    fn test_beta_is_derived() {
+        for (alpha, beta, p, q) in [ZKP::get_constants(), ZKP::get_constants_2048()] {
+            assert!(ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone()).beta_is_derived());
+            // a tampered beta, still an element of the group (alpha * beta)
+            let tampered = &alpha * &beta % &p;
+            assert!(!ZKP::new(alpha, tampered, p, q).beta_is_derived());
+        }
+        // the toy beta is alpha^8, not alpha^beta_exponent()
+        assert!(!toy().beta_is_derived());
+    }
+
+    #[test]
+    fn test_derive_y2() {
+        // x = 6: y1 = 2, y2 = 3 = 2^8 mod 23
+        assert_eq!(toy().derive_y2(&2u32.into(), &8u32.into()), BigUint::from(3u32));
+
+        let i = ZKP::beta_exponent();
+        for (alpha, beta, p, q) in [ZKP::get_constants(), ZKP::get_constants_2048()] {
+            let zkp = ZKP::new(alpha, beta, p, q);
+            let (y1, y2) = zkp.compute_pair(&BigUint::from(0x1234_5678_9abc_u64));
+            assert_eq!(zkp.derive_y2(&y1, &i), y2);
+        }
+    }
+
+    #[test]
+    fn test_timestamp_clock_skew() {
+        let (ttl, skew) = (Duration::from_secs(60), Duration::from_secs(5));
+        let now = ZKP::unix_timestamp();
+
+        // a client clock a little ahead: only fine within the skew
+        assert!(!ZKP::timestamp_is_fresh(now + 3, ttl));
+        assert!(ZKP::timestamp_is_fresh_with_skew(now + 3, ttl, skew));
+        assert!(!ZKP::timestamp_is_fresh_with_skew(now + 60, ttl, skew));
+        // a little behind: the skew is added to the ttl
+        assert!(!ZKP::timestamp_is_fresh(now - 63, ttl));
+        assert!(ZKP::timestamp_is_fresh_with_skew(now - 63, ttl, skew));
+        // far in the past
+        assert!(!ZKP::timestamp_is_fresh_with_skew(now - 3600, ttl, skew));
+        assert!(!ZKP::timestamp_is_fresh_with_skew(0, ttl, skew));
+    }
+
+    #[test]
+    fn test_verify_fixed_run() {
+        // x = 6, k = 7, c = 4 (see test_toy_example)
+        let zkp = toy();
+        let (y1, y2) = (BigUint::from(2u32), BigUint::from(3u32));
+        let (r1, r2) = (BigUint::from(8u32), BigUint::from(4u32));
+        let c = BigUint::from(4u32);
+
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &BigUint::from(5u32)));
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &BigUint::from(6u32)));
+    }
+
+    #[test]
+    fn test_verify_proof_fixed_run() {
+        let zkp = toy();
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        let proof = Proof::new(8u32.into(), 4u32.into(), 4u32.into(), 5u32.into());
+
+        assert!(zkp.verify_proof(
+            &commitment,
+            &proof,
+            ChallengeSource::Given(&BigUint::from(4u32))
+        ));
+        assert!(!zkp.verify_proof(
+            &commitment,
+            &proof,
+            ChallengeSource::Given(&BigUint::from(5u32))
+        ));
+    }
+
+    #[test]
+    fn test_default_group_is_the_pinned_one() {
+        let zkp = ZKP::default();
+        assert_eq!(hex::encode_upper(zkp.fingerprint()), DEFAULT_GROUP_FINGERPRINT);
+        // decoded once, the same group every time
+        assert_eq!(ZKP::default().fingerprint(), zkp.fingerprint());
+
+        #[cfg(not(feature = "group-rfc5114-2048"))]
+        assert_eq!(zkp.p.bits(), 1024);
+        #[cfg(feature = "group-rfc5114-2048")]
+        assert_eq!(zkp.p.bits(), 2048);
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    // use std::result;
+
+    //    use std::collections::btree_map::Keys;
+    use super::*; // Import the functions to be tested
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[test]
+    fn test_default_is_the_1024_bit_group() {
+        let zkp = ZKP::default();
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        assert_eq!(zkp.fingerprint(), ZKP::new(alpha, beta, p, q).fingerprint());
+        assert_eq!(
+            hex::encode_upper(zkp.fingerprint()),
+            "1D803DBF19B14EAC182C1DDBC90E1E2C59607DD1B7631FD4A8387C4D93C57264"
+        );
+        assert!(zkp.beta_is_derived());
+
+        let x = ZKP::generate_random_number_below(&zkp.q);
+        let k = ZKP::generate_random_number_below(&zkp.q);
+        let c = ZKP::generate_random_number_below(&zkp.q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &((&s + 1u32) % &*zkp.q)));
+    }
+
+    #[test]
+    fn test_clone_shares_the_parameters() {
+        let zkp = ZKP::default();
+        let clone = zkp.clone();
+        for (a, b) in [(&zkp.p, &clone.p), (&zkp.q, &clone.q), (&zkp.alpha, &clone.alpha)] {
+            assert!(Arc::ptr_eq(a, b));
+        }
+        assert!(Arc::ptr_eq(&zkp.beta, &clone.beta));
+        // ZKP::default() hands out clones of one group decoded once
+        assert!(Arc::ptr_eq(&zkp.p, &ZKP::default().p));
+        assert_eq!(clone.fingerprint(), zkp.fingerprint());
+    }
+
+    #[test]
+    fn test_public_commitment_matches_compute_pair() {
+        // x = 6 in the toy group: y1 = 2, y2 = 3
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let x = BigUint::from(6u32);
+        let pair = public_commitment(&zkp.alpha, &zkp.beta, &zkp.p, &x);
+        assert_eq!(pair, (BigUint::from(2u32), BigUint::from(3u32)));
+        assert_eq!(pair, zkp.compute_pair(&x));
+
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
+        let x = &q - 2u32;
+        assert_eq!(public_commitment(&alpha, &beta, &p, &x), zkp.compute_pair(&x));
+    }
+
+    #[test]
+    fn test_toy_example() {
+        let alpha = BigUint::from(4u32);
+        let beta = BigUint::from(9u32);
+        let p: BigUint = BigUint::from(23u32);
+        let q: BigUint = BigUint::from(11u32);
+
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
+
+        let x = BigUint::from(6u32);
+        let k = BigUint::from(7u32);
+
+        let c = BigUint::from(4u32);
+
+        let (y1, y2) = zkp.compute_pair(&x);
+
+        assert_eq!(y1, BigUint::from(2u32));
+        assert_eq!(y2, BigUint::from(3u32));
+        let (r1, r2) = zkp.compute_pair(&k);
+        assert_eq!(r1, BigUint::from(8u32));
+        assert_eq!(r2, BigUint::from(4u32));
+
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert_eq!(s, BigUint::from(5u32));
+
+        let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &s);
+        assert!(result);
+
+        //fake secret:
+
+        let fake_x = BigUint::from(7u32);
+        let fake_s = zkp.solve(&k, &c, &fake_x).unwrap();
+
+        let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &fake_s);
+        assert!(!result);
+
+        println!("alpha: {alpha}, beta: {beta}, p: {p}, x: {x}, k: {k}, c: {c}");
+        println!("y1: {y1}, y2: {y2}, c: {c}");
+        println!("r1: {r1}, r2: {r2}, s: {s}");
+        println!("Verification result: {result}");
+    }
+
+    // degenerate but legal runs in the toy group, where y1^c or alpha^s come out as 1
+    #[test]
+    fn test_solve_is_k_minus_cx_mod_q() {
+        // every case of the reduction: k above and below c * x mod q, zeros, q - 1
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let top = &q - 1u32;
+        let mut values = vec![BigUint::ZERO, BigUint::from(1u32), top.clone()];
+        values.extend((0..5).map(|_| ZKP::generate_random_number_below(&q)));
+        for k in &values {
+            for c in &values {
+                for x in &values {
+                    let s = zkp.solve(k, c, x).unwrap();
+                    assert!(s < q);
+                    assert_eq!((&s + c * x) % &q, k % &q);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_rejects_oversized_values() {
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let n = |v: u32| BigUint::from(v);
+        assert_eq!(zkp.solve(&n(7), &n(4), &n(6)), Ok(n(5)));
+
+        // a c of 100 000 bits never reaches the multiplication
+        let huge = (BigUint::from(1u32) << 100_000u32) + 4u32;
+        assert_eq!(zkp.solve(&n(7), &huge, &n(6)), Err(ZKPError::ValueOutOfRange));
+        assert_eq!(zkp.solve(&n(7), &n(4), &huge), Err(ZKPError::ValueOutOfRange));
+        assert_eq!(zkp.solve(&n(11), &n(4), &n(6)), Err(ZKPError::ValueOutOfRange));
+    }
+
+    #[test]
+    fn test_toy_example_edge_cases() {
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let x = BigUint::from(6u32);
+        let (y1, y2) = zkp.compute_pair(&x);
+
+        // c = 0: y^c = 1 and s = k, the proof says nothing about x but is valid
+        let k = BigUint::from(7u32);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let c = BigUint::ZERO;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert_eq!(s, k);
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &(&s + 1u32)));
+
+        // s = 0: k = c * x mod q (4 * 6 = 24 = 2 mod 11), alpha^s = beta^s = 1
+        let k = BigUint::from(2u32);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let c = BigUint::from(4u32);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert_eq!(s, BigUint::ZERO);
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+        assert!(zkp.verify_detailed(&r1, &r2, &y1, &y2, &c, &s).is_valid());
+        assert!(zkp.verify_precomputed(&r1, &r2, &s, &zkp.challenge_terms(&y1, &y2, &c)));
+
+        // k = 0 (r1 = r2 = 1) and x = 0 (y1 = y2 = 1)
+        let zero = BigUint::ZERO;
+        let (r1, r2) = zkp.compute_pair(&zero);
+        assert_eq!((r1.clone(), r2.clone()), (BigUint::from(1u32), BigUint::from(1u32)));
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &zkp.solve(&zero, &c, &x).unwrap()));
+        let (y1_0, y2_0) = zkp.compute_pair(&zero);
+        assert!(zkp.verify(&r1, &r2, &y1_0, &y2_0, &c, &zkp.solve(&zero, &c, &zero).unwrap()));
+
+        // every (x, k, c) of the group: the honest answer passes and is the only one that does
+        for x in 0..11u32 {
+            let (y1, y2) = zkp.compute_pair(&x.into());
+            for k in 0..11u32 {
+                let (r1, r2) = zkp.compute_pair(&k.into());
+                for c in 0..11u32 {
+                    let s = zkp.solve(&k.into(), &c.into(), &x.into()).unwrap();
+                    assert!(s < BigUint::from(11u32));
+                    for answer in 0..11u32 {
+                        let answer = BigUint::from(answer);
+                        let valid = zkp.verify(&r1, &r2, &y1, &y2, &c.into(), &answer);
+                        assert_eq!(valid, answer == s, "x={x} k={k} c={c} s={answer}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_toy_example_with_random_numbers() {
+        let alpha = BigUint::from(4u32);
+        let beta = BigUint::from(9u32);
+        let p: BigUint = BigUint::from(23u32);
+        let q: BigUint = BigUint::from(11u32);
+
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
+        let x = BigUint::from(6u32);
+        let k = ZKP::generate_random_number_below(&q);
+
+        let c = ZKP::generate_random_number_below(&q);
+
+        let (y1, y2) = zkp.compute_pair(&x);
+        assert_eq!(y1, BigUint::from(2u32));
+        assert_eq!(y2, BigUint::from(3u32));
+        assert_eq!(y1, BigUint::from(2u32));
+        assert_eq!(y2, BigUint::from(3u32));
+
+        let (r1, r2) = zkp.compute_pair(&k);
+
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &s);
+        assert!(result);
+    }
+
+    #[test]
+    fn test_powm_matches_num_bigint_on_toy_group() {
+        // run with `--features rug` to cross-check the GMP backend against num-bigint
+        let p = BigUint::from(23u32);
+        for base in 0u32..23 {
+            for exp in 0u32..23 {
+                let (base, exp) = (BigUint::from(base), BigUint::from(exp));
+                assert_eq!(powm(&base, &exp, &p), base.modpow(&exp, &p));
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_in_subgroup_toy_group() {
+        let zkp = ZKP::new(
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+            BigUint::from(23u32),
+            BigUint::from(11u32),
+        );
+
+        // the order-11 subgroup of Z_23* are the quadratic residues
+        for element in [1u32, 2, 3, 4, 6, 8, 9, 12, 13, 16, 18] {
+            assert!(zkp.is_in_subgroup(&BigUint::from(element)));
+        }
+        // 22 = p - 1 has order 2 and 5 generates the whole group (order 22)
+        for element in [0u32, 5, 22, 23, 100] {
+            assert!(!zkp.is_in_subgroup(&BigUint::from(element)));
+        }
+    }
+
+    #[test]
+    fn test_verify_many_three_secrets() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
 
-    pub fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
-        let p1 = self.alpha.modpow(exp, &self.p);
-        let p2 = self.beta.modpow(exp, &self.p);
-        (p1, p2)
+        let xs: Vec<BigUint> = (0..3).map(|_| ZKP::generate_random_number_below(&q)).collect();
+        let ks: Vec<BigUint> = (0..3).map(|_| ZKP::generate_random_number_below(&q)).collect();
+
+        let ys = zkp.compute_pairs(&xs);
+        let rs = zkp.compute_pairs(&ks);
+        assert_eq!(ys[1], zkp.compute_pair(&xs[1]));
+
+        // one shared challenge for the three secrets
+        let c = ZKP::generate_random_number_below(&q);
+        let ss: Vec<BigUint> =
+            ks.iter().zip(&xs).map(|(k, x)| zkp.solve(k, &c, x).unwrap()).collect();
+
+        assert!(zkp.verify_many(&rs, &ys, &c, &ss));
+
+        // a single wrong secret breaks the whole AND-composition
+        let mut bad_ss = ss.clone();
+        bad_ss[2] = zkp.solve(&ks[2], &c, &(&xs[2] + 1u32)).unwrap();
+        assert!(!zkp.verify_many(&rs, &ys, &c, &bad_ss));
+
+        // mismatched lengths are rejected
+        assert!(!zkp.verify_many(&rs, &ys, &c, &ss[..2]));
     }
 
-    //output = s = k - c*x mod q
-    //k is the prover's random number, c is the challenge, x is the secret, q is the modulus
-    //returns s as BigUint
+    #[test]
+    fn test_or_proof_one_of_three() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let xs: Vec<BigUint> = (0..3).map(|_| ZKP::generate_random_number_below(&q)).collect();
+        let ys = zkp.compute_pairs(&xs);
+
+        // the prover knows a different secret each time, the verifier code is the same
+        for known in 0..3 {
+            let secrets: Vec<Option<BigUint>> =
+                (0..3).map(|i| if i == known { Some(xs[i].clone()) } else { None }).collect();
 
-    pub fn solve(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
-        let cx = c * x;
-        let k_mod_q = k % &self.q;
-        let cx_mod_q = &cx % &self.q;
+            let c = ZKP::generate_random_number_below(&q);
+            let proof = zkp.prove_or(&secrets, &ys, |_| c.clone());
+            assert!(zkp.verify_or(&ys, &proof, &c));
 
-        if k_mod_q >= cx_mod_q {
-            return (k_mod_q - cx_mod_q).modpow(&BigUint::from(1u32), &self.q);
+            // nothing in the proof singles out the real branch: every branch verifies on its own
+            for (i, (y1, y2)) in ys.iter().enumerate() {
+                let (r1, r2) = &proof.commitments[i];
+                assert!(zkp.verify(r1, r2, y1, y2, &proof.challenges[i], &proof.responses[i]));
+            }
+
+            // the challenge shares must add up to the verifier's challenge
+            let other_c = &c + 1u32;
+            assert!(!zkp.verify_or(&ys, &proof, &other_c));
         }
-        (&self.q + k_mod_q - cx_mod_q).modpow(&BigUint::from(1u32), &self.q)
+
+        // a secret that is not behind any of the pairs does not verify
+        let stranger = vec![None, Some(&xs[1] + 1u32), None];
+        let c = ZKP::generate_random_number_below(&q);
+        let proof = zkp.prove_or(&stranger, &ys, |_| c.clone());
+        assert!(!zkp.verify_or(&ys, &proof, &c));
     }
 
-    //cond1: r1 =alpha^s *y1^c  mod p
-    //cond2: r2 =beta^s *y2^c  mod p
-    //returns true if both conditions are satisfied
-    //r1, r2, y1, y2, alpha, beta, c, s, p are BigUint
-    //p is the modulus, c is the challenge, s is the response, alpha and beta are the public keys, y1 and y2 are the commitments
-    //r1 and r2 are the responses to be verified
-    pub fn verify(
-        &self,
-        r1: &BigUint,
-        r2: &BigUint,
-        y1: &BigUint,
-        y2: &BigUint,
+    #[test]
+    fn test_is_probable_prime() {
+        for prime in [2u32, 3, 23, 97, 7919, 104729] {
+            assert!(is_probable_prime(&BigUint::from(prime), 20));
+        }
+        // 561 is a Carmichael number, it fools the Fermat test but not Miller-Rabin
+        for composite in [0u32, 1, 4, 561, 7917, 104730] {
+            assert!(!is_probable_prime(&BigUint::from(composite), 20));
+        }
+    }
 
-        c: &BigUint,
-        s: &BigUint,
-    ) -> bool {
-        let cond1: bool = *r1
-            == ((&self.alpha.modpow(s, &self.p)) % &self.p * (y1.modpow(c, &self.p)) % &self.p)
-                % &self.p;
+    #[test]
+    fn test_byte_lengths() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+        assert_eq!((zkp.element_byte_len(), zkp.scalar_byte_len()), (128, 20));
 
-        let cond2: bool = *r2
-            == ((&self.beta.modpow(s, &self.p)) % &self.p * (y2.modpow(c, &self.p)) % &self.p)
-                % &self.p;
+        let (alpha, beta, p, q) = ZKP::get_constants_2048();
+        let zkp = ZKP::new(alpha, beta, p, q);
+        assert_eq!((zkp.element_byte_len(), zkp.scalar_byte_len()), (256, 28));
 
-        cond1 && cond2
+        // toy group: 23 and 11 fit in one byte
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        assert_eq!((zkp.element_byte_len(), zkp.scalar_byte_len()), (1, 1));
+        let s = zkp.solve(&7u32.into(), &4u32.into(), &6u32.into()).unwrap();
+        assert!(s.to_bytes_be().len() <= zkp.scalar_byte_len());
     }
 
-    pub fn generate_random_number_below(bound: &BigUint) -> BigUint {
-        let mut rng = rand::thread_rng();
-        // Create a random number generator
+    #[test]
+    fn test_from_safe_prime() {
+        // toy group: 23 = 2 * 11 + 1
+        let zkp = ZKP::from_safe_prime(4u32.into(), 9u32.into(), 23u32.into()).unwrap();
+        assert_eq!(*zkp.q, BigUint::from(11u32));
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        assert_eq!(zkp.fingerprint(), toy.fingerprint());
 
-        rng.gen_biguint_below(bound) // The `gen_biguint_below` function is provided by the `num-bigint` crate with the `rand` feature enabled
-    } // Generates a random BigUint below the specified bound
+        // 29 is prime but 14 is not, 21 is not prime at all
+        for p in [29u32, 21, 4, 0] {
+            let result = ZKP::from_safe_prime(4u32.into(), 9u32.into(), p.into());
+            assert_eq!(result.unwrap_err(), ZKPError::NotASafePrime, "p = {p}");
+        }
 
-    pub fn generate_random_string(size: usize) -> String {
-        rand::thread_rng() // Create a random number generator
-            .sample_iter(rand::distributions::Alphanumeric) // Sample from alphanumeric characters
-            .take(size) // Take the specified number of characters
-            .map(char::from) // Convert each byte to a char
-            .collect() // Collect into a String
+        // 5 is not a square mod 23 (order 22), 1 and 22 (order 2) generate no order-q subgroup
+        for alpha in [5u32, 1, 22] {
+            let result = ZKP::from_safe_prime(alpha.into(), 9u32.into(), 23u32.into());
+            assert_eq!(result.unwrap_err(), ZKPError::GeneratorNotInSubgroup, "alpha = {alpha}");
+        }
+        let result = ZKP::from_safe_prime(4u32.into(), 5u32.into(), 23u32.into());
+        assert_eq!(result.unwrap_err(), ZKPError::GeneratorNotInSubgroup);
     }
-    // Generates a random alphanumeric string of the specified size
 
-    pub fn get_constants() -> (BigUint, BigUint, BigUint, BigUint) {
-        let p = BigUint::from_bytes_be(&hex::decode("B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B616073E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACCBDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151AF5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371").unwrap());
-        let q = BigUint::from_bytes_be(
-            &hex::decode("F518AA8781A8DF278ABA4E7D64B7CB9D49462353").unwrap(),
-        );
+    #[test]
+    fn test_parse_params() {
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let zkp: ZKP = "p=17,q=B,alpha=4,beta=9".parse().unwrap();
+        assert_eq!(zkp.fingerprint(), toy.fingerprint());
+        // any order, spaces around the parts
+        let zkp: ZKP = " beta = 09 , alpha=04,q=0b, p=17 ".parse().unwrap();
+        assert_eq!(zkp.fingerprint(), toy.fingerprint());
 
-        let alpha = BigUint::from_bytes_be(
-            &hex::decode("A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5").unwrap(),
-        );
+        // the 1024-bit group (q of 160 bits: p is not a safe prime, which is fine here)
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let to_hex = |value: &BigUint| hex::encode_upper(value.to_bytes_be());
+        let (p_hex, q_hex) = (to_hex(&p), to_hex(&q));
+        let params = format!("p={p_hex},q={q_hex},alpha={},beta={}", to_hex(&alpha), to_hex(&beta));
+        let zkp: ZKP = params.parse().unwrap();
+        assert_eq!(zkp.fingerprint(), ZKP::new(alpha, beta, p, q).fingerprint());
 
-        // beta = alpha^i is also a generator
-        let exp = BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap());
-        let beta = alpha.modpow(&exp, &p);
+        for params in [
+            "",
+            "p=17,q=B,alpha=4",
+            "p=17,q=B,alpha=4,beta=9,gamma=2",
+            "p=17,q=B,alpha=4,beta=9,p=17",
+            "p=17,q=B,alpha=4,beta",
+            "p=17,q=B,alpha=4,beta=",
+            "p=17,q=B,alpha=4,beta=+9",
+            "p=0x17,q=B,alpha=4,beta=9",
+        ] {
+            let result = params.parse::<ZKP>();
+            assert!(matches!(result, Err(ZKPError::MalformedEncoding(_))), "{params:?}");
+        }
 
-        (alpha, beta, p, q)
+        // 21 is not prime, 22 = 2 * 11 not prime, 7 does not divide 22
+        for (params, reason) in [
+            ("p=15,q=B,alpha=4,beta=9", "p is not prime"),
+            ("p=17,q=16,alpha=4,beta=9", "q is not prime"),
+            ("p=17,q=7,alpha=4,beta=9", "q does not divide p - 1"),
+        ] {
+            assert_eq!(
+                params.parse::<ZKP>().unwrap_err(),
+                ZKPError::InvalidGroupParameters(reason)
+            );
+        }
+        // 5 has order 22, 1 generates nothing
+        for params in ["p=17,q=B,alpha=5,beta=9", "p=17,q=B,alpha=4,beta=1"] {
+            assert_eq!(params.parse::<ZKP>().unwrap_err(), ZKPError::GeneratorNotInSubgroup);
+        }
     }
 
-    pub fn new(alpha: BigUint, beta: BigUint, p: BigUint, q: BigUint) -> Self {
-        ZKP { alpha, beta, p, q }
-    }
-}
+    #[test]
+    fn test_generate_params_small_group() {
+        let (alpha, beta, p, q) = ZKP::generate_params(32);
+        let one = BigUint::from(1u32);
 
-#[cfg(test)]
-mod test {
-    // use std::result;
+        assert_eq!(p.bits(), 32);
+        assert_eq!(p, &q * 2u32 + 1u32);
+        assert!(is_probable_prime(&p, 20));
+        assert!(is_probable_prime(&q, 20));
 
-    //    use std::collections::btree_map::Keys;
-    use super::*; // Import the functions to be tested
+        // alpha and beta generate the order-q subgroup
+        assert_ne!(alpha, one);
+        assert_ne!(beta, one);
+        assert_eq!(alpha.modpow(&q, &p), one);
+        assert_eq!(beta.modpow(&q, &p), one);
+
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+    }
 
     #[test]
-    fn test_toy_example() {
-        let alpha = BigUint::from(4u32);
-        let beta = BigUint::from(9u32);
-        let p: BigUint = BigUint::from(23u32);
-        let q: BigUint = BigUint::from(11u32);
+    fn test_fingerprint_identifies_the_group() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
+        let same = ZKP::new(alpha.clone(), beta, p.clone(), q.clone());
+        let other = ZKP::new(alpha.clone(), alpha, p, q);
 
-        let zkp = ZKP { p: p.clone(), q: q.clone(), alpha: alpha.clone(), beta: beta.clone() };
+        assert_eq!(zkp.fingerprint(), same.fingerprint());
+        assert_ne!(zkp.fingerprint(), other.fingerprint());
+    }
 
-        let x = BigUint::from(6u32);
-        let k = BigUint::from(7u32);
+    #[test]
+    fn test_verify_proof_with_given_challenge() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
 
-        let c = BigUint::from(4u32);
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
 
         let (y1, y2) = zkp.compute_pair(&x);
+        let commitment = Commitment::new(y1, y2);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let proof = Proof::new(r1, r2, c.clone(), zkp.solve(&k, &c, &x).unwrap());
 
-        assert_eq!(y1, BigUint::from(2u32));
-        assert_eq!(y2, BigUint::from(3u32));
+        assert!(zkp.verify_proof(&commitment, &proof, ChallengeSource::Given(&c)));
+
+        // the proof answers c, not any other challenge
+        let other_c = &c + 1u32;
+        assert!(!zkp.verify_proof(&commitment, &proof, ChallengeSource::Given(&other_c)));
+    }
+
+    #[test]
+    fn test_pedersen_opening() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let r = ZKP::generate_random_number_below(&q);
+        let commitment = zkp.pedersen_commit(&x, &r);
+
+        let c = ZKP::generate_random_number_below(&q);
+        let proof = zkp.prove_pedersen_opening(&x, &r, |_| c.clone());
+        assert!(zkp.verify_pedersen_opening(&commitment, &proof, &c));
+        assert!(!zkp.verify_pedersen_opening(&commitment, &proof, &(&c + 1u32)));
+
+        // the right x with the wrong blinding factor does not open the commitment
+        let wrong_r = &r + 1u32;
+        let proof = zkp.prove_pedersen_opening(&x, &wrong_r, |_| c.clone());
+        assert!(!zkp.verify_pedersen_opening(&commitment, &proof, &c));
+
+        // out of range: a response plus q, and -commitment (not of order q) with an even c,
+        // both of which the equation alone would accept
+        let proof = zkp.prove_pedersen_opening(&x, &r, |_| c.clone());
+        let wide = PedersenProof { s1: &proof.s1 + &q, ..proof.clone() };
+        assert!(!zkp.verify_pedersen_opening(&commitment, &wide, &c));
+        let even_c = &c - &c % 2u32;
+        let proof = zkp.prove_pedersen_opening(&x, &r, |_| even_c.clone());
+        assert!(zkp.verify_pedersen_opening(&commitment, &proof, &even_c));
+        let negated = &*zkp.p - &commitment;
+        assert!(!zkp.verify_pedersen_opening(&negated, &proof, &even_c));
+    }
+
+    #[test]
+    fn test_verify_rejects_out_of_range_values() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
         let (r1, r2) = zkp.compute_pair(&k);
-        assert_eq!(r1, BigUint::from(8u32));
-        assert_eq!(r2, BigUint::from(4u32));
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
 
-        let s = zkp.solve(&k, &c, &x);
-        assert_eq!(s, BigUint::from(5u32));
+        // s + q and c + q satisfy the equations (exponents live mod q) but are not valid answers
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &(&s + &q)));
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &(&c + &q), &s));
 
-        let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &s);
-        assert!(result);
+        let zero = BigUint::ZERO;
+        assert!(!zkp.verify(&zero, &r2, &y1, &y2, &c, &s));
+        assert!(!zkp.verify(&r1, &zero, &y1, &y2, &c, &s));
+    }
 
-        //fake secret:
+    #[test]
+    fn test_verify_and_verify_detailed_refuse_zero_r() {
+        // y1 = y2 = 0 makes alpha^s * y1^c = 0 for any s, so r1 = r2 = 0 would pass the equations
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let zero = BigUint::ZERO;
+        let (c, s) = (BigUint::from(4u32), BigUint::from(5u32));
+        assert!(!zkp.verify(&zero, &zero, &zero, &zero, &c, &s));
+        let outcome = zkp.verify_detailed(&zero, &zero, &zero, &zero, &c, &s);
+        assert_eq!(outcome, VerifyOutcome::R1Mismatch);
 
-        let fake_x = BigUint::from(7u32);
-        let fake_s = zkp.solve(&k, &c, &fake_x);
+        // only r2 = 0 (toy run x = 6, k = 7, c = 4: r1 = 8, s = 5)
+        let (r1, y1, y2) = (BigUint::from(8u32), BigUint::from(2u32), BigUint::from(0u32));
+        assert!(!zkp.verify(&r1, &zero, &y1, &y2, &c, &s));
+        assert_eq!(zkp.verify_detailed(&r1, &zero, &y1, &y2, &c, &s), VerifyOutcome::R2Mismatch);
+    }
 
-        let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &fake_s);
-        assert!(!result);
+    #[test]
+    fn test_verify_detailed() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
 
-        println!("alpha: {alpha}, beta: {beta}, p: {p}, x: {x}, k: {k}, c: {c}");
-        println!("y1: {y1}, y2: {y2}, c: {c}");
-        println!("r1: {r1}, r2: {r2}, s: {s}");
-        println!("Verification result: {result}");
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        assert_eq!(zkp.verify_detailed(&r1, &r2, &y1, &y2, &c, &s), VerifyOutcome::Valid);
+        assert_eq!(zkp.verify_detailed(&r2, &r2, &y1, &y2, &c, &s), VerifyOutcome::R1Mismatch);
+        assert_eq!(zkp.verify_detailed(&r1, &r1, &y1, &y2, &c, &s), VerifyOutcome::R2Mismatch);
+        assert!(!zkp.verify_detailed(&r1, &r2, &y1, &y2, &c, &(&s + 1u32)).is_valid());
+        // c, s not below q: refused before any exponentiation, like verify does
+        let outcome = zkp.verify_detailed(&r1, &r2, &y1, &y2, &(&c + &q), &s);
+        assert_eq!(outcome, VerifyOutcome::ChallengeOutOfRange);
+        let outcome = zkp.verify_detailed(&r1, &r2, &y1, &y2, &c, &(&s + &q));
+        assert_eq!(outcome, VerifyOutcome::ResponseOutOfRange);
+
+        let terms = zkp.challenge_terms(&y1, &y2, &c);
+        assert_eq!(zkp.verify_precomputed_detailed(&r1, &r2, &s, &terms), VerifyOutcome::Valid);
+        let outcome = zkp.verify_precomputed_detailed(&r1, &r1, &s, &terms);
+        assert_eq!(outcome, VerifyOutcome::R2Mismatch);
     }
 
+    // the codes are a contract with clients: changing one breaks them
     #[test]
-    fn test_toy_example_with_random_numbers() {
-        let alpha = BigUint::from(4u32);
-        let beta = BigUint::from(9u32);
-        let p: BigUint = BigUint::from(23u32);
-        let q: BigUint = BigUint::from(11u32);
+    fn test_verify_outcome_codes() {
+        let codes = [
+            (VerifyOutcome::Valid, "ok", false),
+            (VerifyOutcome::R1Mismatch, "cond1_failed", false),
+            (VerifyOutcome::R2Mismatch, "cond2_failed", false),
+            (VerifyOutcome::ChallengeOutOfRange, "challenge_out_of_range", true),
+            (VerifyOutcome::ResponseOutOfRange, "response_out_of_range", false),
+        ];
+        for (outcome, code, retryable) in codes {
+            assert_eq!(outcome.as_code(), code);
+            assert_eq!(outcome.is_retryable(), retryable);
+        }
+    }
 
-        let zkp = ZKP { p: p.clone(), q: q.clone(), alpha: alpha.clone(), beta: beta.clone() };
-        let x = BigUint::from(6u32);
+    #[test]
+    fn test_verify_precomputed_agrees_with_verify() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
         let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let terms = zkp.challenge_terms(&y1, &y2, &c);
+
+        for s in [s.clone(), &s + 1u32, &s + &q] {
+            assert_eq!(
+                zkp.verify_precomputed(&r1, &r2, &s, &terms),
+                zkp.verify(&r1, &r2, &y1, &y2, &c, &s)
+            );
+        }
+        assert!(zkp.verify_precomputed(&r1, &r2, &s, &terms));
+        // terms of another challenge do not fit this answer
+        let other = zkp.challenge_terms(&y1, &y2, &(&c + 1u32));
+        assert!(!zkp.verify_precomputed(&r1, &r2, &s, &other));
+    }
+
+    #[test]
+    fn test_estimate_verify_throughput() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+
+        let throughput = zkp.estimate_verify_throughput(Duration::from_millis(50));
+        assert!(throughput.is_finite() && throughput > 0.0);
+    }
+
+    #[test]
+    fn test_challenge_from_bytes() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
 
         let c = ZKP::generate_random_number_below(&q);
+        assert_eq!(zkp.challenge_from_bytes(&c.to_bytes_be()), Ok(c));
+
+        let max = &q - 1u32;
+        assert_eq!(zkp.challenge_from_bytes(&max.to_bytes_be()), Ok(max));
+
+        // q itself and anything wider than q are rejected, not reduced
+        assert_eq!(zkp.challenge_from_bytes(&q.to_bytes_be()), Err(ZKPError::ChallengeOutOfRange));
+        let oversized = vec![0xFF; q.to_bytes_be().len() + 1];
+        assert_eq!(zkp.challenge_from_bytes(&oversized), Err(ZKPError::ChallengeOutOfRange));
+    }
+
+    #[test]
+    fn test_committed_challenge() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let (c, nonce, commitment) = zkp.commit_challenge();
+        assert!(c < q);
+        assert!(zkp.verify_challenge_commitment(&commitment, &c, &nonce));
+
+        // the verifier cannot open the commitment to another c or with another nonce
+        assert!(!zkp.verify_challenge_commitment(&commitment, &(&c + 1u32), &nonce));
+        let mut other_nonce = nonce;
+        other_nonce[0] ^= 1;
+        assert!(!zkp.verify_challenge_commitment(&commitment, &c, &other_nonce));
+    }
+
+    #[test]
+    fn test_verify_proof_with_fiat_shamir() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
 
+        let x = ZKP::generate_random_number_below(&q);
         let (y1, y2) = zkp.compute_pair(&x);
-        assert_eq!(y1, BigUint::from(2u32));
-        assert_eq!(y2, BigUint::from(3u32));
-        assert_eq!(y1, BigUint::from(2u32));
-        assert_eq!(y2, BigUint::from(3u32));
+        let commitment = Commitment::new(y1, y2);
+
+        let proof = zkp.prove_non_interactive(&x, b"session 42");
+        assert!(zkp.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(b"session 42")));
+
+        // the proof is bound to its context
+        assert!(!zkp.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(b"session 43")));
 
+        // a c that is not the hash of the public values is rejected even if s answers it
+        let k = ZKP::generate_random_number_below(&q);
         let (r1, r2) = zkp.compute_pair(&k);
+        let c = ZKP::generate_random_number_below(&q);
+        let forged = Proof::new(r1, r2, c.clone(), zkp.solve(&k, &c, &x).unwrap());
+        assert!(!zkp.verify_proof(
+            &commitment,
+            &forged,
+            ChallengeSource::FiatShamir(b"session 42")
+        ));
 
-        let s = zkp.solve(&k, &c, &x);
+        // a proof for another secret does not verify against this commitment
+        let other = zkp.prove_non_interactive(&(&x + 1u32), b"session 42");
+        assert!(!zkp.verify_proof(&commitment, &other, ChallengeSource::FiatShamir(b"session 42")));
+    }
 
-        let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &s);
-        assert!(result);
+    #[test]
+    fn test_prover_with_external_nonce() {
+        // the toy run: k = 7 comes from outside, r1 = 8 and r2 = 4 are its commitments
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let x = BigUint::from(6u32);
+        let proof = toy.prove_non_interactive_with_nonce(&x, &BigUint::from(7u32), b"ctx");
+        assert_eq!((proof.r1.clone(), proof.r2.clone()), (8u32.into(), 4u32.into()));
+        let commitment = Commitment::new(2u32.into(), 3u32.into());
+        assert!(toy.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(b"ctx")));
+
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let commitment = Commitment::new(y1.clone(), y2.clone());
+        let k = zkp.generate_nonce();
+
+        let domain = Some(&b"test/v1/nonce"[..]);
+        let proof = zkp.prove_non_interactive_in_with_nonce(&x, k.expose(), domain, b"one");
+        assert_eq!((proof.r1.clone(), proof.r2.clone()), zkp.compute_pair(k.expose()));
+        let source = ChallengeSource::FiatShamirIn(b"test/v1/nonce", b"one");
+        assert!(zkp.verify_proof(&commitment, &proof, source));
+
+        let c = ZKP::generate_random_number_below(&q);
+        let ys = vec![zkp.compute_pair(&(&x + 1u32)), (y1, y2)];
+        let or = zkp.prove_or_with_nonce(&[None, Some(x.clone())], &ys, k.expose(), |_| c.clone());
+        assert_eq!(or.commitments[1], zkp.compute_pair(k.expose()));
+        assert!(zkp.verify_or(&ys, &or, &c));
+
+        let r = ZKP::generate_random_number_below(&q);
+        let k2 = zkp.generate_nonce();
+        let nonces = (k.expose(), k2.expose());
+        let opening = zkp.prove_pedersen_opening_with_nonce(&x, &r, nonces, |_| c.clone());
+        assert_eq!(opening.t, zkp.pedersen_commit(k.expose(), k2.expose()));
+        assert!(zkp.verify_pedersen_opening(&zkp.pedersen_commit(&x, &r), &opening, &c));
+
+        // why a nonce must never be reused: two proofs with the same k give x away,
+        // x = (s1 - s2) / (c2 - c1) mod q
+        let other = zkp.prove_non_interactive_in_with_nonce(&x, k.expose(), domain, b"two");
+        let dc = (&other.c + &q - &proof.c) % &q;
+        let ds = (&proof.s + &q - &other.s) % &q;
+        let leaked = ds * dc.modpow(&(&q - 2u32), &q) % &q;
+        assert_eq!(leaked, x);
+    }
+
+    #[test]
+    fn test_soundness_trial() {
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        assert_eq!(toy.soundness_trial(3, 200, false), 1.0);
+
+        // a cheater passes about 2^-bits of the time: 1/2 and 1/8 here, with a wide margin
+        let rate = toy.soundness_trial(1, 4000, true);
+        assert!((0.45..0.55).contains(&rate), "1-bit challenges: {rate}");
+        let rate = toy.soundness_trial(3, 4000, true);
+        assert!((0.09..0.16).contains(&rate), "3-bit challenges: {rate}");
+
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+        assert_eq!(zkp.soundness_trial(8, 20, false), 1.0);
+        assert!(zkp.soundness_trial(64, 20, true) == 0.0);
     }
 
     #[test]
@@ -192,7 +2455,7 @@ mod test {
         // alpha^i is also a generator
         let beta = alpha.modpow(&ZKP::generate_random_number_below(&q), &p);
 
-        let zkp = ZKP { p: p.clone(), q: q.clone(), alpha: alpha.clone(), beta: beta.clone() };
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
 
         let x = ZKP::generate_random_number_below(&q);
 
@@ -206,7 +2469,7 @@ mod test {
         // assert_eq!(y2, BigUint::from(3u32));
         let (r1, r2) = zkp.compute_pair(&k);
 
-        let s = zkp.solve(&k, &c, &x);
+        let s = zkp.solve(&k, &c, &x).unwrap();
 
         let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &s);
         assert!(result);
@@ -227,7 +2490,7 @@ mod test {
         // beta = alpha^i is also a generator
         let beta = alpha.modpow(&ZKP::generate_random_number_below(&q), &p);
 
-        let zkp = ZKP { p: p.clone(), q: q.clone(), alpha: alpha.clone(), beta: beta.clone() };
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
 
         let x = ZKP::generate_random_number_below(&q);
         let k = ZKP::generate_random_number_below(&q);
@@ -237,9 +2500,191 @@ mod test {
         let (y1, y2) = zkp.compute_pair(&x);
         let (r1, r2) = zkp.compute_pair(&k);
 
-        let s = zkp.solve(&k, &c, &x);
+        let s = zkp.solve(&k, &c, &x).unwrap();
 
         let result = zkp.verify(&r1, &r2, &y1, &y2, &c, &s);
         assert!(result);
     }
+
+    #[test]
+    fn test_secure_id_minimum_length() {
+        for length in [0, 2, 12, MIN_ID_LENGTH - 1] {
+            assert_eq!(ZKP::generate_secure_id(length), Err(ZKPError::IdTooShort(length)));
+        }
+        let id = ZKP::generate_secure_id(MIN_ID_LENGTH).unwrap();
+        assert_eq!(id.len(), MIN_ID_LENGTH);
+        assert!(id.bytes().all(|b| b.is_ascii_alphanumeric()));
+        assert_eq!(ZKP::generate_secure_id(32).unwrap().len(), 32);
+        assert_eq!(ZKP::generate_session_id().len(), MIN_ID_LENGTH);
+
+        // the general-purpose one takes any size
+        assert_eq!(ZKP::generate_random_string(2).len(), 2);
+    }
+
+    #[test]
+    fn test_readable_id() {
+        for bits in [1, 5, 60, 128] {
+            let id = ZKP::generate_readable_id(bits);
+            assert!(id.bytes().all(|b| CROCKFORD_ALPHABET.contains(&b)));
+            assert!(id.len() as u32 * 5 >= bits);
+            assert!((id.len() as u32 - 1) * 5 < bits);
+        }
+        assert!(!CROCKFORD_ALPHABET.iter().any(|b| b"ILOU".contains(b)));
+
+        let ids: std::collections::HashSet<_> =
+            (0..100).map(|_| ZKP::generate_readable_id(60)).collect();
+        assert_eq!(ids.len(), 100);
+
+        assert_eq!(ZKP::generate_random_string_from(b"ab", 8).len(), 8);
+        assert!(ZKP::generate_random_string_from(b"ab", 8).chars().all(|c| c == 'a' || c == 'b'));
+    }
+
+    #[test]
+    fn test_stateless_challenge() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let secret = b"server secret";
+        let ttl = Duration::from_secs(60);
+
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        let commitment = Commitment::new(y1, y2);
+        let (r1, r2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+
+        // fresh: recomputed from the same values, accepted
+        let now = ZKP::unix_timestamp();
+        let c = zkp.stateless_challenge(secret, &commitment, &r1, &r2, now);
+        assert!(c < q);
+        assert!(zkp.check_stateless_challenge(secret, &commitment, &r1, &r2, now, &c, ttl));
+        // another secret or another timestamp gives another c
+        assert!(!zkp.check_stateless_challenge(b"other", &commitment, &r1, &r2, now, &c, ttl));
+        assert!(!zkp.check_stateless_challenge(secret, &commitment, &r1, &r2, now - 1, &c, ttl));
+
+        // stale: the right c, but issued more than ttl ago
+        let old = now - 61;
+        let c = zkp.stateless_challenge(secret, &commitment, &r1, &r2, old);
+        assert!(!zkp.check_stateless_challenge(secret, &commitment, &r1, &r2, old, &c, ttl));
+
+        // and not from the future
+        assert!(!ZKP::timestamp_is_fresh(now + 3600, ttl));
+    }
+
+    #[test]
+    fn test_generate_nonzero_below() {
+        let bound = BigUint::from(11u32);
+        let drawn: std::collections::HashSet<BigUint> =
+            (0..1000).map(|_| ZKP::generate_nonzero_below(&bound)).collect();
+        assert!(!drawn.contains(&BigUint::ZERO));
+        assert_eq!(drawn.len(), 10);
+        assert_eq!(ZKP::generate_nonzero_below(&BigUint::from(2u32)), BigUint::from(1u32));
+    }
+
+    #[test]
+    fn test_sample_below_and_hash_to_range_are_uniform() {
+        // 11 buckets (the toy q: 4 bits, so 5 of every 16 raw draws are rejected), 22 000 draws;
+        // chi-square with 10 degrees of freedom stays under 35 except once in ~10 000 runs
+        let bound = BigUint::from(11u32);
+        let chi_square = |draw: &mut dyn FnMut(u32) -> BigUint| {
+            let mut counts = [0u32; 11];
+            for i in 0..22_000 {
+                let value = draw(i);
+                assert!(value < bound);
+                counts[value.to_u32_digits().first().copied().unwrap_or(0) as usize] += 1;
+            }
+            counts.iter().map(|&n| (n as f64 - 2000.0).powi(2) / 2000.0).sum::<f64>()
+        };
+
+        assert!(chi_square(&mut |_| ZKP::sample_below(&bound)) < 35.0);
+        assert!(chi_square(&mut |i| ZKP::hash_to_range(&i.to_be_bytes(), &bound)) < 35.0);
+
+        // deterministic, and fine at the edges
+        assert_eq!(ZKP::hash_to_range(b"x", &bound), ZKP::hash_to_range(b"x", &bound));
+        assert_eq!(ZKP::sample_below(&BigUint::from(1u32)), BigUint::from(0u32));
+        let q = ZKP::get_constants().3;
+        assert!(ZKP::hash_to_range(b"x", &q) < q);
+        let wide = BigUint::from(1u32) << 1000;
+        assert!(ZKP::hash_to_range(b"x", &wide).bits() > 900);
+    }
+
+    #[test]
+    fn test_verify_with_generator() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
+
+        // a client on alpha' = alpha^t, beta' = beta^t
+        let t = BigUint::from(3u32);
+        let (alpha_t, beta_t) = (powm(&alpha, &t, &p), powm(&beta, &t, &p));
+        let client = ZKP::new(alpha_t.clone(), beta_t.clone(), p.clone(), q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = client.compute_pair(&x);
+        let (r1, r2) = client.compute_pair(&k);
+        let s = client.solve(&k, &c, &x).unwrap();
+
+        assert!(!zkp.verify(&r1, &r2, &y1, &y2, &c, &s));
+        assert_eq!(
+            zkp.verify_with_generator(&alpha_t, &beta_t, &r1, &r2, &y1, &y2, &c, &s),
+            Ok(true)
+        );
+        // the override is only what it says: the struct's own pair still rejects it
+        assert_eq!(zkp.verify_with_generator(&alpha, &beta, &r1, &r2, &y1, &y2, &c, &s), Ok(false));
+
+        // 1 and p - 1 are no generators of the order-q subgroup
+        let one = BigUint::from(1u32);
+        let minus_one = &p - 1u32;
+        for bad in [&one, &minus_one] {
+            let result = zkp.verify_with_generator(bad, &beta_t, &r1, &r2, &y1, &y2, &c, &s);
+            assert_eq!(result, Err(ZKPError::GeneratorNotInSubgroup));
+        }
+    }
+
+    #[test]
+    fn test_domain_separated_challenges() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        // the same transcript under two domains
+        let transcript = b"y1 y2 r1 r2";
+        let login = zkp.domain_challenge(b"myapp/v1/login", transcript);
+        assert!(login < q);
+        assert_eq!(login, zkp.domain_challenge(b"myapp/v1/login", transcript));
+        assert_ne!(login, zkp.domain_challenge(b"myapp/v1/signup", transcript));
+        assert_ne!(
+            zkp.domain_challenge(b"ab", b"c"),
+            zkp.domain_challenge(b"a", b"bc"),
+            "the tag is length-prefixed"
+        );
+
+        // a proof made under one domain only verifies under that domain
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let commitment = Commitment::new(y1, y2);
+        let proof = zkp.prove_non_interactive_in(&x, Some(b"myapp/v1/login"), b"session 42");
+        let under = |domain: &'static [u8]| ChallengeSource::FiatShamirIn(domain, b"session 42");
+        assert!(zkp.verify_proof(&commitment, &proof, under(b"myapp/v1/login")));
+        assert!(!zkp.verify_proof(&commitment, &proof, under(b"myapp/v1/signup")));
+        assert!(!zkp.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(b"session 42")));
+
+        let untagged = zkp.prove_non_interactive_in(&x, None, b"session 42");
+        assert!(zkp.verify_proof(
+            &commitment,
+            &untagged,
+            ChallengeSource::FiatShamir(b"session 42")
+        ));
+    }
+
+    #[test]
+    fn test_bind_challenge() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let c = ZKP::generate_random_number_below(&q);
+
+        assert_eq!(zkp.bind_challenge(&c, b""), c);
+        let bound = zkp.bind_challenge(&c, b"channel A");
+        assert!(bound < q);
+        assert_ne!(bound, c);
+        assert_ne!(bound, zkp.bind_challenge(&c, b"channel B"));
+        assert_eq!(bound, zkp.bind_challenge(&c, b"channel A"));
+    }
 }
@@ -0,0 +1,220 @@
+// server loadtest: registers N users on a running server (ZKP_ADDR) and logs them in over and
+// over through AuthClient, the end-to-end counterpart of the pure-crypto criterion benches
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use num_bigint::BigUint;
+use tonic::Status;
+use zkp_chaum_pedersen::{
+    AuthClient, ZKP, solve_registration_pow,
+    zkp_auth::{AuthenticationAnswerRequest, AuthenticationChallengeRequest, RegisterRequest},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadtestArgs {
+    pub users: usize,
+    pub concurrency: usize,
+    pub duration: Duration,
+}
+
+impl Default for LoadtestArgs {
+    fn default() -> Self {
+        LoadtestArgs { users: 100, concurrency: 8, duration: Duration::from_secs(10) }
+    }
+}
+
+const USAGE: &str = "usage: server loadtest [--users N] [--concurrency C] [--duration SECS]";
+
+pub fn parse_args(args: &[String]) -> Result<LoadtestArgs, String> {
+    let mut parsed = LoadtestArgs::default();
+    for pair in args.chunks(2) {
+        let [flag, value] = pair else {
+            return Err(USAGE.to_string());
+        };
+        let number = |what: &str| {
+            value
+                .trim_end_matches('s')
+                .parse::<u64>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or(format!("{what} must be a positive number"))
+        };
+        match flag.as_str() {
+            "--users" => parsed.users = number("--users")? as usize,
+            "--concurrency" => parsed.concurrency = number("--concurrency")? as usize,
+            "--duration" => parsed.duration = Duration::from_secs(number("--duration")?),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(parsed)
+}
+
+// nearest-rank percentile of sorted latencies, zero when there are none
+pub fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// one full login: challenge for a fresh k, then the answer
+async fn login(
+    client: &AuthClient,
+    (zkp, q): &(ZKP, BigUint),
+    user: &str,
+    x: &BigUint,
+) -> Result<(), Status> {
+    let k = ZKP::generate_random_number_below(q);
+    let (r1, r2) = zkp.compute_pair(&k);
+    let challenge = client
+        .create_authentication_challenge(AuthenticationChallengeRequest {
+            user: user.to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        })
+        .await?;
+    let c = zkp
+        .challenge_from_bytes(&challenge.c)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let s = zkp.solve(&k, &c, x).map_err(|e| Status::invalid_argument(e.to_string()))?;
+    client
+        .verify_authentication(AuthenticationAnswerRequest {
+            auth_id: challenge.auth_id,
+            s: s.to_bytes_be(),
+            binding: Vec::new(),
+        })
+        .await?;
+    Ok(())
+}
+
+pub async fn run(args: &[String]) {
+    let args = parse_args(args).unwrap_or_else(|e| panic!("{e}"));
+    let addr = std::env::var("ZKP_ADDR").unwrap_or_else(|_| "127.0.0.1:50051".to_string());
+    // same variable as the server, registrations must carry the proof of work it asks for
+    let pow_bits = std::env::var("ZKP_REGISTRATION_POW_BITS")
+        .map(|bits| bits.parse().expect("ZKP_REGISTRATION_POW_BITS must be a number"))
+        .unwrap_or(0);
+
+    let client = AuthClient::builder()
+        .connect(&format!("http://{addr}"))
+        .expect("could not connect to the server");
+    let (alpha, beta, p, q) = ZKP::default_constants();
+    // (the ZKP keeps q private, login needs it for k)
+    let group = Arc::new((ZKP::new(alpha, beta, p, q.clone()), q.clone()));
+
+    // fresh names on every run, so a server that saw an earlier run does not refuse them
+    let run_id = ZKP::generate_random_string(6);
+    let users: Arc<Vec<(String, BigUint)>> = Arc::new(
+        (0..args.users)
+            .map(|i| (format!("loadtest-{run_id}-{i}"), ZKP::generate_random_number_below(&q)))
+            .collect(),
+    );
+
+    let started = Instant::now();
+    let mut registrations = Vec::new();
+    for worker in 0..args.concurrency {
+        let (client, group, users) = (client.clone(), group.clone(), users.clone());
+        registrations.push(tokio::spawn(async move {
+            let zkp = &group.0;
+            for (user, x) in users.iter().skip(worker).step_by(args.concurrency) {
+                let (y1, y2) = zkp.compute_pair(x);
+                let request = RegisterRequest {
+                    user: user.clone(),
+                    y1: y1.to_bytes_be(),
+                    y2: y2.to_bytes_be(),
+                    group_id: zkp.fingerprint().to_vec(),
+                    pow_nonce: solve_registration_pow(user, pow_bits),
+                };
+                client.register(request).await.expect("could not register a loadtest user");
+            }
+        }));
+    }
+    for registration in registrations {
+        registration.await.expect("registration task panicked");
+    }
+    println!("✅ Registered {} users in {:.2?}", args.users, started.elapsed());
+
+    // every worker logs in round robin over the users until the deadline
+    let started = Instant::now();
+    let deadline = started + args.duration;
+    let mut workers = Vec::new();
+    for worker in 0..args.concurrency {
+        let (client, group, users) = (client.clone(), group.clone(), users.clone());
+        workers.push(tokio::spawn(async move {
+            let (mut latencies, mut failures) = (Vec::new(), 0usize);
+            let mut next = worker;
+            while Instant::now() < deadline {
+                let (user, x) = &users[next % users.len()];
+                let login_started = Instant::now();
+                match login(&client, &group, user, x).await {
+                    Ok(()) => latencies.push(login_started.elapsed()),
+                    Err(_) => failures += 1,
+                }
+                next += args.concurrency;
+            }
+            (latencies, failures)
+        }));
+    }
+
+    let (mut latencies, mut failures) = (Vec::new(), 0);
+    for worker in workers {
+        let (worker_latencies, worker_failures) = worker.await.expect("login task panicked");
+        latencies.extend(worker_latencies);
+        failures += worker_failures;
+    }
+    let elapsed = started.elapsed();
+    latencies.sort();
+
+    println!(
+        "✅ {} logins ({failures} failed) in {elapsed:.2?} with {} concurrent clients: {:.1} logins/s",
+        latencies.len(),
+        args.concurrency,
+        latencies.len() as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "   latency p50 {:.2?}, p95 {:.2?}, p99 {:.2?}",
+        percentile(&latencies, 50.0),
+        percentile(&latencies, 95.0),
+        percentile(&latencies, 99.0)
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]), Ok(LoadtestArgs::default()));
+        let parsed =
+            parse_args(&args(&["--users", "50", "--concurrency", "4", "--duration", "30s"]));
+        assert_eq!(
+            parsed,
+            Ok(LoadtestArgs { users: 50, concurrency: 4, duration: Duration::from_secs(30) })
+        );
+
+        assert!(parse_args(&args(&["--users"])).is_err());
+        assert!(parse_args(&args(&["--users", "0"])).is_err());
+        assert!(parse_args(&args(&["--threads", "4"])).is_err());
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&latencies, 95.0), Duration::from_millis(95));
+        assert_eq!(percentile(&latencies, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&latencies[..1], 99.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+}
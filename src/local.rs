@@ -0,0 +1,101 @@
+use num_bigint::BigUint;
+
+use crate::{Commitment, Secret, StreamVerdict, StreamingVerifier, TranscriptMessage, ZKP};
+
+// Both roles of an interactive run in one process, no network (local simulations, a layer
+// caching proofs...): the prover holds x, the verifier only the registration (y1, y2) and
+// checks the messages the prover sends it with a StreamingVerifier
+#[derive(Debug)]
+pub struct LocalSession {
+    zkp: ZKP,
+    x: Secret,
+    registration: Commitment,
+}
+
+impl LocalSession {
+    // panics unless x is in [1, q)
+    pub fn new(zkp: ZKP, x: Secret) -> Self {
+        assert!(
+            *x.expose() != BigUint::ZERO && zkp.is_scalar(x.expose()),
+            "the secret must be in [1, q)"
+        );
+        let (y1, y2) = zkp.compute_pair(x.expose());
+        LocalSession { zkp, x, registration: Commitment::new(y1, y2) }
+    }
+
+    // with a fresh random x
+    pub fn random(zkp: ZKP) -> Self {
+        let x = Secret::new(ZKP::generate_nonzero_below(&zkp.q));
+        LocalSession::new(zkp, x)
+    }
+
+    pub fn zkp(&self) -> &ZKP {
+        &self.zkp
+    }
+
+    // what the verifier knows of the prover
+    pub fn registration(&self) -> &Commitment {
+        &self.registration
+    }
+
+    // one honest exchange with a fresh k and a fresh c: the verdict and the messages in the
+    // order they went (Commitment, Challenge, Response)
+    pub fn run(&self) -> (bool, Vec<TranscriptMessage>) {
+        let mut verifier = StreamingVerifier::new(&self.zkp, &self.registration);
+        let mut transcript = Vec::with_capacity(3);
+
+        // prover
+        let k = self.zkp.generate_nonce();
+        let (r1, r2) = self.zkp.compute_pair(k.expose());
+        let commitment = TranscriptMessage::Commitment { r1, r2 };
+        verifier.feed(commitment.clone());
+        transcript.push(commitment);
+
+        // verifier
+        let c = self.zkp.generate_challenge();
+        verifier.feed(TranscriptMessage::Challenge(c.clone()));
+        transcript.push(TranscriptMessage::Challenge(c.clone()));
+
+        // prover
+        let s = self
+            .zkp
+            .solve(k.expose(), &c, self.x.expose())
+            .expect("x is checked by new, k and c are drawn below q");
+        let verdict = verifier.feed(TranscriptMessage::Response(s.clone()));
+        transcript.push(TranscriptMessage::Response(s));
+
+        (verdict == StreamVerdict::Valid, transcript)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_local_session() {
+        // x = 6 in the toy group: y1 = 2, y2 = 3
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let session = LocalSession::new(toy, Secret::new(6u32.into()));
+        assert_eq!(session.registration(), &Commitment::new(2u32.into(), 3u32.into()));
+        for _ in 0..20 {
+            let (verified, transcript) = session.run();
+            assert!(verified);
+            // the transcript checks out on its own
+            let mut verifier = StreamingVerifier::new(session.zkp(), session.registration());
+            assert_eq!(verifier.feed_all(transcript), StreamVerdict::Valid);
+        }
+
+        let session = LocalSession::random(ZKP::default());
+        let (verified, transcript) = session.run();
+        assert!(verified);
+        assert!(matches!(
+            transcript.as_slice(),
+            [
+                TranscriptMessage::Commitment { .. },
+                TranscriptMessage::Challenge(_),
+                TranscriptMessage::Response(_)
+            ]
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+// Latency histograms of the RPCs by operation (register, challenge, verify) and group size
+// (bits of p), in the Prometheus text format: GET /metrics on ZKP_METRICS_ADDR
+use std::{
+    collections::BTreeMap, fmt::Write, net::SocketAddr, sync::Arc, sync::Mutex, time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::AuthImpl;
+
+// upper bounds in seconds, from a 1024-bit verification on a fast core to a slow 3072-bit one
+pub const BUCKETS: [f64; 12] =
+    [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    // observations per bucket (not cumulative), the last one past the largest bound
+    counts: [u64; BUCKETS.len() + 1],
+    sum: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    // (operation, group bits) -> histogram, sorted so the output is stable
+    histograms: Mutex<BTreeMap<(&'static str, u64), Histogram>>,
+}
+
+impl Metrics {
+    pub fn observe(&self, operation: &'static str, group_bits: u64, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = BUCKETS.iter().position(|&bound| seconds <= bound).unwrap_or(BUCKETS.len());
+        let histograms = &mut self.histograms.lock().unwrap();
+        let histogram = histograms.entry((operation, group_bits)).or_default();
+        histogram.counts[bucket] += 1;
+        histogram.sum += seconds;
+    }
+
+    // every histogram with cumulative buckets, then _sum and _count
+    pub fn render(&self) -> String {
+        let mut out = String::from(
+            "# HELP zkp_rpc_duration_seconds Latency of the RPCs by operation and group size\n\
+             # TYPE zkp_rpc_duration_seconds histogram\n",
+        );
+        let histograms = self.histograms.lock().unwrap();
+        for ((operation, bits), histogram) in histograms.iter() {
+            let labels = format!("operation=\"{operation}\",group_bits=\"{bits}\"");
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(histogram.counts) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "zkp_rpc_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let total: u64 = histogram.counts.iter().sum();
+            let _ =
+                writeln!(out, "zkp_rpc_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {total}");
+            let _ = writeln!(out, "zkp_rpc_duration_seconds_sum{{{labels}}} {}", histogram.sum);
+            let _ = writeln!(out, "zkp_rpc_duration_seconds_count{{{labels}}} {total}");
+        }
+        out
+    }
+}
+
+// just enough HTTP/1.1 for a scraper: GET /metrics, anything else is a 404
+pub async fn serve(addr: SocketAddr, auth: Arc<AuthImpl>) {
+    let listener = TcpListener::bind(addr).await.expect("could not bind ZKP_METRICS_ADDR");
+    println!("✅ Serving metrics in http://{addr}/metrics");
+
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let auth = auth.clone();
+        tokio::spawn(async move {
+            // the request line is all that matters, it comes in the first read
+            let mut request = [0u8; 1024];
+            let Ok(read) = stream.read(&mut request).await else {
+                return;
+            };
+            let response = if request[..read].starts_with(b"GET /metrics ") {
+                let body = auth.metrics.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/plain; version=0.0.4\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    .to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_cumulative_buckets() {
+        let metrics = Metrics::default();
+        metrics.observe("verify", 1024, Duration::from_millis(3));
+        metrics.observe("verify", 1024, Duration::from_millis(30));
+        metrics.observe("verify", 1024, Duration::from_secs(10));
+
+        let text = metrics.render();
+        let series = |le: &str| {
+            format!(
+                "zkp_rpc_duration_seconds_bucket{{operation=\"verify\",group_bits=\"1024\",le=\"{le}\"}}"
+            )
+        };
+        assert!(text.contains(&format!("{} 0\n", series("0.0025"))));
+        assert!(text.contains(&format!("{} 1\n", series("0.005"))));
+        assert!(text.contains(&format!("{} 2\n", series("0.05"))));
+        assert!(text.contains(&format!("{} 2\n", series("2.5"))));
+        assert!(text.contains(&format!("{} 3\n", series("+Inf"))));
+        assert!(text.contains(
+            "zkp_rpc_duration_seconds_count{operation=\"verify\",group_bits=\"1024\"} 3\n"
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+
+// Proof of work attached to a registration: a nonce such that
+// Argon2id(username || nonce) starts with at least `bits` zero bits.
+// Argon2 (memory-hard) rather than SHA-256 so that GPUs do not make mass registration cheap,
+// with small parameters since the client evaluates it ~2^bits times
+const POW_SALT: &[u8] = b"zkp_auth registration pow";
+const POW_MEMORY_KIB: u32 = 64;
+
+fn argon2() -> Argon2<'static> {
+    let params = Params::new(POW_MEMORY_KIB, 1, 1, Some(32)).expect("valid argon2 parameters");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+//the nonce is a fixed 8 bytes (big-endian) after the username, so no two (username, nonce)
+//pairs hash the same input
+pub fn registration_pow_hash(user: &str, nonce: u64) -> [u8; 32] {
+    let mut input = user.as_bytes().to_vec();
+    input.extend_from_slice(&nonce.to_be_bytes());
+
+    let mut hash = [0u8; 32];
+    argon2().hash_password_into(&input, POW_SALT, &mut hash).expect("32-byte argon2 output");
+    hash
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+//server side: 0 bits means no proof of work is required
+pub fn check_registration_pow(user: &str, nonce: u64, bits: u32) -> bool {
+    bits == 0 || leading_zero_bits(&registration_pow_hash(user, nonce)) >= bits
+}
+
+//client side: tries nonces from 0 until one is good enough, ~2^bits hashes on average
+pub fn solve_registration_pow(user: &str, bits: u32) -> u64 {
+    (0..).find(|nonce| check_registration_pow(user, *nonce, bits)).expect("a nonce below 2^64")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xFF]), 0);
+        assert_eq!(leading_zero_bits(&[0x00, 0x10]), 11);
+        assert_eq!(leading_zero_bits(&[0x00, 0x00]), 16);
+    }
+
+    #[test]
+    fn test_solve_and_check_registration_pow() {
+        let nonce = solve_registration_pow("alice", 6);
+        assert!(check_registration_pow("alice", nonce, 6));
+        assert!(leading_zero_bits(&registration_pow_hash("alice", nonce)) >= 6);
+
+        // nothing is required with 0 bits
+        assert!(check_registration_pow("alice", 12345, 0));
+    }
+}
@@ -0,0 +1,265 @@
+use num_bigint::BigUint;
+use std::fmt;
+
+use crate::ZKP;
+#[cfg(feature = "cbor")]
+use crate::ZKPError;
+
+// Where verify_proof takes the challenge from
+pub enum ChallengeSource<'a> {
+    // interactive protocol: the c the verifier sent to the prover
+    Given(&'a BigUint),
+    // non-interactive protocol: c is recomputed from the public values and this context
+    // (see ZKP::fiat_shamir_challenge)
+    FiatShamir(&'a [u8]),
+    // the same under a domain separation tag: (domain, context), see ZKP::fiat_shamir_challenge_in
+    FiatShamirIn(&'a [u8], &'a [u8]),
+}
+
+// Public commitment a user registers: y1 = alpha^x mod p, y2 = beta^x mod p
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Commitment {
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_biguint"))]
+    pub y1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_biguint"))]
+    pub y2: BigUint,
+}
+
+impl Commitment {
+    pub fn new(y1: BigUint, y2: BigUint) -> Self {
+        Commitment { y1, y2 }
+    }
+
+    //Display view with every value zero-padded to the group size, plus the group fingerprint
+    pub fn with_group<'a>(&'a self, zkp: &'a ZKP) -> WithGroup<'a, Commitment> {
+        WithGroup { value: self, zkp }
+    }
+}
+
+// One run of the protocol, as seen by the verifier:
+// r1 = alpha^k mod p, r2 = beta^k mod p (commitment), c (challenge), s = k - c * x mod q (response)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Proof {
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_biguint"))]
+    pub r1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_biguint"))]
+    pub r2: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_biguint"))]
+    pub c: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_biguint"))]
+    pub s: BigUint,
+}
+
+impl Proof {
+    pub fn new(r1: BigUint, r2: BigUint, c: BigUint, s: BigUint) -> Self {
+        Proof { r1, r2, c, s }
+    }
+
+    //Display view with every value zero-padded to the group size, plus the group fingerprint
+    pub fn with_group<'a>(&'a self, zkp: &'a ZKP) -> WithGroup<'a, Proof> {
+        WithGroup { value: self, zkp }
+    }
+}
+
+// Human-friendly views: upper-case hex instead of the (huge) decimal of Debug
+impl fmt::Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "y1={:X} y2={:X}", self.y1, self.y2)
+    }
+}
+
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "r1={:X} r2={:X} c={:X} s={:X}", self.r1, self.r2, self.c, self.s)
+    }
+}
+
+// A value displayed in the context of its group (see Proof::with_group / Commitment::with_group)
+// group elements are padded to the byte size of p, scalars (c, s) to the byte size of q
+pub struct WithGroup<'a, T> {
+    value: &'a T,
+    zkp: &'a ZKP,
+}
+
+impl<T> WithGroup<'_, T> {
+    // (element width, scalar width) in hex digits
+    fn widths(&self) -> (usize, usize) {
+        (self.zkp.element_byte_len() * 2, self.zkp.scalar_byte_len() * 2)
+    }
+}
+
+impl fmt::Display for WithGroup<'_, Commitment> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (element, _) = self.widths();
+        let group = hex::encode_upper(self.zkp.fingerprint());
+        let Commitment { y1, y2 } = self.value;
+        write!(f, "group={group} y1={y1:0element$X} y2={y2:0element$X}")
+    }
+}
+
+impl fmt::Display for WithGroup<'_, Proof> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (element, scalar) = self.widths();
+        let group = hex::encode_upper(self.zkp.fingerprint());
+        let Proof { r1, r2, c, s } = self.value;
+        write!(
+            f,
+            "group={group} r1={r1:0element$X} r2={r2:0element$X} c={c:0scalar$X} s={s:0scalar$X}"
+        )
+    }
+}
+
+// CBOR layout of a proof: raw big-endian bytes (no hex) plus the fingerprint of its group
+#[cfg(feature = "cbor")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CborProof {
+    #[serde(with = "serde_bytes")]
+    group: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    r1: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    r2: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    c: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    s: Vec<u8>,
+}
+
+#[cfg(feature = "cbor")]
+impl Proof {
+    //most compact self-describing form (e.g. to embed a proof in a QR code)
+    //the group fingerprint travels with the proof so a reader can check it is the expected group
+    pub fn to_cbor(&self, zkp: &ZKP) -> Vec<u8> {
+        let cbor = CborProof {
+            group: zkp.fingerprint().to_vec(),
+            r1: self.r1.to_bytes_be(),
+            r2: self.r2.to_bytes_be(),
+            c: self.c.to_bytes_be(),
+            s: self.s.to_bytes_be(),
+        };
+
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&cbor, &mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    //decodes a proof written by to_cbor, failing if it was made for a group other than zkp's
+    pub fn from_cbor(bytes: &[u8], zkp: &ZKP) -> Result<Proof, ZKPError> {
+        let cbor: CborProof =
+            ciborium::from_reader(bytes).map_err(|e| ZKPError::MalformedEncoding(e.to_string()))?;
+
+        if cbor.group != zkp.fingerprint() {
+            return Err(ZKPError::GroupMismatch);
+        }
+
+        Ok(Proof {
+            r1: BigUint::from_bytes_be(&cbor.r1),
+            r2: BigUint::from_bytes_be(&cbor.r2),
+            c: BigUint::from_bytes_be(&cbor.c),
+            s: BigUint::from_bytes_be(&cbor.s),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn toy_group() -> ZKP {
+        ZKP::new(
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+            BigUint::from(23u32),
+            BigUint::from(11u32),
+        )
+    }
+
+    #[test]
+    fn test_display_is_hex_padded_to_the_group() {
+        let toy = toy_group();
+        let group = hex::encode_upper(toy.fingerprint());
+
+        // toy example: x = 6, k = 7, c = 4
+        let commitment = Commitment::new(BigUint::from(2u32), BigUint::from(3u32));
+        let proof = Proof::new(
+            BigUint::from(8u32),
+            BigUint::from(4u32),
+            BigUint::from(4u32),
+            BigUint::from(5u32),
+        );
+
+        assert_eq!(commitment.to_string(), "y1=2 y2=3");
+        assert_eq!(proof.to_string(), "r1=8 r2=4 c=4 s=5");
+        assert_eq!(commitment.with_group(&toy).to_string(), format!("group={group} y1=02 y2=03"));
+        assert_eq!(
+            proof.with_group(&toy).to_string(),
+            format!("group={group} r1=08 r2=04 c=04 s=05")
+        );
+
+        // 1024-bit group: elements take 256 hex digits, scalars 40
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+        let shown = proof.with_group(&zkp).to_string();
+        assert!(shown.contains(&format!(" r1={:0>256} ", "8")));
+        assert!(shown.ends_with(&format!(" s={:0>40}", "5")));
+    }
+
+    #[cfg(all(feature = "serde", feature = "prover"))]
+    fn honest_proof(zkp: &ZKP, q: &BigUint) -> Proof {
+        let x = ZKP::generate_random_number_below(q);
+        let k = ZKP::generate_random_number_below(q);
+        let c = ZKP::generate_random_number_below(q);
+
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        Proof::new(r1, r2, c, s)
+    }
+
+    #[cfg(all(feature = "cbor", feature = "prover"))]
+    #[test]
+    fn test_cbor_round_trip_and_size() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let proof = honest_proof(&zkp, &q);
+
+        let cbor = proof.to_cbor(&zkp);
+        assert_eq!(Proof::from_cbor(&cbor, &zkp).unwrap(), proof);
+
+        // even carrying the 32-byte fingerprint, CBOR is much smaller than hex JSON
+        let json = serde_json::to_vec(&proof).unwrap();
+        assert!(
+            cbor.len() < json.len() * 2 / 3,
+            "cbor: {} bytes, json: {} bytes",
+            cbor.len(),
+            json.len()
+        );
+    }
+
+    #[cfg(all(feature = "cbor", feature = "prover"))]
+    #[test]
+    fn test_cbor_rejects_other_group_and_garbage() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let toy = toy_group();
+
+        let cbor = honest_proof(&zkp, &q).to_cbor(&zkp);
+        assert_eq!(Proof::from_cbor(&cbor, &toy), Err(ZKPError::GroupMismatch));
+        assert!(matches!(
+            Proof::from_cbor(&cbor[..cbor.len() / 2], &zkp),
+            Err(ZKPError::MalformedEncoding(_))
+        ));
+    }
+
+    #[cfg(all(feature = "serde", feature = "prover"))]
+    #[test]
+    fn test_json_round_trip() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let proof = honest_proof(&zkp, &q);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<Proof>(&json).unwrap(), proof);
+    }
+}
@@ -0,0 +1,97 @@
+// Signed verification receipts (VerificationReceipt of the proto): the server proves it knows
+// its receipt key with a Fiat-Shamir proof whose context is the receipt itself, so the proof
+// only verifies for these exact fields. Anyone with the public key can check a receipt offline
+use num_bigint::BigUint;
+
+#[cfg(feature = "prover")]
+use crate::Secret;
+use crate::{ChallengeSource, Commitment, Proof, ZKP, zkp_auth::VerificationReceipt};
+
+// domain tag of the receipt signatures, no login proof can pass for one
+pub const RECEIPT_DOMAIN: &[u8] = b"zkp_auth/v1/receipt";
+
+// what is signed: the fields 1-6, each one length-prefixed so no two receipts share an encoding
+pub fn receipt_message(receipt: &VerificationReceipt) -> Vec<u8> {
+    let fields: [&[u8]; 6] = [
+        receipt.auth_id.as_bytes(),
+        receipt.user.as_bytes(),
+        &receipt.c,
+        &receipt.s,
+        &[receipt.valid as u8],
+        &receipt.timestamp.to_be_bytes(),
+    ];
+    let mut message = Vec::new();
+    for field in fields {
+        message.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        message.extend_from_slice(field);
+    }
+    message
+}
+
+// The server side: the receipt key and its public half in the default group
+#[cfg(feature = "prover")]
+#[derive(Debug)]
+pub struct ReceiptSigner {
+    zkp: ZKP,
+    key: Secret,
+    public_key: Commitment,
+}
+
+#[cfg(feature = "prover")]
+impl ReceiptSigner {
+    pub fn new(key: Secret) -> Self {
+        let zkp = ZKP::default();
+        let (y1, y2) = zkp.compute_pair(key.expose());
+        ReceiptSigner { zkp, key, public_key: Commitment::new(y1, y2) }
+    }
+
+    // what clients check receipts against (receipt_key_y1/y2 of get_parameters)
+    pub fn public_key(&self) -> &Commitment {
+        &self.public_key
+    }
+
+    // receipt of the verification of s for the challenge c, stamped now
+    pub fn sign(
+        &self,
+        auth_id: &str,
+        user: &str,
+        c: &BigUint,
+        s: &BigUint,
+        valid: bool,
+    ) -> VerificationReceipt {
+        let mut receipt = VerificationReceipt {
+            auth_id: auth_id.to_string(),
+            user: user.to_string(),
+            c: c.to_bytes_be(),
+            s: s.to_bytes_be(),
+            valid,
+            timestamp: ZKP::unix_timestamp(),
+            ..Default::default()
+        };
+        let signature = self.zkp.prove_non_interactive_in(
+            self.key.expose(),
+            Some(RECEIPT_DOMAIN),
+            &receipt_message(&receipt),
+        );
+        receipt.sig_r1 = signature.r1.to_bytes_be();
+        receipt.sig_r2 = signature.r2.to_bytes_be();
+        receipt.sig_c = signature.c.to_bytes_be();
+        receipt.sig_s = signature.s.to_bytes_be();
+        receipt
+    }
+}
+
+impl ZKP {
+    //true if receipt was signed with the receipt key whose public half is key (in this group,
+    //the default one for receipts of the server) and none of its fields changed since
+    pub fn verify_receipt(&self, key: &Commitment, receipt: &VerificationReceipt) -> bool {
+        let signature = Proof::new(
+            BigUint::from_bytes_be(&receipt.sig_r1),
+            BigUint::from_bytes_be(&receipt.sig_r2),
+            BigUint::from_bytes_be(&receipt.sig_c),
+            BigUint::from_bytes_be(&receipt.sig_s),
+        );
+        let message = receipt_message(receipt);
+        self.verify_proof(key, &signature, ChallengeSource::FiatShamirIn(RECEIPT_DOMAIN, &message))
+    }
+}
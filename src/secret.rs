@@ -0,0 +1,68 @@
+// Secret scalar of the prover (its x, or the nonce k of a login): scrubbed when dropped and
+// never printed, for clients running on shared machines
+use std::fmt;
+
+use num_bigint::BigUint;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub struct Secret(BigUint);
+
+impl Secret {
+    pub fn new(value: BigUint) -> Self {
+        Secret(value)
+    }
+
+    // e.g. a password read from the user
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        Secret(BigUint::from_bytes_be(bytes))
+    }
+
+    // the value itself, for solve and compute_pair: do not keep copies of it around
+    pub fn expose(&self) -> &BigUint {
+        &self.0
+    }
+}
+
+impl Zeroize for Secret {
+    // num-bigint gives no access to the limbs, so they are overwritten through its in-place
+    // operators: OR with all ones of the same length, then XOR with it leaves zeros in the same
+    // buffer (the all-ones value only depends on the length, nothing to scrub there)
+    fn zeroize(&mut self) {
+        let ones = (BigUint::from(1u32) << self.0.bits()) - 1u32;
+        self.0 |= &ones;
+        self.0 ^= &ones;
+        std::hint::black_box(&self.0);
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Secret {}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_secret_is_scrubbed_and_not_printed() {
+        let mut x = Secret::from_bytes_be(b"correct horse battery staple");
+        assert_eq!(x.expose(), &BigUint::from_bytes_be(b"correct horse battery staple"));
+        assert_eq!(format!("{x:?}"), "Secret(***)");
+
+        x.zeroize();
+        assert_eq!(x.expose(), &BigUint::ZERO);
+        // nothing left to scrub, still fine
+        x.zeroize();
+        assert_eq!(x.expose(), &BigUint::ZERO);
+    }
+}
@@ -1,166 +1,5526 @@
+// the handlers and their helpers all fail with tonic's Status, which is what the RPCs return;
+// boxing it in every helper only to unbox it at the RPC boundary buys nothing
+#![allow(clippy::result_large_err)]
+
 //use core::num;
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet, hash_map::Entry},
+    fmt,
+    future::Future,
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
 //mod lib;
-use zkp_chaum_pedersen::ZKP;
+use zkp_chaum_pedersen::{
+    AuthBackend, ChallengeProvider, ChallengeSource, ChallengeTranscript, ChaumPedersen,
+    Commitment, GroupEncoding, PROOF_BLOB_DOMAIN, Proof, ProtocolPolicy, ReceiptSigner, Secret,
+    VerifyOutcome, ZKP, ZKPError, check_registration_pow, proof_blob_fingerprint,
+};
 
+use lru::LruCache;
 use num_bigint::BigUint;
-use tonic::{Code, Request, Response, Status, transport::Server};
+use prost::Message;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    Code, Request, Response, Status,
+    codegen::Bytes,
+    metadata::{Ascii, MetadataValue},
+    transport::Server,
+};
+use tracing::{Instrument, info, warn};
+use zeroize::Zeroizing;
 
-//use zkp_chaum_pedersen::*;
+#[cfg(feature = "audit")]
+mod audit_log;
+#[cfg(feature = "audit")]
+use audit_log::{AuditLog, AuthEvent};
+#[cfg(feature = "audit")]
+mod session_record;
+#[cfg(feature = "audit")]
+use session_record::SessionRecord;
+#[cfg(feature = "dump")]
+mod dump;
+#[cfg(feature = "http")]
+mod gateway;
+mod loadtest;
+mod metrics;
+use metrics::Metrics;
 
-pub mod zkp_auth {
-    include!("./zkp_auth.rs");
-}
+// a predictable challenge is only acceptable in tests
+#[cfg(all(feature = "testutil", not(debug_assertions)))]
+compile_error!("the testutil feature cannot be enabled in release builds");
+
+//use zkp_chaum_pedersen::*;
 
-use zkp_auth::{
-    AuthenticationAnswerRequest, AuthenticationAnswerResponse, AuthenticationChallengeRequest,
-    AuthenticationChallengeResponse, RegisterRequest, RegisterResponse,
+use zkp_chaum_pedersen::zkp_auth::{
+    AnswerFailure, AuthenticationAnswerRequest, AuthenticationAnswerResponse,
+    AuthenticationChallengeRequest, AuthenticationChallengeResponse, CommittedChallengeRequest,
+    CommittedChallengeResponse, ListUsersRequest, ListUsersResponse, ParametersRequest,
+    ParametersResponse, RegisterAndLoginRequest, RegisterAndLoginResponse, RegisterRequest,
+    RegisterResponse, RegisteredUser, RevealChallengeRequest, RevealChallengeResponse,
+    SubmitProofRequest, SubmitProofResponse, VerificationReceipt, VerifyBlobRequest,
+    VerifyBlobResponse, VerifyBulkRequest, VerifyBulkResult, VerifyCompleteRequest,
+    VerifyCompleteResponse, WatchEventsRequest, WatchedEvent, WhoAmIRequest, WhoAmIResponse,
     auth_server::{Auth, AuthServer},
 };
 // create a function that returns a random user_id
 
-#[derive(Debug, Default)]
-
+// All the state sits behind Mutexes and every RPC takes &self, so one AuthImpl can be shared
+// by any number of tasks as an Arc<AuthImpl> (main shares it between gRPC and the HTTP gateway).
+// The locks are never held across an .await
+#[derive(Debug)]
 pub struct AuthImpl {
     pub user_info: Mutex<HashMap<String, UserInfo>>,
-    pub auth_id_to_user: Mutex<HashMap<String, String>>,
+    // pending challenges and sessions are bounded: when full, the least recently used entry is
+    // evicted (a login whose challenge is evicted fails and starts over), see ZKP_MAX_CHALLENGES
+    pub auth_id_to_user: Mutex<LruCache<String, ChallengeInfo>>,
+    // session_id -> owner and expiry of the sessions minted by verify_authentication
+    // (ZKP_MAX_SESSIONS)
+    pub sessions: Mutex<LruCache<String, SessionInfo>>,
+    // challenge and session TTLs, enforced here and advertised by get_parameters
+    pub policy: ProtocolPolicy,
+    // every s already submitted per auth_id, a second identical answer is a replay
+    // (unless it is still in verify_results). Bounded like auth_id_to_user: the answers of an
+    // auth_id only matter while its challenge is there, afterwards it is not found anyway
+    pub seen_responses: Mutex<LruCache<String, HashSet<BigUint>>>,
+    // outcome of the verification of each answer, for policy.answer_retry_ttl: bounded like
+    // auth_id_to_user, and an outcome past the TTL is dropped when it is looked up
+    pub verify_results: Mutex<LruCache<RetryKey, CachedVerification>>,
+    // replay checks applied to challenges and answers, all on by default
+    pub verification: VerificationPolicy,
+    // login flow the server offers: challenges (default) or SubmitProof, see ZKP_SERVER_MODE
+    pub mode: ServerMode,
+    // digests of the (r1, r2) challenges were requested with, for verification.fresh_commitment
+    // (bounded like auth_id_to_user: past MAX_SEEN_COMMITMENTS the oldest are forgotten)
+    pub seen_commitments: Mutex<LruCache<[u8; 32], ()>>,
+    // latency histograms of the login RPCs (register, challenge, verify...), see ZKP_METRICS_ADDR
+    pub metrics: Metrics,
+    // threads running the exponentiations of verify_authentication and VerifyBulk, see
+    // ZKP_VERIFY_THREADS (shared with the tasks streaming VerifyBulk results)
+    pub verify_pool: Arc<VerifyPool>,
+    // answers to one challenge one at a time, see verify_authentication
+    pub answer_locks: AnswerLocks,
+    // optional upper bound of a random delay added before answering verify_authentication
+    // None (default) disables it, see ZKP_VERIFY_JITTER_MS in main
+    pub verify_jitter: Option<Duration>,
+    // shared secret expected in the x-admin-token metadata of admin RPCs (list_users)
+    // None (default) disables them, see ZKP_ADMIN_TOKEN in main
+    pub admin_token: Option<String>,
+    // groups users may register under, see GroupSet
+    pub groups: GroupSet,
+    // protocol checked by register, create_authentication_challenge and verify_authentication
+    // (ChaumPedersen unless with_backend picks another one)
+    pub backend: Arc<dyn AuthBackend>,
+    // stateless mode: challenges are derived from this secret (ZKP::stateless_challenge) and
+    // nothing is stored per challenge, None (default) disables it, see ZKP_STATELESS_SECRET in main
+    pub stateless_secret: Option<Vec<u8>>,
+    // signs a VerificationReceipt into every successful answer, None (default) sends none,
+    // see ZKP_RECEIPT_KEY in main
+    pub receipt_signer: Option<ReceiptSigner>,
+    // registrations and logins appended to a hash-chained file, None (default) records nothing,
+    // see ZKP_AUDIT_LOG in main
+    #[cfg(feature = "audit")]
+    pub audit_log: Option<AuditLog>,
+    // every recorded AuthEvent (with its timestamp) goes out here to the WatchEvents streams,
+    // whether or not there is an audit log; nobody watching is fine
+    #[cfg(feature = "audit")]
+    pub event_watchers: tokio::sync::broadcast::Sender<(u64, AuthEvent)>,
+    // every checked answer of a registered user is also recorded as a whole SessionRecord (off
+    // by default), see ZKP_SESSION_RECORDS in main
+    #[cfg(feature = "audit")]
+    pub session_records: bool,
+    // users are stored with commitment_digest instead of y1, y2, which challenge requests then
+    // have to carry (off by default), see ZKP_HASHED_COMMITMENTS in main
+    pub hashed_commitments: bool,
+    // users are stored with y1 only when y2 = y1^ZKP::beta_exponent() (the built-in groups), y2
+    // is recomputed when needed (off by default), see ZKP_DERIVED_Y2 in main
+    pub derived_y2: bool,
+    // the user_id of a registration is ZKP::commitment_handle of its y1, y2 instead of a random
+    // id, and one commitment registers one user only (off by default), see ZKP_COMMITMENT_HANDLES
+    pub commitment_handles: bool,
+    // handle -> username of the users registered with commitment_handles, see user_by_handle
+    pub handles: Mutex<HashMap<String, String>>,
+    // where the c of create_authentication_challenge comes from, None (default) is the backend's
+    // random one. The backend still checks r1, r2 first. FixedChallenge lets tests predict the
+    // whole transcript, see ZKP_TEST_FIXED_CHALLENGE in main (testutil feature, debug builds)
+    pub challenge_provider: Option<Box<dyn ChallengeProvider>>,
+    // run on every new session before its id is returned, None (default) runs nothing,
+    // see AuthServiceBuilder::on_verified
+    pub on_verified: Option<VerifiedHook>,
 }
 
-#[derive(Debug, Default)] // 
-pub struct UserInfo {
-    // registration
-    pub user_name: String,
-    pub y1: BigUint,
-    pub y2: BigUint,
-    // authorization
-    pub r1: BigUint,
-    pub r2: BigUint,
-    // verification
-    pub c: BigUint,
-    pub s: BigUint,
-    pub session_id: String,
+// results of verify_bulk computed ahead of what the client has read, at most
+pub const VERIFY_BULK_BUFFER: usize = 16;
+// events a WatchEvents stream may fall behind by before it skips the oldest ones
+pub const EVENT_WATCH_BUFFER: usize = 256;
+// longest x-request-id taken from a caller, a longer one is replaced by a fresh id
+pub const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// default capacities of auth_id_to_user and sessions
+pub const MAX_CHALLENGES: usize = 100_000;
+pub const MAX_SESSIONS: usize = 100_000;
+pub const MAX_SEEN_COMMITMENTS: usize = 1_000_000;
+// draws of a challenge before new_challenge gives up on a backend that only returns c = 0
+pub const MAX_CHALLENGE_DRAWS: usize = 16;
+
+// Dedicated threads for the exponentiations of verify_authentication and VerifyBulk, apart from
+// tokio's blocking pool (whatever spawn_blocking work the application runs there) and from its
+// workers, so a burst of other blocking work does not delay logins and a login does
+// not hold up the runtime. Built on first use
+#[derive(Debug, Default)]
+pub struct VerifyPool {
+    // 0: one per CPU
+    threads: usize,
+    pool: OnceLock<rayon::ThreadPool>,
+    jobs: AtomicU64,
+    // verifications running right now, and the most there ever were at once (shared with the
+    // jobs, which do not borrow the pool)
+    running: Arc<AtomicU64>,
+    peak: Arc<AtomicU64>,
 }
 
-#[tonic::async_trait]
-impl Auth for AuthImpl {
-    async fn register(
-        &self,
-        request: Request<RegisterRequest>,
-    ) -> Result<Response<RegisterResponse>, Status> {
-        let request = request.into_inner();
+impl VerifyPool {
+    pub fn new(threads: usize) -> Self {
+        VerifyPool { threads, ..Default::default() }
+    }
 
-        let user_name = request.user;
-        println!("Processing Registration username: {:?}", user_name);
+    // runs verify on one of the pool's threads, the caller's task waits for it without taking
+    // a tokio worker: verify_authentication holds no lock meanwhile, so the answers of different
+    // users use as many threads at once. A panic in verify is resumed in the caller
+    pub async fn run<R: Send + 'static>(&self, verify: impl FnOnce() -> R + Send + 'static) -> R {
+        let pool = self.pool.get_or_init(|| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .thread_name(|i| format!("zkp-verify-{i}"))
+                .build()
+                .expect("could not start the verification threads")
+        });
+        self.jobs.fetch_add(1, Ordering::Relaxed);
+        let (running, peak) = (self.running.clone(), self.peak.clone());
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        pool.spawn(move || {
+            let now_running = running.fetch_add(1, Ordering::Relaxed) + 1;
+            peak.fetch_max(now_running, Ordering::Relaxed);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(verify));
+            running.fetch_sub(1, Ordering::Relaxed);
+            // an Err only means that the caller is gone (its RPC was cancelled)
+            let _ = sender.send(result);
+        });
+        match receiver.await.expect("a job always sends its result") {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
 
-        let user_info = UserInfo {
-            user_name: user_name.clone(),
-            y1: BigUint::from_bytes_be(&request.y1),
-            y2: BigUint::from_bytes_be(&request.y2),
-            ..Default::default()
-        };
+    // verifications run so far
+    pub fn jobs(&self) -> u64 {
+        self.jobs.load(Ordering::Relaxed)
+    }
 
-        let user_info_hashmap = &mut self.user_info.lock().unwrap();
-        user_info_hashmap.insert(user_name.clone(), user_info);
+    // the most verifications that ran at the same time
+    pub fn peak(&self) -> u64 {
+        self.peak.load(Ordering::Relaxed)
+    }
+}
+
+// Application code run right after a successful verification, before the session id goes out
+// (issue a token, tell an identity provider, write a row...): called with the user and the new
+// session id, an Err takes the session back and the login fails with INTERNAL
+pub type VerifiedFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
 
-        println!("✅ Successful Registration username: {:?}", user_name);
-        Ok(Response::new(RegisterResponse {}))
+#[derive(Clone)]
+pub struct VerifiedHook(Arc<dyn Fn(String, String) -> VerifiedFuture + Send + Sync>);
+
+impl VerifiedHook {
+    pub fn new<F, Fut>(hook: F) -> Self
+    where
+        F: Fn(String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        VerifiedHook(Arc::new(move |user, session_id| Box::pin(hook(user, session_id))))
     }
 
-    async fn create_authentication_challenge(
+    pub fn call(&self, user: String, session_id: String) -> VerifiedFuture {
+        (self.0)(user, session_id)
+    }
+}
+
+impl fmt::Debug for VerifiedHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VerifiedHook")
+    }
+}
+
+// Interactive: challenge, then answer (CreateAuthenticationChallenge, CreateCommittedChallenge)
+// NonInteractive: only self-contained Fiat-Shamir proofs (SubmitProof), no challenge is issued
+// the RPCs of the other mode answer UNIMPLEMENTED
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServerMode {
+    #[default]
+    Interactive,
+    NonInteractive,
+}
+
+impl std::str::FromStr for ServerMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, String> {
+        match mode {
+            "interactive" => Ok(ServerMode::Interactive),
+            "non-interactive" => Ok(ServerMode::NonInteractive),
+            other => Err(format!("unknown server mode: {other} (interactive, non-interactive)")),
+        }
+    }
+}
+
+// the replay defenses of the stored-challenge flow, each one can be turned off on its own
+// (stateless challenges store nothing, so only the expiry applies to them)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationPolicy {
+    // an answer or reveal after policy.challenge_ttl: DEADLINE_EXCEEDED
+    pub challenge_expiry: bool,
+    // a second, different answer to an already answered challenge: FAILED_PRECONDITION
+    pub single_use_challenge: bool,
+    // a challenge requested with an (r1, r2) seen before: INVALID_ARGUMENT
+    // (two answers for the same k give x away: s1 - s2 = (c2 - c1) * x)
+    pub fresh_commitment: bool,
+    // an s already submitted for the same challenge: ALREADY_EXISTS
+    pub unique_response: bool,
+}
+
+// the strictest policy
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        VerificationPolicy {
+            challenge_expiry: true,
+            single_use_challenge: true,
+            fresh_commitment: true,
+            unique_response: true,
+        }
+    }
+}
+
+impl VerificationPolicy {
+    // turns off the checks named in a comma separated list (ZKP_RELAXED_CHECKS)
+    pub fn relax(mut self, checks: &str) -> Result<Self, String> {
+        for check in checks.split(',').map(str::trim).filter(|check| !check.is_empty()) {
+            match check {
+                "challenge_expiry" => self.challenge_expiry = false,
+                "single_use_challenge" => self.single_use_challenge = false,
+                "fresh_commitment" => self.fresh_commitment = false,
+                "unique_response" => self.unique_response = false,
+                other => return Err(format!("unknown check: {other}")),
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn capacity(entries: usize) -> NonZeroUsize {
+    NonZeroUsize::new(entries).expect("a capacity must be at least 1")
+}
+
+impl Default for AuthImpl {
+    fn default() -> Self {
+        AuthImpl {
+            user_info: Mutex::default(),
+            auth_id_to_user: Mutex::new(LruCache::new(capacity(MAX_CHALLENGES))),
+            sessions: Mutex::new(LruCache::new(capacity(MAX_SESSIONS))),
+            policy: ProtocolPolicy::default(),
+            seen_responses: Mutex::new(LruCache::new(capacity(MAX_CHALLENGES))),
+            verify_results: Mutex::new(LruCache::new(capacity(MAX_CHALLENGES))),
+            verification: VerificationPolicy::default(),
+            mode: ServerMode::default(),
+            seen_commitments: Mutex::new(LruCache::new(capacity(MAX_SEEN_COMMITMENTS))),
+            metrics: Metrics::default(),
+            verify_pool: Arc::default(),
+            answer_locks: AnswerLocks::default(),
+            verify_jitter: None,
+            admin_token: None,
+            groups: GroupSet::default(),
+            backend: Arc::new(ChaumPedersen),
+            stateless_secret: None,
+            receipt_signer: None,
+            #[cfg(feature = "audit")]
+            audit_log: None,
+            #[cfg(feature = "audit")]
+            event_watchers: tokio::sync::broadcast::channel(EVENT_WATCH_BUFFER).0,
+            #[cfg(feature = "audit")]
+            session_records: false,
+            hashed_commitments: false,
+            derived_y2: false,
+            commitment_handles: false,
+            handles: Mutex::default(),
+            challenge_provider: None,
+            on_verified: None,
+        }
+    }
+}
+
+impl AuthImpl {
+    // resizes the challenge and session maps, evicting the least recently used entries if needed
+    pub fn with_capacities(self, max_challenges: usize, max_sessions: usize) -> Self {
+        self.auth_id_to_user.lock().unwrap().resize(capacity(max_challenges));
+        self.seen_responses.lock().unwrap().resize(capacity(max_challenges));
+        self.verify_results.lock().unwrap().resize(capacity(max_challenges));
+        self.sessions.lock().unwrap().resize(capacity(max_sessions));
+        self
+    }
+
+    // swaps the protocol behind the gRPC surface, the transport and bookkeeping stay as they are
+    pub fn with_backend(mut self, backend: impl AuthBackend + 'static) -> Self {
+        self.backend = Arc::new(backend);
+        self
+    }
+}
+
+// Assembles the service outside of main (tests, applications embedding it): every setting main
+// reads from the environment, plus the groups and the users to start with.
+// The store is the in-memory user map of AuthImpl, there is no pluggable storage backend
+#[derive(Debug, Default)]
+pub struct AuthServiceBuilder {
+    auth: AuthImpl,
+}
+
+impl AuthServiceBuilder {
+    // group of the registrations without group_id, replaces the default RFC 5114 groups
+    // (neither this nor extra_group exists when group-rfc5114-2048 pins the group)
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    pub fn group(mut self, zkp: ZKP) -> Self {
+        self.auth.groups = GroupSet::new(zkp);
+        self
+    }
+
+    // one more group users may register under
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    pub fn extra_group(mut self, zkp: ZKP) -> Self {
+        self.auth.groups.insert(zkp);
+        self
+    }
+
+    // users to start with, by username (e.g. loaded from a store kept elsewhere)
+    pub fn store(mut self, users: HashMap<String, UserInfo>) -> Self {
+        self.auth.user_info = Mutex::new(users);
+        self
+    }
+
+    pub fn policy(mut self, policy: ProtocolPolicy) -> Self {
+        self.auth.policy = policy;
+        self
+    }
+
+    pub fn challenge_ttl(mut self, challenge_ttl: Duration) -> Self {
+        self.auth.policy.challenge_ttl = challenge_ttl;
+        self
+    }
+
+    pub fn session_ttl(mut self, session_ttl: Duration) -> Self {
+        self.auth.policy.session_ttl = session_ttl;
+        self
+    }
+
+    pub fn max_challenges(mut self, max_challenges: usize) -> Self {
+        self.auth.auth_id_to_user.get_mut().unwrap().resize(capacity(max_challenges));
+        self.auth.seen_responses.get_mut().unwrap().resize(capacity(max_challenges));
+        self.auth.verify_results.get_mut().unwrap().resize(capacity(max_challenges));
+        self
+    }
+
+    pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+        self.auth.sessions.get_mut().unwrap().resize(capacity(max_sessions));
+        self
+    }
+
+    // None keeps the admin RPCs disabled
+    pub fn admin_token(mut self, admin_token: Option<String>) -> Self {
+        self.auth.admin_token = admin_token;
+        self
+    }
+
+    // 0 (default) is one thread per CPU
+    pub fn verify_threads(mut self, threads: usize) -> Self {
+        self.auth.verify_pool = Arc::new(VerifyPool::new(threads));
+        self
+    }
+
+    pub fn verify_jitter(mut self, verify_jitter: Option<Duration>) -> Self {
+        self.auth.verify_jitter = verify_jitter;
+        self
+    }
+
+    pub fn verification(mut self, verification: VerificationPolicy) -> Self {
+        self.auth.verification = verification;
+        self
+    }
+
+    pub fn mode(mut self, mode: ServerMode) -> Self {
+        self.auth.mode = mode;
+        self
+    }
+
+    pub fn stateless_secret(mut self, stateless_secret: Option<Vec<u8>>) -> Self {
+        self.auth.stateless_secret = stateless_secret;
+        self
+    }
+
+    // None keeps the receipts off
+    pub fn receipt_key(mut self, receipt_key: Option<Secret>) -> Self {
+        self.auth.receipt_signer = receipt_key.map(ReceiptSigner::new);
+        self
+    }
+
+    // None (default) keeps no audit log
+    #[cfg(feature = "audit")]
+    pub fn audit_log(mut self, audit_log: Option<AuditLog>) -> Self {
+        self.auth.audit_log = audit_log;
+        self
+    }
+
+    #[cfg(feature = "audit")]
+    pub fn session_records(mut self, session_records: bool) -> Self {
+        self.auth.session_records = session_records;
+        self
+    }
+
+    pub fn hashed_commitments(mut self, hashed_commitments: bool) -> Self {
+        self.auth.hashed_commitments = hashed_commitments;
+        self
+    }
+
+    pub fn derived_y2(mut self, derived_y2: bool) -> Self {
+        self.auth.derived_y2 = derived_y2;
+        self
+    }
+
+    pub fn commitment_handles(mut self, commitment_handles: bool) -> Self {
+        self.auth.commitment_handles = commitment_handles;
+        self
+    }
+
+    pub fn challenge_provider(mut self, provider: impl ChallengeProvider + 'static) -> Self {
+        self.auth.challenge_provider = Some(Box::new(provider));
+        self
+    }
+
+    // e.g. .on_verified(|user, session_id| async move { idp.notify(&user, &session_id).await })
+    pub fn on_verified<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.auth.on_verified = Some(VerifiedHook::new(hook));
+        self
+    }
+
+    pub fn backend(self, backend: impl AuthBackend + 'static) -> Self {
+        AuthServiceBuilder { auth: self.auth.with_backend(backend) }
+    }
+
+    // the AuthImpl itself, to share it (Arc) or call it directly
+    pub fn build_impl(self) -> AuthImpl {
+        self.auth
+    }
+
+    // ready for Server::builder().add_service(...)
+    pub fn build(self) -> AuthServer<AuthImpl> {
+        AuthServer::new(self.auth)
+    }
+}
+
+impl AuthImpl {
+    pub fn builder() -> AuthServiceBuilder {
+        AuthServiceBuilder::default()
+    }
+}
+
+// fails to compile if a new field makes AuthImpl unshareable between tasks
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+    assert_send_sync::<AuthImpl>();
+};
+
+// The groups a server accepts, by fingerprint (the group_id of the RPCs)
+// a registration without group_id goes to the default group
+#[derive(Debug)]
+pub struct GroupSet {
+    default_id: [u8; 32],
+    groups: HashMap<[u8; 32], ZKP>,
+}
+
+impl GroupSet {
+    pub fn new(default: ZKP) -> Self {
+        let default_id = default.fingerprint();
+        GroupSet { default_id, groups: HashMap::from([(default_id, default)]) }
+    }
+
+    pub fn insert(&mut self, zkp: ZKP) -> [u8; 32] {
+        let id = zkp.fingerprint();
+        self.groups.insert(id, zkp);
+        id
+    }
+
+    pub fn get(&self, id: &[u8; 32]) -> Option<&ZKP> {
+        self.groups.get(id)
+    }
+
+    // resolves the group_id field of a request (empty = default group)
+    pub fn resolve(&self, group_id: &[u8]) -> Option<([u8; 32], &ZKP)> {
+        let id = if group_id.is_empty() { self.default_id } else { group_id.try_into().ok()? };
+        self.get(&id).map(|zkp| (id, zkp))
+    }
+}
+
+// the 1024-bit group as default and the 2048-bit group, for clients migrating to it
+impl Default for GroupSet {
+    // a build with the group-rfc5114-2048 feature knows that group only
+    #[cfg(feature = "group-rfc5114-2048")]
+    fn default() -> Self {
+        GroupSet::new(ZKP::default())
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    fn default() -> Self {
+        let mut groups = GroupSet::new(ZKP::default());
+
+        let (alpha, beta, p, q) = ZKP::get_constants_2048();
+        groups.insert(ZKP::new(alpha, beta, p, q));
+        groups
+    }
+}
+
+impl AuthImpl {
+    // the backend checks (r1, r2) and draws c, a binding is mixed in (ZKP::bind_challenge)
+    fn new_challenge(
         &self,
-        request: Request<AuthenticationChallengeRequest>,
+        transcript: &ChallengeTranscript,
+        r1: &[u8],
+        r2: &[u8],
+        binding: &[u8],
+    ) -> Result<BigUint, ZKPError> {
+        let zkp = transcript.zkp;
+        // c = 0 would let anyone through (y^0 = 1), so it is never issued: drawn again if the
+        // backend, the provider (or the binding hash) ever comes up with it, one stuck on 0 is an
+        // error
+        for _ in 0..MAX_CHALLENGE_DRAWS {
+            let c = zkp.decode_scalar(&self.backend.challenge(zkp, r1, r2)?)?;
+            let c = match &self.challenge_provider {
+                Some(provider) => provider.challenge(transcript),
+                None => c,
+            };
+            let c = zkp.bind_challenge(&c, binding);
+            if c != BigUint::ZERO {
+                return Ok(c);
+            }
+            warn!("⚠️  Drew a zero challenge, drawing again");
+        }
+        Err(ZKPError::ZeroChallenge)
+    }
+
+    // stateless mode: the auth_id carries "timestamp.r1.r2.user" (hex r1, r2) and c is derived
+    // from it, so verify_authentication can recompute everything. Chaum-Pedersen only, and an
+    // unknown user gets a challenge against the same decoy commitment as in the stored mode
+    fn stateless_challenge(
+        &self,
+        secret: &[u8],
+        user_name: &str,
+        r1: &[u8],
+        r2: &[u8],
+        binding: &[u8],
+        group_bits: &mut Option<u64>,
     ) -> Result<Response<AuthenticationChallengeResponse>, Status> {
-        let request = request.into_inner();
+        let user_info_hashmap = self.user_info.lock().unwrap();
+        let (group_id, zkp, commitment) = match user_info_hashmap.get(user_name) {
+            Some(user_info) => {
+                let zkp =
+                    self.groups.get(&user_info.group_id).expect("registered under a known group");
+                (user_info.group_id, zkp, Self::stored_commitment(zkp, user_info)?)
+            }
+            None => {
+                let (group_id, zkp) =
+                    self.groups.resolve(&[]).expect("the default group is always there");
+                let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                (group_id, zkp, Commitment::new(y1, y2))
+            }
+        };
+        *group_bits = Some(zkp.group_bits());
 
-        let user_name = request.user;
-        println!("Processing Challenge Request username: {:?}", user_name);
+        let subject = format!("User: {user_name}");
+        let r1_value = zkp.decode_element(r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+        let r2_value = zkp.decode_element(r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+        if !zkp.is_in_subgroup(&r1_value) || !zkp.is_in_subgroup(&r2_value) {
+            return Err(rejected(&subject, ZKPError::NotInSubgroup("r1/r2")));
+        }
 
-        let user_info_hashmap = &mut self.user_info.lock().unwrap();
+        let timestamp = ZKP::unix_timestamp();
+        let c = zkp.stateless_challenge(secret, &commitment, &r1_value, &r2_value, timestamp);
+        let c = zkp.bind_challenge(&c, binding);
+        let auth_id = format!("{timestamp}.{}.{}.{user_name}", hex::encode(r1), hex::encode(r2));
 
-        if let Some(user_info) = user_info_hashmap.get_mut(&user_name) {
-            let (_, _, _, q) = ZKP::get_constants();
-            let c = ZKP::generate_random_number_below(&q);
-            let auth_id = ZKP::generate_random_string(12);
+        info!("✅ Stateless Challenge Request username: {user_name:?}");
 
-            user_info.c = c.clone();
-            user_info.r1 = BigUint::from_bytes_be(&request.r1);
-            user_info.r2 = BigUint::from_bytes_be(&request.r2);
+        Ok(Response::new(AuthenticationChallengeResponse {
+            auth_id,
+            c: zkp.encode_scalar(&c),
+            group_id: group_id.to_vec(),
+            binding: binding.to_vec(),
+        }))
+    }
 
-            let auth_id_to_user = &mut self.auth_id_to_user.lock().unwrap();
-            auth_id_to_user.insert(auth_id.clone(), user_name.clone());
+    // without any record of the challenge there is no replay detection either: the same answer
+    // is accepted again until the timestamp is policy.challenge_ttl old, keep it short
+    async fn stateless_verify(
+        &self,
+        secret: &[u8],
+        auth_id: &str,
+        s: &[u8],
+        binding: &[u8],
+        group_bits: &mut Option<u64>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        let malformed = || {
+            Status::new(
+                Code::InvalidArgument,
+                format!("AuthId: {auth_id} is not a stateless challenge"),
+            )
+        };
+        let mut parts = auth_id.splitn(4, '.');
+        let (Some(timestamp), Some(r1), Some(r2), Some(user_name)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed());
+        };
+        let timestamp: u64 = timestamp.parse().map_err(|_| malformed())?;
+        let r1 = hex::decode(r1).map_err(|_| malformed())?;
+        let r2 = hex::decode(r2).map_err(|_| malformed())?;
+
+        let bad_solution = || {
+            answer_failure(
+                Code::PermissionDenied,
+                format!("AuthId: {auth_id} bad solution to the challenge"),
+                "wrong_answer",
+                false,
+            )
+        };
+        // decoded under the user_info lock, verified once it is released
+        let (zkp, r1, r2, commitment, c, s, decoy) = {
+            let user_info_hashmap = self.user_info.lock().unwrap();
+            let user_info = user_info_hashmap.get(user_name);
+            // an unknown user is checked against the decoy commitment of stateless_challenge
+            // (x = 1): same steps, same work, then refused as a wrong answer
+            let zkp = match user_info {
+                Some(user_info) => {
+                    self.groups.get(&user_info.group_id).expect("registered under a known group")
+                }
+                None => self.groups.resolve(&[]).expect("the default group is always there").1,
+            };
+            *group_bits = Some(zkp.group_bits());
+
+            let subject = format!("AuthId: {auth_id}");
+            let r1 = zkp.decode_element(&r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+            let r2 = zkp.decode_element(&r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+            let s = zkp.decode_scalar(s).map_err(|e| invalid_encoding(&subject, "s", e))?;
 
-            println!("✅ Successful Challenge Request username: {user_name:?}");
+            let (ttl, skew) = (self.policy.challenge_ttl, self.policy.clock_skew);
+            if !ZKP::timestamp_is_fresh_with_skew(timestamp, ttl, skew) {
+                warn!("❌ Expired Challenge Solution username: {user_name:?}");
+                return Err(answer_failure(
+                    Code::DeadlineExceeded,
+                    format!("AuthId: {auth_id} challenge expired"),
+                    "challenge_expired",
+                    true,
+                ));
+            }
+
+            let commitment = match user_info {
+                Some(user_info) => Self::stored_commitment(zkp, user_info)?,
+                None => {
+                    let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                    Commitment::new(y1, y2)
+                }
+            };
+            // another binding gives another c, so the answer does not verify
+            let c = zkp.stateless_challenge(secret, &commitment, &r1, &r2, timestamp);
+            let c = zkp.bind_challenge(&c, binding);
+            (zkp.clone(), r1, r2, commitment, c, s, user_info.is_none())
+        };
 
-            Ok(Response::new(AuthenticationChallengeResponse { auth_id, c: c.to_bytes_be() }))
+        let verified = {
+            let (c, s) = (c.clone(), s.clone());
+            let verify = move || zkp.verify(&r1, &r2, &commitment.y1, &commitment.y2, &c, &s);
+            self.verify_pool.run(verify).await
+        };
+        if decoy {
+            // even an s for x = 1: there is no user to log in
+            warn!("❌ Stateless Challenge Solution for unknown username: {user_name:?}");
+            Err(bad_solution())
+        } else if verified {
+            // the user as it is now, gone if it was removed while the answer was verified
+            let session_id = {
+                let user_info_hashmap = &mut self.user_info.lock().unwrap();
+                let Some(user_info) = user_info_hashmap.get_mut(user_name) else {
+                    return Err(bad_solution());
+                };
+                self.mint_session(user_info)
+            };
+            info!("✅ Correct Stateless Challenge Solution username: {user_name:?}");
+            let receipt = self.receipt(auth_id, user_name, &c, &s);
+            Ok(Response::new(AuthenticationAnswerResponse { session_id, receipt }))
         } else {
-            Err(Status::new(Code::NotFound, format!("User: {user_name} not found in database")))
+            warn!("❌ Wrong Challenge Solution username: {user_name:?}");
+            warn_if_swapped(user_name, &c, &s);
+            Err(bad_solution())
         }
     }
 
-    async fn verify_authentication(
+    // verification.fresh_commitment: remembers (r1, r2) under its group and refuses one
+    // already seen, whoever sent it
+    fn check_fresh_commitment(
         &self,
-        request: Request<AuthenticationAnswerRequest>,
-    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
-        let request = request.into_inner();
+        subject: &str,
+        group_id: &[u8; 32],
+        r1: &BigUint,
+        r2: &BigUint,
+    ) -> Result<(), Status> {
+        if !self.verification.fresh_commitment {
+            return Ok(());
+        }
+        let r1 = r1.to_bytes_be();
+        let digest: [u8; 32] = Sha256::new()
+            .chain_update(group_id)
+            .chain_update((r1.len() as u64).to_be_bytes())
+            .chain_update(&r1)
+            .chain_update(r2.to_bytes_be())
+            .finalize()
+            .into();
+        if self.seen_commitments.lock().unwrap().put(digest, ()).is_some() {
+            warn!("❌ Reused commitment {subject}");
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("{subject} r1/r2 already used for another challenge"),
+            ));
+        }
+        Ok(())
+    }
+
+    // every check of a registration (proof of work, group, y1/y2 in the subgroup),
+    // returns the user to store
+    fn checked_registration(&self, request: RegisterRequest) -> Result<UserInfo, Status> {
+        let user_name = request.user;
 
-        let auth_id = request.auth_id;
-        println!("Processing Challenge Solution auth_id: {:?}", auth_id);
+        // cheap to check, so before anything else
+        let pow_bits = self.policy.registration_pow_bits;
+        if !check_registration_pow(&user_name, request.pow_nonce, pow_bits) {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("User: {user_name} proof of work does not have {pow_bits} zero bits"),
+            ));
+        }
 
-        let auth_id_to_user_hashmap = &mut self.auth_id_to_user.lock().unwrap();
+        let Some((group_id, zkp)) = self.groups.resolve(&request.group_id) else {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("User: {user_name} group is not supported by this server"),
+            ));
+        };
 
-        if let Some(user_name) = auth_id_to_user_hashmap.get(&auth_id) {
-            let user_info_hashmap = &mut self.user_info.lock().unwrap();
-            let user_info =
-                user_info_hashmap.get_mut(user_name).expect("AuthId not found on hashmap");
+        let subject = format!("User: {user_name}");
+        let y1 =
+            zkp.decode_element(&request.y1).map_err(|e| invalid_encoding(&subject, "y1", e))?;
+        let y2 =
+            zkp.decode_element(&request.y2).map_err(|e| invalid_encoding(&subject, "y2", e))?;
 
-            let s = BigUint::from_bytes_be(&request.s);
-            user_info.s = s;
+        // checked once here so verify_authentication only ever sees valid commitments
+        // (for Chaum-Pedersen: y1, y2 in the order-q subgroup)
+        self.backend.register(zkp, &request.y1, &request.y2).map_err(|e| rejected(&subject, e))?;
 
-            let (alpha, beta, p, q) = ZKP::get_constants();
+        // users may have to read it out or type it: no look-alike characters
+        let user_id = if self.commitment_handles {
+            zkp.commitment_handle(&Commitment::new(y1.clone(), y2.clone()))
+        } else {
+            ZKP::generate_readable_id(60)
+        };
+        Ok(UserInfo { user_name, user_id, group_id, y1, y2, ..Default::default() })
+    }
 
-            let zkp = ZKP::new(alpha, beta, p, q);
-            // let zkp = ZKP { alpha, beta, p, q }; // avoiding the "field private" error
+    // with commitment_handles, the handle of a new user (its user_id) must be free: called under
+    // the user_info lock, right before the user is inserted
+    fn claim_handle(&self, user_info: &UserInfo) -> Result<(), Status> {
+        if !self.commitment_handles {
+            return Ok(());
+        }
+        match self.handles.lock().unwrap().entry(user_info.user_id.clone()) {
+            Entry::Occupied(_) => Err(Status::new(
+                Code::AlreadyExists,
+                format!("User: {} commitment is already registered", user_info.user_name),
+            )),
+            Entry::Vacant(entry) => {
+                entry.insert(user_info.user_name.clone());
+                Ok(())
+            }
+        }
+    }
 
-            let verification = zkp.verify(
-                &user_info.r1,
-                &user_info.r2,
-                &user_info.y1,
-                &user_info.y2,
-                &user_info.c,
-                &user_info.s,
-            );
+    // username registered under this ZKP::commitment_handle (commitment_handles only)
+    pub fn user_by_handle(&self, handle: &str) -> Option<String> {
+        self.handles.lock().unwrap().get(handle).cloned()
+    }
 
-            if verification {
-                let session_id = ZKP::generate_random_string(12);
+    // new session for a user who just proved knowledge of x
+    fn mint_session(&self, user_info: &mut UserInfo) -> String {
+        let session_id = ZKP::generate_session_id();
+        user_info.session_id = session_id.clone();
 
-                println!("✅ Correct Challenge Solution username: {user_name:?}");
+        let sessions = &mut self.sessions.lock().unwrap();
+        sessions.put(
+            session_id.clone(),
+            SessionInfo {
+                user_name: user_info.user_name.clone(),
+                group_id: user_info.group_id,
+                expires_at: Instant::now() + self.policy.session_ttl,
+            },
+        );
+        session_id
+    }
 
-                Ok(Response::new(AuthenticationAnswerResponse { session_id }))
-            } else {
-                println!("❌ Wrong Challenge Solution username: {user_name:?}",);
+    // on_verified for a session just minted, with no lock held: when the hook fails the session
+    // is revoked before anyone saw its id
+    async fn provision_session(&self, session_id: &str) -> Result<(), Status> {
+        let Some(hook) = &self.on_verified else {
+            return Ok(());
+        };
+        let user_name = self.sessions.lock().unwrap().peek(session_id).map(|s| s.user_name.clone());
+        let Some(user_name) = user_name else {
+            // already evicted, nothing left to provision
+            return Ok(());
+        };
+        if let Err(error) = hook.call(user_name.clone(), session_id.to_string()).await {
+            warn!("❌ Session provisioning failed username: {user_name:?}: {error}");
+            self.sessions.lock().unwrap().pop(session_id);
+            return Err(Status::new(
+                Code::Internal,
+                format!("User: {user_name} session provisioning failed: {error}"),
+            ));
+        }
+        Ok(())
+    }
 
-                Err(Status::new(
-                    Code::PermissionDenied,
-                    format!("AuthId: {auth_id} bad solution to the challenge"),
-                ))
+    // latency of one RPC, once its group is known (requests refused before are not counted)
+    fn observe(&self, operation: &'static str, group_bits: Option<u64>, started: Instant) {
+        if let Some(group_bits) = group_bits {
+            self.metrics.observe(operation, group_bits, started.elapsed());
+        }
+    }
+
+    // the RPCs of one mode only, UNIMPLEMENTED in the other one
+    fn require_mode(&self, mode: ServerMode, rpc: &str) -> Result<(), Status> {
+        if self.mode == mode {
+            return Ok(());
+        }
+        let hint = match self.mode {
+            ServerMode::Interactive => "ask for a challenge instead",
+            ServerMode::NonInteractive => "use SubmitProof instead",
+        };
+        Err(Status::new(
+            Code::Unimplemented,
+            format!("{rpc} is not offered by this {:?} server, {hint}", self.mode),
+        ))
+    }
+
+    // signed receipt of a successful answer, when the server has a receipt key
+    fn receipt(
+        &self,
+        auth_id: &str,
+        user_name: &str,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> Option<VerificationReceipt> {
+        let signer = self.receipt_signer.as_ref()?;
+        Some(signer.sign(auth_id, user_name, c, s, true))
+    }
+
+    // a failed write is logged, not turned into a failed RPC
+    #[cfg(feature = "audit")]
+    fn record(&self, event: AuthEvent) {
+        // an Err only means that no one is watching
+        let _ = self.event_watchers.send((ZKP::unix_timestamp(), event.clone()));
+        if let Some(audit_log) = &self.audit_log
+            && let Err(e) = audit_log.append(event)
+        {
+            warn!("❌ Could not write to the audit log: {e}");
+        }
+    }
+
+    // the user as it is kept: with hashed_commitments, y1 and y2 give way to their digest
+    fn stored(&self, mut user_info: UserInfo) -> UserInfo {
+        if self.hashed_commitments {
+            let zkp = self.groups.get(&user_info.group_id).expect("registered under a known group");
+            let commitment = Commitment::new(
+                std::mem::take(&mut user_info.y1),
+                std::mem::take(&mut user_info.y2),
+            );
+            user_info.commitment_digest = Some(zkp.commitment_digest(&commitment));
+        } else if self.derived_y2 {
+            let zkp = self.groups.get(&user_info.group_id).expect("registered under a known group");
+            // only a y2 that really is y1^i can be left out (not in a group with another beta)
+            let i = ZKP::beta_exponent();
+            if zkp.beta_is_derived() && zkp.derive_y2(&user_info.y1, &i) == user_info.y2 {
+                user_info.y2 = BigUint::ZERO;
+                user_info.y2_derived = true;
             }
-        } else {
-            Err(Status::new(Code::NotFound, format!("AuthId: {auth_id} not found in database")))
         }
+        user_info
     }
-}
 
-#[tokio::main]
-async fn main() {
-    let addr = "127.0.0.1:50051".to_string();
+    // y1, y2 sent with a challenge request, checked against what is stored: None when none were
+    // sent (fine unless only the digest is stored), a mismatch is PermissionDenied
+    fn presented_commitment(
+        zkp: &ZKP,
+        user_info: &UserInfo,
+        subject: &str,
+        y1: &[u8],
+        y2: &[u8],
+    ) -> Result<Option<Commitment>, Status> {
+        if y1.is_empty() && y2.is_empty() {
+            return match user_info.commitment_digest {
+                Some(_) => Err(Status::new(
+                    Code::FailedPrecondition,
+                    format!(
+                        "{subject} commitments are stored hashed, send y1, y2 with the request"
+                    ),
+                )),
+                None => Ok(None),
+            };
+        }
+        let y1 = zkp.decode_element(y1).map_err(|e| invalid_encoding(subject, "y1", e))?;
+        let y2 = zkp.decode_element(y2).map_err(|e| invalid_encoding(subject, "y2", e))?;
+        let commitment = Commitment::new(y1, y2);
+        let matches = match &user_info.commitment_digest {
+            Some(digest) => tokens_match(&zkp.commitment_digest(&commitment), digest),
+            None => commitment.y1 == user_info.y1 && commitment.y2 == user_info.registered_y2(zkp),
+        };
+        if !matches {
+            warn!("❌ Mismatched commitments {subject}");
+            return Err(Status::new(
+                Code::PermissionDenied,
+                format!("{subject} y1/y2 do not match the registered ones"),
+            ));
+        }
+        Ok(Some(commitment))
+    }
 
-    println!("✅ Running the server in {addr}");
+    // y1, y2 as stored, for the flows where the client does not send them again
+    fn stored_commitment(zkp: &ZKP, user_info: &UserInfo) -> Result<Commitment, Status> {
+        match user_info.commitment_digest {
+            Some(_) => Err(Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "User: {} commitments are stored hashed, only CreateAuthenticationChallenge \
+                     with y1, y2 can be used",
+                    user_info.user_name
+                ),
+            )),
+            None => Ok(Commitment::new(user_info.y1.clone(), user_info.registered_y2(zkp))),
+        }
+    }
 
-    let auth_impl = AuthImpl::default();
+    // the checks of verify_complete (known user, encodings, ranges), the run is verified later
+    fn complete_run(&self, request: &VerifyCompleteRequest) -> Result<CompleteRun, Status> {
+        let user_name = &request.user;
+        let user_info_hashmap = &self.user_info.lock().unwrap();
+        // an unknown user is checked against the decoy commitment (x = 1) of the default group
+        // and never valid, so VerifyComplete does not tell which names are registered
+        let user_info = user_info_hashmap.get(user_name);
+        let zkp = match user_info {
+            Some(user_info) => {
+                self.groups.get(&user_info.group_id).expect("registered under a known group")
+            }
+            None => self.groups.resolve(&[]).expect("the default group is always there").1,
+        };
+        let subject = format!("User: {user_name}");
+        let r1 =
+            zkp.decode_element(&request.r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+        let r2 =
+            zkp.decode_element(&request.r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+        let c = zkp.decode_scalar(&request.c).map_err(|e| invalid_encoding(&subject, "c", e))?;
+        let s = zkp.decode_scalar(&request.s).map_err(|e| invalid_encoding(&subject, "s", e))?;
+        if !zkp.is_in_subgroup(&r1) || !zkp.is_in_subgroup(&r2) {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("User: {user_name} r1/r2 are not in the order-q subgroup"),
+            ));
+        }
+        if !zkp.is_scalar(&c) || !zkp.is_scalar(&s) {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("User: {user_name} c/s are not below q"),
+            ));
+        }
 
-    Server::builder()
-        .add_service(AuthServer::new(auth_impl))
-        .serve(addr.parse().expect("could not convert address"))
-        .await
-        .unwrap();
+        let Commitment { y1, y2 } = match user_info {
+            Some(user_info) => Self::stored_commitment(zkp, user_info)?,
+            None => {
+                let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                Commitment::new(y1, y2)
+            }
+        };
+        let decoy = user_info.is_none();
+        Ok(CompleteRun { zkp: zkp.clone(), y1, y2, r1, r2, c, s, decoy })
+    }
+
+    // conformance testing of a client from a capture of one login: its registration, its
+    // challenge request and the response it got, and its answer, checked with the Chaum-Pedersen
+    // verification in this server's groups but nothing stored (no user, challenge or session).
+    // Err when the answer does not even belong to that challenge (unknown group, undecodable
+    // values or ones outside the order-q subgroup, another user, auth_id or binding), otherwise
+    // the outcome of the verification
+    pub fn validate_answer_offline(
+        &self,
+        registration: &RegisterRequest,
+        challenge: (&AuthenticationChallengeRequest, &AuthenticationChallengeResponse),
+        answer: &AuthenticationAnswerRequest,
+    ) -> Result<VerifyOutcome, Status> {
+        let (request, response) = challenge;
+        let subject = format!("User: {}", registration.user);
+        let mismatch = |what: &str| {
+            Status::new(Code::InvalidArgument, format!("{subject} {what} does not match"))
+        };
+
+        let Some((group_id, zkp)) = self.groups.resolve(&registration.group_id) else {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("{subject} group is not supported by this server"),
+            ));
+        };
+        if request.user != registration.user {
+            return Err(mismatch("challenge request user"));
+        }
+        if response.group_id != group_id {
+            return Err(mismatch("challenge group"));
+        }
+        if answer.auth_id != response.auth_id {
+            return Err(mismatch("answer auth_id"));
+        }
+        if answer.binding != response.binding {
+            return Err(mismatch("answer binding"));
+        }
+
+        let element = |field: &str, bytes: &[u8]| {
+            zkp.decode_element(bytes).map_err(|e| invalid_encoding(&subject, field, e))
+        };
+        let (y1, y2) = (element("y1", &registration.y1)?, element("y2", &registration.y2)?);
+        let (r1, r2) = (element("r1", &request.r1)?, element("r2", &request.r2)?);
+        // as in verify_blob: 0, or anything outside the order-q subgroup, is no answer at all
+        if ![&y1, &y2, &r1, &r2].into_iter().all(|element| zkp.is_in_subgroup(element)) {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("{subject} y1/y2/r1/r2 are not in the order-q subgroup"),
+            ));
+        }
+        // c and s as sent: not below q is an outcome, not a decoding error
+        let c = BigUint::from_bytes_be(&response.c);
+        let s = BigUint::from_bytes_be(&answer.s);
+
+        Ok(zkp.verify_detailed(&r1, &r2, &y1, &y2, &c, &s))
+    }
+
+    // verify_authentication under its locks: the outcome when there is nothing to verify (an
+    // exact retry, an expired, replayed... answer, an unknown auth_id), otherwise what the
+    // verification needs, owned, so it can run once the locks are released
+    fn answer_state(
+        &self,
+        request: &AuthenticationAnswerRequest,
+        group_bits: &mut Option<u64>,
+    ) -> AnswerState {
+        let auth_id = &request.auth_id;
+        let verify_results = &mut self.verify_results.lock().unwrap();
+        let retry_ttl = self.policy.answer_retry_ttl;
+
+        // an exact retry sends the very same bytes
+        // the binding is part of it: a relayed s with another binding must not find a success
+        let key = (auth_id.clone(), request.s.clone(), request.binding.clone());
+        if verify_results.peek(&key).is_some_and(|cached| cached.verified_at.elapsed() > retry_ttl)
+        {
+            verify_results.pop(&key);
+        }
+        if let Some(cached) = verify_results.get(&key) {
+            info!("Retried Challenge Solution auth_id: {:?}, same outcome", auth_id);
+            return AnswerState::Done(Box::new(cached.to_result()));
+        }
+
+        let auth_id_to_user_hashmap = &mut self.auth_id_to_user.lock().unwrap();
+        let Some(challenge) = auth_id_to_user_hashmap.get_mut(auth_id) else {
+            return AnswerState::Done(Box::new(Err(answer_failure(
+                Code::NotFound,
+                format!("AuthId: {auth_id} not found in database"),
+                "challenge_not_found",
+                true,
+            ))));
+        };
+
+        // owned: challenge is marked answered further down
+        let user_name = challenge.user_name.clone();
+        let expired = self.verification.challenge_expiry
+            && challenge.issued_at.elapsed() > self.policy.challenge_ttl;
+        // r1, r2 of a committed challenge are only known once it has been revealed
+        let unrevealed = challenge.pending_reveal.is_some();
+        // no r1, r2 at all for a user's challenge: nothing to check the answer against
+        let absent = challenge.decoy.is_none() && challenge.prover_commitment.is_none();
+
+        let user_info_hashmap = &mut self.user_info.lock().unwrap();
+        // a decoy has no user behind it (even if the name was registered since): it goes
+        // through the same steps as a real answer and then fails as a wrong one
+        let user_info = match challenge.decoy {
+            Some(_) => None,
+            None => {
+                Some(user_info_hashmap.get_mut(&user_name).expect("AuthId not found on hashmap"))
+            }
+        };
+
+        let zkp = match &user_info {
+            Some(user_info) => {
+                self.groups.get(&user_info.group_id).expect("registered under a known group")
+            }
+            None => self.groups.resolve(&[]).expect("the default group is always there").1,
+        };
+        *group_bits = Some(zkp.group_bits());
+        // let zkp = ZKP { alpha, beta, p, q }; // avoiding the "field private" error
+
+        let s = match zkp.decode_scalar(&request.s) {
+            Ok(s) => s,
+            Err(e) => {
+                return AnswerState::Done(Box::new(Err(invalid_encoding(
+                    &format!("AuthId: {auth_id}"),
+                    "s",
+                    e,
+                ))));
+            }
+        };
+
+        // insert() is false when this exact s was already submitted for this auth_id
+        // (compared decoded, so padding the same s differently is still a replay)
+        let seen_responses = &mut self.seen_responses.lock().unwrap();
+        let first_submission =
+            seen_responses.get_or_insert_mut(auth_id.clone(), HashSet::new).insert(s.clone());
+        let replayed = self.verification.unique_response && !first_submission;
+        // any answer checked (right or wrong) uses the challenge up
+        let reused = self.verification.single_use_challenge && challenge.answered;
+        if !expired && !unrevealed {
+            challenge.answered = true;
+        }
+
+        // the details say whether a new challenge can help: not for a wrong (or
+        // replayed) answer, the same secret gives the same result
+        let refused = if expired {
+            warn!("❌ Expired Challenge Solution username: {user_name:?}");
+
+            answer_failure(
+                Code::DeadlineExceeded,
+                format!("AuthId: {auth_id} challenge expired"),
+                "challenge_expired",
+                true,
+            )
+        } else if unrevealed {
+            answer_failure(
+                Code::FailedPrecondition,
+                format!("AuthId: {auth_id} challenge not revealed yet"),
+                "challenge_unrevealed",
+                true,
+            )
+        } else if absent {
+            warn!("❌ Challenge without r1, r2 username: {user_name:?}");
+
+            answer_failure(
+                Code::FailedPrecondition,
+                format!("AuthId: {auth_id} challenge has no commitment r1, r2"),
+                "commitment_absent",
+                true,
+            )
+        } else if replayed {
+            warn!("❌ Replayed Challenge Solution username: {user_name:?}");
+
+            answer_failure(
+                Code::AlreadyExists,
+                format!("AuthId: {auth_id} solution already submitted"),
+                "answer_replayed",
+                false,
+            )
+        } else if reused {
+            warn!("❌ Challenge answered twice username: {user_name:?}");
+
+            answer_failure(
+                Code::FailedPrecondition,
+                format!("AuthId: {auth_id} challenge already answered"),
+                "challenge_answered",
+                true,
+            )
+        } else {
+            let (r1, r2, commitment) = match (&challenge.decoy, &user_info) {
+                (Some((r1, r2)), _) => (r1.clone(), r2.clone(), None),
+                (None, Some(user_info)) => {
+                    let (r1, r2) =
+                        challenge.prover_commitment.clone().expect("checked: not absent");
+                    // only the verification without (y1^c, y2^c) needs y1, y2
+                    let commitment = match (&challenge.challenge_terms, &challenge.commitment) {
+                        (Some(_), _) => None,
+                        (None, Some(commitment)) => Some(commitment.clone()),
+                        (None, None) => Some(Commitment::new(
+                            user_info.y1.clone(),
+                            user_info.registered_y2(zkp),
+                        )),
+                    };
+                    (r1, r2, commitment)
+                }
+                (None, None) => unreachable!("a challenge is a decoy or has a user"),
+            };
+            // an answer from another channel than the challenge request fails like a wrong
+            // one, without being verified
+            let same_binding = request.binding == challenge.binding;
+            // a decoy has no registration to replay the answer against
+            #[cfg(feature = "audit")]
+            let record = match &user_info {
+                Some(user_info) if self.session_records && same_binding => {
+                    Some(SessionRecord::new(zkp, auth_id, user_info, challenge, &request.s))
+                }
+                _ => None,
+            };
+            return AnswerState::Check(Box::new(AnswerCheck {
+                zkp: zkp.clone(),
+                user_name,
+                decoy: challenge.decoy.is_some(),
+                verify: same_binding,
+                r1,
+                r2,
+                c: challenge.c.clone(),
+                s,
+                challenge_terms: challenge.challenge_terms.clone(),
+                commitment,
+                #[cfg(feature = "audit")]
+                record,
+            }));
+        };
+
+        let result = Err(refused);
+        if let Some(cached) = CachedVerification::of(&result) {
+            verify_results.put(key, cached);
+        }
+        AnswerState::Done(Box::new(result))
+    }
+
+    // verify_authentication once the answer was verified (outcome None: not verified at all, or
+    // by a backend that only says yes or no): the session, or the PermissionDenied of a wrong
+    // answer, kept for the exact retries of policy.answer_retry_ttl
+    fn finish_answer(
+        &self,
+        request: &AuthenticationAnswerRequest,
+        #[allow(unused_mut)] mut check: AnswerCheck,
+        outcome: Option<VerifyOutcome>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        let auth_id = &request.auth_id;
+        let user_name = &check.user_name;
+
+        #[cfg(feature = "audit")]
+        if let Some(record) = check.record.take() {
+            self.record(AuthEvent::Session(Box::new(record.with_outcome(outcome))));
+        }
+
+        // released before verify_results is locked, answer_state takes them the other way round
+        let result = {
+            let user_info_hashmap = &mut self.user_info.lock().unwrap();
+            // the user as it is now, gone if it was removed while the answer was verified
+            let mut user_info =
+                if check.decoy { None } else { user_info_hashmap.get_mut(user_name) };
+            if let (true, Some(user_info)) = (check.verify, &mut user_info) {
+                user_info.s = check.s.clone();
+            }
+            self.answer_result(auth_id, &check, outcome, user_info)
+        };
+
+        if let Some(cached) = CachedVerification::of(&result) {
+            let key = (auth_id.clone(), request.s.clone(), request.binding.clone());
+            self.verify_results.lock().unwrap().put(key, cached);
+        }
+        result
+    }
+
+    fn answer_result(
+        &self,
+        auth_id: &str,
+        check: &AnswerCheck,
+        outcome: Option<VerifyOutcome>,
+        user_info: Option<&mut UserInfo>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        let user_name = &check.user_name;
+        match (outcome.is_some_and(VerifyOutcome::is_valid), user_info) {
+            (true, Some(user_info)) => {
+                let session_id = self.mint_session(user_info);
+                let receipt = self.receipt(auth_id, user_name, &check.c, &check.s);
+
+                info!("✅ Correct Challenge Solution username: {user_name:?}");
+                #[cfg(feature = "audit")]
+                self.record(AuthEvent::LoginSucceeded { user: user_name.clone() });
+
+                Ok(Response::new(AuthenticationAnswerResponse { session_id, receipt }))
+            }
+            (_, user_info) => {
+                warn!("❌ Wrong Challenge Solution username: {user_name:?}",);
+                if check.verify && user_info.is_some() {
+                    warn_if_swapped(user_name, &check.c, &check.s);
+                }
+                #[cfg(feature = "audit")]
+                self.record(AuthEvent::LoginFailed { user: user_name.clone() });
+
+                let message = format!("AuthId: {auth_id} bad solution to the challenge");
+                // the code is VerifyOutcome::as_code when the verification says why
+                Err(match outcome {
+                    Some(outcome) => answer_failure(
+                        Code::PermissionDenied,
+                        message,
+                        outcome.as_code(),
+                        outcome.is_retryable(),
+                    ),
+                    None => answer_failure(Code::PermissionDenied, message, "wrong_answer", false),
+                })
+            }
+        }
+    }
+
+    fn check_admin(&self, metadata: &tonic::metadata::MetadataMap) -> Result<(), Status> {
+        let Some(expected) = &self.admin_token else {
+            return Err(Status::new(Code::PermissionDenied, "admin RPCs are disabled"));
+        };
+
+        let given = metadata.get("x-admin-token").and_then(|token| token.to_str().ok());
+        match given {
+            Some(given) if tokens_match(given.as_bytes(), expected.as_bytes()) => Ok(()),
+            Some(_) => Err(Status::new(Code::PermissionDenied, "wrong admin token")),
+            None => Err(Status::new(Code::Unauthenticated, "missing x-admin-token")),
+        }
+    }
+}
+
+// where verify_authentication is once its locks are released: done, or an answer to verify
+enum AnswerState {
+    Done(Box<Result<Response<AuthenticationAnswerResponse>, Status>>),
+    Check(Box<AnswerCheck>),
+}
+
+// an answer to a challenge with everything its verification needs, owned so it runs without
+// any of the locks (answers of different users verify in parallel on the verify_pool)
+struct AnswerCheck {
+    zkp: ZKP,
+    user_name: String,
+    // a challenge of an unknown user: the same work, then refused whatever comes out
+    decoy: bool,
+    // false: refused as a wrong answer without running the verification (another binding)
+    verify: bool,
+    r1: BigUint,
+    r2: BigUint,
+    c: BigUint,
+    s: BigUint,
+    // (y1^c, y2^c) computed when c was issued, None falls back to the full verify
+    challenge_terms: Option<(BigUint, BigUint)>,
+    // y1, y2 of that full verify, None for a decoy (x = 1) or when challenge_terms are there
+    commitment: Option<Commitment>,
+    #[cfg(feature = "audit")]
+    record: Option<SessionRecord>,
+}
+
+// a backend error (bad encoding, value out of the subgroup) counts as a failed verification
+#[allow(clippy::too_many_arguments)]
+fn backend_verify(
+    backend: &dyn AuthBackend,
+    zkp: &ZKP,
+    y1: &BigUint,
+    y2: &BigUint,
+    r1: &BigUint,
+    r2: &BigUint,
+    c: &BigUint,
+    s: &BigUint,
+) -> bool {
+    let (y1, y2) = (zkp.encode_element(y1), zkp.encode_element(y2));
+    let (r1, r2) = (zkp.encode_element(r1), zkp.encode_element(r2));
+    let (c, s) = (zkp.encode_scalar(c), zkp.encode_scalar(s));
+    backend.verify(zkp, &y1, &y2, &r1, &r2, &c, &s).unwrap_or(false)
+}
+
+impl AnswerCheck {
+    // None from a backend that only says yes or no
+    fn run(&self, backend: &dyn AuthBackend) -> Option<VerifyOutcome> {
+        let AnswerCheck { zkp, r1, r2, c, s, .. } = self;
+        let outcome = match (&self.challenge_terms, &self.commitment) {
+            (Some(terms), _) => Some(zkp.verify_precomputed_detailed(r1, r2, s, terms)),
+            (None, Some(Commitment { y1, y2 })) => {
+                backend_verify(backend, zkp, y1, y2, r1, r2, c, s).then_some(VerifyOutcome::Valid)
+            }
+            (None, None) => {
+                let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                let valid = backend_verify(backend, zkp, &y1, &y2, r1, r2, c, s);
+                valid.then_some(VerifyOutcome::Valid)
+            }
+        };
+        if !self.decoy {
+            return outcome;
+        }
+        // the same work as for a real user, and reported as a wrong answer from that backend
+        // whatever it says
+        match std::hint::black_box(outcome) {
+            Some(VerifyOutcome::Valid) => {
+                self.challenge_terms.is_some().then_some(VerifyOutcome::R1Mismatch)
+            }
+            outcome => outcome,
+        }
+    }
+}
+
+// one async lock per auth_id being answered, dropped from the map with its last answer
+#[derive(Debug, Default)]
+pub struct AnswerLocks {
+    locks: Mutex<HashMap<String, AnswerLock>>,
+}
+
+// answers holding the lock or waiting for it
+#[derive(Debug, Default)]
+struct AnswerLock {
+    lock: Arc<tokio::sync::Mutex<()>>,
+    answers: usize,
+}
+
+impl AnswerLocks {
+    // the claim is taken before waiting, so an answer dropped while it waits (the client went
+    // away) gives its place back too
+    async fn acquire(&self, auth_id: &str) -> AnswerGuard<'_> {
+        let lock = {
+            let locks = &mut self.locks.lock().unwrap();
+            let entry = locks.entry(auth_id.to_string()).or_default();
+            entry.answers += 1;
+            entry.lock.clone()
+        };
+        let claim = AnswerClaim { locks: self, auth_id: auth_id.to_string() };
+        AnswerGuard { _held: lock.lock_owned().await, _claim: claim }
+    }
+
+    // auth_ids with an answer being handled
+    pub fn in_use(&self) -> usize {
+        self.locks.lock().unwrap().len()
+    }
+}
+
+// the lock of one auth_id, held until the guard is dropped, wherever the answer stops
+struct AnswerGuard<'a> {
+    _held: tokio::sync::OwnedMutexGuard<()>,
+    _claim: AnswerClaim<'a>,
+}
+
+struct AnswerClaim<'a> {
+    locks: &'a AnswerLocks,
+    auth_id: String,
+}
+
+impl Drop for AnswerClaim<'_> {
+    fn drop(&mut self) {
+        let locks = &mut self.locks.locks.lock().unwrap();
+        if let Some(entry) = locks.get_mut(&self.auth_id) {
+            entry.answers -= 1;
+            if entry.answers == 0 {
+                locks.remove(&self.auth_id);
+            }
+        }
+    }
+}
+
+// a decoded (r1, r2, c, s) with the group and commitment of its user, owned so it can be
+// verified away from the locks (and from &self, see verify_bulk)
+struct CompleteRun {
+    zkp: ZKP,
+    y1: BigUint,
+    y2: BigUint,
+    r1: BigUint,
+    r2: BigUint,
+    c: BigUint,
+    s: BigUint,
+    // a run of an unknown user: the same work, never valid
+    decoy: bool,
+}
+
+impl CompleteRun {
+    fn verify(&self) -> bool {
+        let valid = self.zkp.verify(&self.r1, &self.r2, &self.y1, &self.y2, &self.c, &self.s);
+        std::hint::black_box(valid) && !self.decoy
+    }
+}
+
+// x-request-id sent by the caller, or a fresh one: it tags the logs of the request
+// (tracing span field request_id) and is echoed back in the response metadata, so only a
+// short run of visible ASCII characters is taken as is
+fn request_id<T>(request: &Request<T>) -> String {
+    request
+        .metadata()
+        .get("x-request-id")
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| {
+            (1..=MAX_REQUEST_ID_LENGTH).contains(&id.len())
+                && id.bytes().all(|byte| byte.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| ZKP::generate_random_string(16))
+}
+
+// on success the id goes in the response headers, on failure in the status metadata,
+// which tonic sends as trailers
+fn echo_request_id<T>(
+    mut result: Result<Response<T>, Status>,
+    request_id: &str,
+) -> Result<Response<T>, Status> {
+    if let Ok(id) = request_id.parse::<MetadataValue<Ascii>>() {
+        match &mut result {
+            Ok(response) => response.metadata_mut().insert("x-request-id", id),
+            Err(status) => status.metadata_mut().insert("x-request-id", id),
+        };
+    }
+    result
+}
+
+// bytes the group cannot decode (for an EC group: not a point of the curve) are the caller's mistake
+// a refused answer, with AnswerFailure { code, retryable } as its details
+fn answer_failure(code: Code, message: String, failure: &str, retryable: bool) -> Status {
+    let details = AnswerFailure { code: failure.to_string(), retryable }.encode_to_vec();
+    Status::with_details(code, message, Bytes::from(details))
+}
+
+fn invalid_encoding(subject: &str, field: &str, error: ZKPError) -> Status {
+    Status::new(Code::InvalidArgument, format!("{subject} {field}: {error}"))
+}
+
+// c and s are both below q and travel as bare bytes, so a client mixing up the two fields sends
+// the issued c back as s: still the usual wrong answer, this only tells whoever debugs the client
+fn warn_if_swapped(user_name: &str, c: &BigUint, s: &BigUint) {
+    if c == s {
+        warn!("⚠️ Possible c/s swap username: {user_name:?}: the answer s is the issued c");
+    }
+}
+
+// a value the backend refuses (e.g. not in the order-q subgroup), also the caller's mistake
+fn rejected(subject: &str, error: ZKPError) -> Status {
+    Status::new(Code::InvalidArgument, format!("{subject} {error}"))
+}
+
+// compares every byte whatever the first difference, so the time taken does not leak
+// how much of the token a caller guessed right
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given.iter().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[derive(Debug)]
+pub struct ChallengeInfo {
+    pub user_name: String,
+    // the answer must arrive within policy.challenge_ttl of this instant
+    pub issued_at: Instant,
+    // coin-flipping flow only: nonce of the committed c, until reveal_challenge receives r1, r2
+    pub pending_reveal: Option<[u8; 32]>,
+    // (y1^c, y2^c) computed when c was issued, so verify_authentication only computes
+    // alpha^s and beta^s; None falls back to the full verify
+    pub challenge_terms: Option<(BigUint, BigUint)>,
+    // y1, y2 sent with the challenge request (checked against the stored ones or their digest),
+    // None: the answer is checked against the stored ones
+    pub commitment: Option<Commitment>,
+    // the prover's r1, r2 the answer is checked with, None until they are known (a committed
+    // challenge before its reveal): such an answer is refused, never compared against zeros
+    pub prover_commitment: Option<(BigUint, BigUint)>,
+    // the c issued, kept per challenge: a user may have several open at once
+    pub c: BigUint,
+    // r1, r2 of a challenge issued to a user that does not exist (no UserInfo to keep them
+    // in), such a challenge never verifies
+    pub decoy: Option<(BigUint, BigUint)>,
+    // binding of the challenge request, the answer must come with the same one
+    pub binding: Vec<u8>,
+    // an answer was checked against this challenge already (verification.single_use_challenge)
+    pub answered: bool,
+}
+
+#[derive(Debug)]
+pub struct SessionInfo {
+    pub user_name: String,
+    // group the user proved knowledge of x in, who_am_i refuses the session for any other
+    pub group_id: [u8; 32],
+    pub expires_at: Instant,
+}
+
+// (auth_id, s as sent, binding): what an exact retry of an answer sends again
+pub type RetryKey = (String, Vec<u8>, Vec<u8>);
+
+// outcome of verify_authentication replayed to an exact retry of the same answer
+#[derive(Debug)]
+pub struct CachedVerification {
+    // the response (session_id and receipt), or the code, message and details of the rejection
+    pub outcome: Result<AuthenticationAnswerResponse, (Code, String, Bytes)>,
+    pub verified_at: Instant,
+}
+
+impl CachedVerification {
+    // only the outcomes of an actual verification: a new session or a wrong answer
+    fn of(result: &Result<Response<AuthenticationAnswerResponse>, Status>) -> Option<Self> {
+        let outcome = match result {
+            Ok(response) => Ok(response.get_ref().clone()),
+            Err(status) if status.code() == Code::PermissionDenied => Err((
+                status.code(),
+                status.message().to_string(),
+                Bytes::copy_from_slice(status.details()),
+            )),
+            Err(_) => return None,
+        };
+        Some(CachedVerification { outcome, verified_at: Instant::now() })
+    }
+
+    fn to_result(&self) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        match &self.outcome {
+            Ok(response) => Ok(Response::new(response.clone())),
+            Err((code, message, details)) => {
+                Err(Status::with_details(*code, message.clone(), details.clone()))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UserInfo {
+    // registration
+    pub user_name: String,
+    pub user_id: String,
+    // fingerprint of the group y1, y2 (and every later run) live in
+    pub group_id: [u8; 32],
+    pub y1: BigUint,
+    pub y2: BigUint,
+    // hashed_commitments: ZKP::commitment_digest of y1, y2, which are then left at 0
+    pub commitment_digest: Option<[u8; 32]>,
+    // derived_y2: y2 is left at 0 and recomputed from y1, see registered_y2
+    pub y2_derived: bool,
+    // authorization
+    pub r1: BigUint,
+    pub r2: BigUint,
+    // verification
+    pub s: BigUint,
+    pub session_id: String,
+}
+
+// only the username and the public commitments: s (with r1, r2 and c it is the last answer of
+// the user) and the session_id (a bearer token) show as ***, so a {:?} in a log cannot leak them
+impl fmt::Debug for UserInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserInfo")
+            .field("user_name", &self.user_name)
+            .field("y1", &self.y1)
+            .field("y2", &self.y2)
+            .field("commitment_digest", &self.commitment_digest.map(hex::encode))
+            .field("y2_derived", &self.y2_derived)
+            .field("s", &format_args!("***"))
+            .field("session_id", &format_args!("***"))
+            .finish_non_exhaustive()
+    }
+}
+
+impl UserInfo {
+    // y2 as it was registered, y1^ZKP::beta_exponent() mod p when only y1 is kept
+    // (0 for a user stored hashed)
+    pub fn registered_y2(&self, zkp: &ZKP) -> BigUint {
+        if self.y2_derived {
+            zkp.derive_y2(&self.y1, &ZKP::beta_exponent())
+        } else {
+            self.y2.clone()
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Auth for AuthImpl {
+    async fn register(
+        &self,
+        request: Request<RegisterRequest>,
+    ) -> Result<Response<RegisterResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("register", %request_id);
+        let started = Instant::now();
+        let group_bits =
+            self.groups.resolve(&request.get_ref().group_id).map(|(_, zkp)| zkp.group_bits());
+
+        let result = span.in_scope(|| {
+            let request = request.into_inner();
+
+            let user_name = request.user.clone();
+            info!("Processing Registration username: {:?}", user_name);
+
+            let user_info = self.stored(self.checked_registration(request)?);
+
+            // looked up and inserted under one lock: of two concurrent registrations of the same
+            // name exactly one gets in, and a challenge never sees a half-registered user
+            let user_info_hashmap = &mut self.user_info.lock().unwrap();
+            match user_info_hashmap.entry(user_name.clone()) {
+                Entry::Occupied(_) => {
+                    warn!("❌ Registration of an existing username: {:?}", user_name);
+                    Err(Status::new(
+                        Code::AlreadyExists,
+                        format!("User: {user_name} is already registered"),
+                    ))
+                }
+                Entry::Vacant(entry) => {
+                    self.claim_handle(&user_info)?;
+                    entry.insert(user_info);
+                    info!("✅ Successful Registration username: {:?}", user_name);
+                    #[cfg(feature = "audit")]
+                    self.record(AuthEvent::Registered { user: user_name.clone() });
+                    Ok(Response::new(RegisterResponse {}))
+                }
+            }
+        });
+        self.observe("register", group_bits, started);
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn create_authentication_challenge(
+        &self,
+        request: Request<AuthenticationChallengeRequest>,
+    ) -> Result<Response<AuthenticationChallengeResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("create_authentication_challenge", %request_id);
+        let started = Instant::now();
+        let mut group_bits = None;
+
+        let result = span.in_scope(|| {
+            self.require_mode(ServerMode::Interactive, "CreateAuthenticationChallenge")?;
+            let request = request.into_inner();
+
+            let user_name = request.user;
+            info!("Processing Challenge Request username: {:?}", user_name);
+
+            if let Some(secret) = &self.stateless_secret {
+                return self.stateless_challenge(
+                    secret,
+                    &user_name,
+                    &request.r1,
+                    &request.r2,
+                    &request.binding,
+                    &mut group_bits,
+                );
+            }
+
+            let mut user_info_hashmap = self.user_info.lock().unwrap();
+            let mut user_info = user_info_hashmap.get_mut(&user_name);
+
+            // an unknown user gets a challenge too, against the default group and a decoy
+            // commitment (x = 1): same checks, same work, same response, so user names cannot be
+            // probed here; the answer is then refused with the PermissionDenied of a wrong one
+            let (group_id, zkp) = match &user_info {
+                Some(user_info) => (
+                    user_info.group_id,
+                    self.groups.get(&user_info.group_id).expect("registered under a known group"),
+                ),
+                None => self.groups.resolve(&[]).expect("the default group is always there"),
+            };
+            group_bits = Some(zkp.group_bits());
+            let subject = format!("User: {user_name}");
+            let r1 =
+                zkp.decode_element(&request.r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+            let r2 =
+                zkp.decode_element(&request.r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+            // only the digest may be stored, then these are the y1, y2 the answer is checked with
+            // (a decoy ignores them)
+            let presented = match user_info.as_deref() {
+                Some(user_info) => {
+                    Self::presented_commitment(zkp, user_info, &subject, &request.y1, &request.y2)?
+                }
+                None => None,
+            };
+            // what the answer is checked against: the presented or stored y1, y2, or the decoy's
+            let commitment = match (&presented, user_info.as_deref()) {
+                (Some(commitment), _) => commitment.clone(),
+                (None, Some(user_info)) => {
+                    Commitment::new(user_info.y1.clone(), user_info.registered_y2(zkp))
+                }
+                (None, None) => {
+                    let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                    Commitment::new(y1, y2)
+                }
+            };
+            let transcript = ChallengeTranscript::new(zkp, &commitment, &r1, &r2);
+            let c = self
+                .new_challenge(&transcript, &request.r1, &request.r2, &request.binding)
+                .map_err(|e| match e {
+                    ZKPError::ZeroChallenge => Status::new(Code::Internal, e.to_string()),
+                    e => rejected(&subject, e),
+                })?;
+            self.check_fresh_commitment(&subject, &group_id, &r1, &r2)?;
+            let auth_id = ZKP::generate_session_id();
+
+            // only the Chaum-Pedersen verification can use (y1^c, y2^c) computed now
+            let challenge_terms = self
+                .backend
+                .is_chaum_pedersen()
+                .then(|| zkp.challenge_terms(&commitment.y1, &commitment.y2, &c));
+            let (decoy, prover_commitment) = match &mut user_info {
+                Some(user_info) => {
+                    user_info.r1 = r1.clone();
+                    user_info.r2 = r2.clone();
+                    (None, Some((r1, r2)))
+                }
+                None => (Some((r1, r2)), None),
+            };
+
+            if decoy.is_some() {
+                warn!("Challenge Request for unknown username: {user_name:?}, decoy issued");
+            } else {
+                info!("✅ Successful Challenge Request username: {user_name:?}");
+            }
+            // released first: answer_state takes auth_id_to_user, then user_info
+            drop(user_info_hashmap);
+
+            let auth_id_to_user = &mut self.auth_id_to_user.lock().unwrap();
+            auth_id_to_user.put(
+                auth_id.clone(),
+                ChallengeInfo {
+                    user_name: user_name.clone(),
+                    issued_at: Instant::now(),
+                    pending_reveal: None,
+                    challenge_terms,
+                    commitment: presented,
+                    prover_commitment,
+                    c: c.clone(),
+                    decoy,
+                    binding: request.binding.clone(),
+                    answered: false,
+                },
+            );
+
+            Ok(Response::new(AuthenticationChallengeResponse {
+                auth_id,
+                c: zkp.encode_scalar(&c),
+                group_id: group_id.to_vec(),
+                binding: request.binding,
+            }))
+        });
+        self.observe("challenge", group_bits, started);
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn verify_authentication(
+        &self,
+        request: Request<AuthenticationAnswerRequest>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("verify_authentication", %request_id);
+        let started = Instant::now();
+        let mut group_bits = None;
+
+        let request = request.into_inner();
+        let auth_id = request.auth_id.clone();
+
+        // answers to one challenge are handled one after the other (two copies of one answer
+        // racing each other cannot end up as a success and a replay error), answers to other
+        // challenges in parallel: no std lock is held while one is being verified. Only for an
+        // auth_id that was issued, any other goes straight to its error (or cached retry) and
+        // a stateless one has no state to race over
+        let issued = self.stateless_secret.is_none()
+            && self.auth_id_to_user.lock().unwrap().contains(&auth_id);
+        let answering = if issued { Some(self.answer_locks.acquire(&auth_id).await) } else { None };
+
+        span.in_scope(|| info!("Processing Challenge Solution auth_id: {:?}", auth_id));
+        let state = match &self.stateless_secret {
+            Some(secret) => {
+                let verified = self.stateless_verify(
+                    secret,
+                    &auth_id,
+                    &request.s,
+                    &request.binding,
+                    &mut group_bits,
+                );
+                AnswerState::Done(Box::new(verified.instrument(span.clone()).await))
+            }
+            // the locks are released at the end of answer_state, the verification runs after it
+            None => span.in_scope(|| self.answer_state(&request, &mut group_bits)),
+        };
+
+        // a session minted now (not one handed back again to a retry) goes through on_verified
+        let minted = self.stateless_secret.is_some() || matches!(state, AnswerState::Check(_));
+        let result = match state {
+            AnswerState::Done(result) => *result,
+            AnswerState::Check(check) => {
+                let (check, outcome) = if check.verify {
+                    let backend = self.backend.clone();
+                    let verify = move || {
+                        let outcome = check.run(&*backend);
+                        (check, outcome)
+                    };
+                    self.verify_pool.run(verify).await
+                } else {
+                    (check, None)
+                };
+                span.in_scope(|| self.finish_answer(&request, *check, outcome))
+            }
+        };
+        // still under the answer lock, so a retry of the answer waits for the hook
+        let result = match result {
+            Ok(response) if minted => {
+                match self.provision_session(&response.get_ref().session_id).await {
+                    Ok(()) => Ok(response),
+                    Err(status) => {
+                        // the cached success would hand the revoked session to a retry
+                        let key: RetryKey =
+                            (auth_id.clone(), request.s.clone(), request.binding.clone());
+                        self.verify_results.lock().unwrap().pop(&key);
+                        Err(status)
+                    }
+                }
+            }
+            result => result,
+        };
+        drop(answering);
+
+        // before the jitter, which is not the verification's own latency
+        self.observe("verify", group_bits, started);
+
+        // same delay for success and failure, so the jitter masks which branch was taken
+        if let Some(max_jitter) = self.verify_jitter {
+            let delay = rand::thread_rng().gen_range(0..=max_jitter.as_millis() as u64);
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn get_parameters(
+        &self,
+        _request: Request<ParametersRequest>,
+    ) -> Result<Response<ParametersResponse>, Status> {
+        // the group new users register under, whichever the server was built with
+        let zkp = self.groups.resolve(&[]).expect("the default group is always there").1;
+        let (alpha, beta, p, q) = zkp.constants();
+        let receipt_key = self.receipt_signer.as_ref().map(ReceiptSigner::public_key);
+
+        Ok(Response::new(ParametersResponse {
+            p: p.to_bytes_be(),
+            q: q.to_bytes_be(),
+            alpha: alpha.to_bytes_be(),
+            beta: beta.to_bytes_be(),
+            challenge_ttl_secs: self.policy.challenge_ttl.as_secs(),
+            session_ttl_secs: self.policy.session_ttl.as_secs(),
+            registration_pow_bits: self.policy.registration_pow_bits,
+            receipt_key_y1: receipt_key.map(|key| key.y1.to_bytes_be()).unwrap_or_default(),
+            receipt_key_y2: receipt_key.map(|key| key.y2.to_bytes_be()).unwrap_or_default(),
+            beta_derived: zkp.beta_is_derived(),
+        }))
+    }
+
+    async fn who_am_i(
+        &self,
+        request: Request<WhoAmIRequest>,
+    ) -> Result<Response<WhoAmIResponse>, Status> {
+        let request = request.into_inner();
+        let session_id = request.session_id;
+
+        let sessions = &mut self.sessions.lock().unwrap();
+
+        match sessions.get(&session_id) {
+            // a login in one group says nothing of the same name in another (e.g. mid-migration)
+            Some(session)
+                if !request.group_id.is_empty() && request.group_id != session.group_id =>
+            {
+                warn!("❌ Session of username: {:?} used for another group", session.user_name);
+                Err(Status::new(
+                    Code::PermissionDenied,
+                    format!("Session: {session_id} belongs to another group"),
+                ))
+            }
+            Some(session) if session.expires_at > Instant::now() => {
+                Ok(Response::new(WhoAmIResponse {
+                    user: session.user_name.clone(),
+                    group_id: session.group_id.to_vec(),
+                }))
+            }
+            Some(_) => {
+                sessions.pop(&session_id);
+                Err(Status::new(Code::Unauthenticated, format!("Session: {session_id} expired")))
+            }
+            None => {
+                Err(Status::new(Code::Unauthenticated, format!("Session: {session_id} not found")))
+            }
+        }
+    }
+
+    async fn create_committed_challenge(
+        &self,
+        request: Request<CommittedChallengeRequest>,
+    ) -> Result<Response<CommittedChallengeResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("create_committed_challenge", %request_id);
+        let started = Instant::now();
+        let mut group_bits = None;
+
+        let result = span.in_scope(|| {
+            self.require_mode(ServerMode::Interactive, "CreateCommittedChallenge")?;
+            let request = request.into_inner();
+            let user_name = request.user;
+            info!("Processing Committed Challenge Request username: {:?}", user_name);
+
+            let user_info_hashmap = self.user_info.lock().unwrap();
+            let user_info = user_info_hashmap.get(&user_name);
+
+            // an unknown user gets a committed challenge too, against the default group and the
+            // decoy commitment (x = 1) as in create_authentication_challenge: the reveal and the
+            // answer then go through the same steps, and the answer fails as a wrong one
+            let zkp = match &user_info {
+                Some(user_info) => {
+                    self.groups.get(&user_info.group_id).expect("registered under a known group")
+                }
+                None => self.groups.resolve(&[]).expect("the default group is always there").1,
+            };
+            group_bits = Some(zkp.group_bits());
+            let commitment = match user_info {
+                Some(user_info) => Self::stored_commitment(zkp, user_info)?,
+                None => {
+                    let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                    Commitment::new(y1, y2)
+                }
+            };
+            let (c, nonce, challenge_commitment) = zkp.commit_challenge();
+            let auth_id = ZKP::generate_session_id();
+
+            let challenge_terms = self
+                .backend
+                .is_chaum_pedersen()
+                .then(|| zkp.challenge_terms(&commitment.y1, &commitment.y2, &c));
+            // the r1, r2 of a decoy are only known at the reveal, zeros until then: an answer
+            // before the reveal is refused as unrevealed, so they are never verified
+            let decoy = match user_info {
+                Some(_) => None,
+                None => {
+                    warn!(
+                        "Committed Challenge Request for unknown username: {:?}, decoy issued",
+                        user_name
+                    );
+                    Some((BigUint::ZERO, BigUint::ZERO))
+                }
+            };
+            // released first: answer_state and reveal_challenge take auth_id_to_user, then
+            // user_info
+            drop(user_info_hashmap);
+
+            let auth_id_to_user = &mut self.auth_id_to_user.lock().unwrap();
+            auth_id_to_user.put(
+                auth_id.clone(),
+                ChallengeInfo {
+                    user_name: user_name.clone(),
+                    issued_at: Instant::now(),
+                    pending_reveal: Some(nonce),
+                    challenge_terms,
+                    commitment: None,
+                    prover_commitment: None,
+                    c,
+                    decoy,
+                    binding: Vec::new(),
+                    answered: false,
+                },
+            );
+
+            Ok(Response::new(CommittedChallengeResponse {
+                auth_id,
+                challenge_commitment: challenge_commitment.to_vec(),
+            }))
+        });
+        self.observe("committed_challenge", group_bits, started);
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn reveal_challenge(
+        &self,
+        request: Request<RevealChallengeRequest>,
+    ) -> Result<Response<RevealChallengeResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("reveal_challenge", %request_id);
+        let started = Instant::now();
+        let mut group_bits = None;
+
+        let result = span.in_scope(|| {
+            let request = request.into_inner();
+            let auth_id = request.auth_id;
+
+            let auth_id_to_user_hashmap = &mut self.auth_id_to_user.lock().unwrap();
+            let Some(challenge) = auth_id_to_user_hashmap.get_mut(&auth_id) else {
+                return Err(Status::new(
+                    Code::NotFound,
+                    format!("AuthId: {auth_id} not found in database"),
+                ));
+            };
+
+            if self.verification.challenge_expiry
+                && challenge.issued_at.elapsed() > self.policy.challenge_ttl
+            {
+                return Err(Status::new(
+                    Code::DeadlineExceeded,
+                    format!("AuthId: {auth_id} challenge expired"),
+                ));
+            }
+
+            if challenge.pending_reveal.is_none() {
+                return Err(Status::new(
+                    Code::FailedPrecondition,
+                    format!("AuthId: {auth_id} has no challenge waiting to be revealed"),
+                ));
+            }
+
+            let user_info_hashmap = &mut self.user_info.lock().unwrap();
+            // a decoy has no user behind it: its r1, r2 and c stay on the challenge
+            let mut user_info = match challenge.decoy {
+                Some(_) => None,
+                None => Some(
+                    user_info_hashmap
+                        .get_mut(&challenge.user_name)
+                        .expect("AuthId not found on hashmap"),
+                ),
+            };
+
+            let (group_id, zkp) = match &user_info {
+                Some(user_info) => (
+                    user_info.group_id,
+                    self.groups.get(&user_info.group_id).expect("registered under a known group"),
+                ),
+                None => self.groups.resolve(&[]).expect("the default group is always there"),
+            };
+            group_bits = Some(zkp.group_bits());
+            let subject = format!("AuthId: {auth_id}");
+            let r1 =
+                zkp.decode_element(&request.r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+            let r2 =
+                zkp.decode_element(&request.r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+            if !zkp.is_in_subgroup(&r1) || !zkp.is_in_subgroup(&r2) {
+                return Err(Status::new(
+                    Code::InvalidArgument,
+                    format!("AuthId: {auth_id} r1/r2 are not in the order-q subgroup"),
+                ));
+            }
+            self.check_fresh_commitment(&subject, &group_id, &r1, &r2)?;
+
+            // revealed only once: r1, r2 cannot be swapped once c is known
+            let nonce = challenge.pending_reveal.take().expect("checked above");
+
+            match (&mut challenge.decoy, &mut user_info) {
+                (Some(decoy), _) => *decoy = (r1, r2),
+                (None, Some(user_info)) => {
+                    challenge.prover_commitment = Some((r1.clone(), r2.clone()));
+                    user_info.r1 = r1;
+                    user_info.r2 = r2;
+                }
+                (None, None) => unreachable!("a challenge is a decoy or has a user"),
+            }
+
+            info!("✅ Revealed Challenge username: {:?}", challenge.user_name);
+
+            Ok(Response::new(RevealChallengeResponse {
+                c: zkp.encode_scalar(&challenge.c),
+                nonce: nonce.to_vec(),
+            }))
+        });
+        self.observe("reveal", group_bits, started);
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn list_users(
+        &self,
+        request: Request<ListUsersRequest>,
+    ) -> Result<Response<ListUsersResponse>, Status> {
+        self.check_admin(request.metadata())?;
+
+        // only the public part of each user: no challenge, response or session
+        let user_info_hashmap = &self.user_info.lock().unwrap();
+        let users = user_info_hashmap
+            .values()
+            .map(|user_info| {
+                let zkp =
+                    self.groups.get(&user_info.group_id).expect("registered under a known group");
+                // a user stored hashed has no y1, y2 to show
+                let (y1, y2) = match user_info.commitment_digest {
+                    Some(_) => (Vec::new(), Vec::new()),
+                    None => (
+                        zkp.encode_element(&user_info.y1),
+                        zkp.encode_element(&user_info.registered_y2(zkp)),
+                    ),
+                };
+                RegisteredUser {
+                    user: user_info.user_name.clone(),
+                    user_id: user_info.user_id.clone(),
+                    y1,
+                    y2,
+                    group_id: user_info.group_id.to_vec(),
+                    commitment_digest: user_info
+                        .commitment_digest
+                        .map_or_else(Vec::new, |digest| digest.to_vec()),
+                }
+            })
+            .collect();
+
+        Ok(Response::new(ListUsersResponse { users }))
+    }
+
+    async fn verify_complete(
+        &self,
+        request: Request<VerifyCompleteRequest>,
+    ) -> Result<Response<VerifyCompleteResponse>, Status> {
+        let run = self.complete_run(&request.into_inner())?;
+
+        // stateless: nothing is recorded, so the same run can be checked any number of times
+        // anyone may send one, so its modpows run on the verify_pool, not on a runtime worker
+        let valid = self.verify_pool.run(move || run.verify()).await;
+
+        Ok(Response::new(VerifyCompleteResponse { valid }))
+    }
+
+    type VerifyBulkStream = ReceiverStream<Result<VerifyBulkResult, Status>>;
+
+    async fn verify_bulk(
+        &self,
+        request: Request<VerifyBulkRequest>,
+    ) -> Result<Response<Self::VerifyBulkStream>, Status> {
+        self.check_admin(request.metadata())?;
+
+        // looked up and decoded now, the verifications run one after the other on the verify_pool
+        // and a task streams each result as it is known; the bounded channel keeps it at most VERIFY_BULK_BUFFER
+        // results ahead of the client, and a dropped stream stops it
+        // admins may know who is registered: an unknown user is an error here, not a decoy
+        let runs: Vec<_> = request
+            .into_inner()
+            .runs
+            .iter()
+            .map(|run| match self.complete_run(run) {
+                Ok(complete) if complete.decoy => Err(Status::new(
+                    Code::NotFound,
+                    format!("User: {} not found in database", run.user),
+                )),
+                result => result,
+            })
+            .collect();
+        info!("Bulk verification of {} runs", runs.len());
+
+        let (results, stream) = tokio::sync::mpsc::channel(VERIFY_BULK_BUFFER);
+        let verify_pool = self.verify_pool.clone();
+        tokio::spawn(async move {
+            for (index, run) in runs.into_iter().enumerate() {
+                let result = match run {
+                    Ok(run) => VerifyBulkResult {
+                        index: index as u32,
+                        valid: verify_pool.run(move || run.verify()).await,
+                        error: String::new(),
+                    },
+                    Err(status) => VerifyBulkResult {
+                        index: index as u32,
+                        valid: false,
+                        error: status.message().to_string(),
+                    },
+                };
+                if results.send(Ok(result)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+
+    type WatchEventsStream = ReceiverStream<Result<WatchedEvent, Status>>;
+
+    async fn watch_events(
+        &self,
+        request: Request<WatchEventsRequest>,
+    ) -> Result<Response<Self::WatchEventsStream>, Status> {
+        self.check_admin(request.metadata())?;
+
+        #[cfg(not(feature = "audit"))]
+        return Err(Status::new(Code::Unimplemented, "WatchEvents needs the audit feature"));
+
+        // forwarded from the broadcast channel by a task that ends with the stream; a watcher
+        // more than EVENT_WATCH_BUFFER events behind loses the oldest ones and keeps going
+        #[cfg(feature = "audit")]
+        {
+            use tokio::sync::broadcast::error::RecvError;
+
+            let mut events = self.event_watchers.subscribe();
+            let (watcher, stream) = tokio::sync::mpsc::channel(EVENT_WATCH_BUFFER);
+            info!("Event watcher connected");
+            tokio::spawn(async move {
+                loop {
+                    let received = tokio::select! {
+                        received = events.recv() => received,
+                        () = watcher.closed() => break,
+                    };
+                    let (timestamp, event) = match received {
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("⚠️  Event watcher fell behind, {missed} events skipped");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    let event = WatchedEvent {
+                        timestamp,
+                        kind: event.kind().to_string(),
+                        user: event.user().to_string(),
+                    };
+                    if watcher.send(Ok(event)).await.is_err() {
+                        break;
+                    }
+                }
+                info!("Event watcher disconnected");
+            });
+
+            Ok(Response::new(ReceiverStream::new(stream)))
+        }
+    }
+
+    async fn register_and_login(
+        &self,
+        request: Request<RegisterAndLoginRequest>,
+    ) -> Result<Response<RegisterAndLoginResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("register_and_login", %request_id);
+
+        let result = span.in_scope(|| {
+            let request = request.into_inner();
+            let Some(registration) = request.registration else {
+                return Err(Status::new(Code::InvalidArgument, "registration is missing"));
+            };
+
+            let user_name = registration.user.clone();
+            info!("Processing Registration and Login username: {:?}", user_name);
+
+            let mut user_info = self.checked_registration(registration)?;
+
+            let zkp =
+                self.groups.get(&user_info.group_id).expect("resolved by checked_registration");
+            let subject = format!("User: {user_name}");
+            let r1 =
+                zkp.decode_element(&request.r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+            let r2 =
+                zkp.decode_element(&request.r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+            let c =
+                zkp.decode_scalar(&request.c).map_err(|e| invalid_encoding(&subject, "c", e))?;
+            let s =
+                zkp.decode_scalar(&request.s).map_err(|e| invalid_encoding(&subject, "s", e))?;
+
+            let commitment = Commitment::new(user_info.y1.clone(), user_info.y2.clone());
+            let context = ZKP::register_and_login_context(&user_name);
+            let proof = Proof::new(r1, r2, c, s);
+            if !zkp.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(&context)) {
+                warn!("❌ Wrong Registration Proof username: {user_name:?}");
+
+                return Err(Status::new(
+                    Code::PermissionDenied,
+                    format!("User: {user_name} bad proof, nothing was registered"),
+                ));
+            }
+
+            // first login only: the proof is not tied to a fresh challenge, so a replayed
+            // request must not mint another session for an existing user
+            let user_info_hashmap = &mut self.user_info.lock().unwrap();
+            if user_info_hashmap.contains_key(&user_name) {
+                return Err(Status::new(
+                    Code::AlreadyExists,
+                    format!("User: {user_name} is already registered"),
+                ));
+            }
+
+            self.claim_handle(&user_info)?;
+            let session_id = self.mint_session(&mut user_info);
+            user_info_hashmap.insert(user_name.clone(), self.stored(user_info));
+
+            info!("✅ Successful Registration and Login username: {:?}", user_name);
+            Ok(Response::new(RegisterAndLoginResponse { session_id }))
+        });
+
+        // the new session goes through on_verified before its id is returned
+        let result = match result {
+            Ok(response) => {
+                self.provision_session(&response.get_ref().session_id).await.map(|()| response)
+            }
+            result => result,
+        };
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn submit_proof(
+        &self,
+        request: Request<SubmitProofRequest>,
+    ) -> Result<Response<SubmitProofResponse>, Status> {
+        let request_id = request_id(&request);
+        let span = tracing::info_span!("submit_proof", %request_id);
+
+        let request = request.into_inner();
+        let user_name = request.user.clone();
+        // an unknown user fails like a wrong proof
+        let bad_proof =
+            || Status::new(Code::PermissionDenied, format!("User: {user_name} bad proof"));
+
+        // decoded under the user_info lock, verified once it is released
+        let decoded = span.in_scope(|| {
+            self.require_mode(ServerMode::NonInteractive, "SubmitProof")?;
+            info!("Processing Submitted Proof username: {:?}", user_name);
+
+            let user_info_hashmap = self.user_info.lock().unwrap();
+            let user_info = user_info_hashmap.get(&user_name);
+            // an unknown user is checked against the decoy commitment of stateless_verify
+            // (x = 1): same steps, same work, then refused as a wrong proof
+            let (group_id, zkp) = match user_info {
+                Some(user_info) => (
+                    user_info.group_id,
+                    self.groups.get(&user_info.group_id).expect("registered under a known group"),
+                ),
+                None => self.groups.resolve(&[]).expect("the default group is always there"),
+            };
+
+            let subject = format!("User: {user_name}");
+            let r1 =
+                zkp.decode_element(&request.r1).map_err(|e| invalid_encoding(&subject, "r1", e))?;
+            let r2 =
+                zkp.decode_element(&request.r2).map_err(|e| invalid_encoding(&subject, "r2", e))?;
+            let c =
+                zkp.decode_scalar(&request.c).map_err(|e| invalid_encoding(&subject, "c", e))?;
+            let s =
+                zkp.decode_scalar(&request.s).map_err(|e| invalid_encoding(&subject, "s", e))?;
+
+            // no challenge to expire or use up: the timestamp bounds the proof's validity and
+            // fresh_commitment refuses it a second time
+            if self.verification.challenge_expiry
+                && !ZKP::timestamp_is_fresh_with_skew(
+                    request.timestamp,
+                    self.policy.challenge_ttl,
+                    self.policy.clock_skew,
+                )
+            {
+                warn!("❌ Expired Submitted Proof username: {user_name:?}");
+                return Err(Status::new(
+                    Code::DeadlineExceeded,
+                    format!("{subject} proof timestamp is too old"),
+                ));
+            }
+            let commitment = match user_info {
+                Some(user_info) => Self::stored_commitment(zkp, user_info)?,
+                None => {
+                    let (y1, y2) = zkp.compute_pair(&BigUint::from(1u32));
+                    Commitment::new(y1, y2)
+                }
+            };
+            self.check_fresh_commitment(&subject, &group_id, &r1, &r2)?;
+
+            Ok((zkp.clone(), commitment, Proof::new(r1, r2, c, s), user_info.is_none()))
+        });
+
+        let result = match decoded {
+            Ok((zkp, commitment, proof, decoy)) => {
+                let context = ZKP::submit_proof_context(&user_name, request.timestamp);
+                let verify = move || {
+                    zkp.verify_proof(&commitment, &proof, ChallengeSource::FiatShamir(&context))
+                };
+                let verified = self.verify_pool.run(verify).await;
+                span.in_scope(|| {
+                    if decoy {
+                        warn!("❌ Submitted Proof for unknown username: {user_name:?}");
+                        return Err(bad_proof());
+                    }
+                    if !verified {
+                        warn!("❌ Wrong Submitted Proof username: {user_name:?}");
+                        #[cfg(feature = "audit")]
+                        self.record(AuthEvent::LoginFailed { user: user_name.clone() });
+                        return Err(bad_proof());
+                    }
+
+                    // the user as it is now, gone if it was removed while the proof was verified
+                    let session_id = {
+                        let user_info_hashmap = &mut self.user_info.lock().unwrap();
+                        let Some(user_info) = user_info_hashmap.get_mut(&user_name) else {
+                            return Err(bad_proof());
+                        };
+                        self.mint_session(user_info)
+                    };
+                    info!("✅ Correct Submitted Proof username: {:?}", user_name);
+                    #[cfg(feature = "audit")]
+                    self.record(AuthEvent::LoginSucceeded { user: user_name.clone() });
+                    Ok(Response::new(SubmitProofResponse { session_id }))
+                })
+            }
+            Err(status) => Err(status),
+        };
+
+        // the new session goes through on_verified before its id is returned
+        let result = match result {
+            Ok(response) => {
+                self.provision_session(&response.get_ref().session_id).await.map(|()| response)
+            }
+            result => result,
+        };
+
+        echo_request_id(result, &request_id)
+    }
+
+    async fn verify_blob(
+        &self,
+        request: Request<VerifyBlobRequest>,
+    ) -> Result<Response<VerifyBlobResponse>, Status> {
+        let blob = request.into_inner().blob;
+        let subject = "Proof blob";
+        let group_id =
+            proof_blob_fingerprint(&blob).map_err(|e| invalid_encoding(subject, "header", e))?;
+        let Some(zkp) = self.groups.get(&group_id) else {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("{subject} group is not supported by this server"),
+            ));
+        };
+        let (commitment, proof) =
+            zkp.decode_proof_blob(&blob).map_err(|e| invalid_encoding(subject, "fields", e))?;
+        let elements = [&commitment.y1, &commitment.y2, &proof.r1, &proof.r2];
+        if !elements.into_iter().all(|element| zkp.is_in_subgroup(element)) {
+            return Err(Status::new(
+                Code::InvalidArgument,
+                format!("{subject} y1/y2/r1/r2 are not in the order-q subgroup"),
+            ));
+        }
+
+        // stateless like VerifyComplete: the proof is only checked against its own y1, y2, and
+        // c must be derived from them, not picked by whoever made the blob
+        let source = ChallengeSource::FiatShamirIn(PROOF_BLOB_DOMAIN, b"");
+        let valid = zkp.verify_proof(&commitment, &proof, source);
+
+        Ok(Response::new(VerifyBlobResponse { valid }))
+    }
+}
+
+// server gen-params [--bits N]: prints a freshly generated safe-prime group as hex
+fn gen_params(args: &[String]) {
+    let bits = match args {
+        [] => 2048,
+        [flag, bits] if flag == "--bits" => bits.parse().expect("--bits must be a number"),
+        _ => panic!("usage: server gen-params [--bits N]"),
+    };
+
+    println!(
+        "⚠️  Generating a {bits}-bit safe-prime group: this is SLOW and NOT for production use"
+    );
+    println!("⚠️  Prefer a standard group (RFC 5114) for anything but experiments");
+
+    let (alpha, beta, p, q) = ZKP::generate_params(bits);
+
+    println!("p     = {}", hex::encode_upper(p.to_bytes_be()));
+    println!("q     = {}", hex::encode_upper(q.to_bytes_be()));
+    println!("alpha = {}", hex::encode_upper(alpha.to_bytes_be()));
+    println!("beta  = {}", hex::encode_upper(beta.to_bytes_be()));
+    // the same in one string, as ZKP::from_str reads it
+    println!(
+        "p={},q={},alpha={},beta={}",
+        hex::encode_upper(p.to_bytes_be()),
+        hex::encode_upper(q.to_bytes_be()),
+        hex::encode_upper(alpha.to_bytes_be()),
+        hex::encode_upper(beta.to_bytes_be())
+    );
+}
+
+// server audit <file>: re-verifies a recorded JSON transcript offline
+#[cfg(feature = "audit")]
+fn audit(args: &[String]) {
+    let [path] = args else {
+        panic!("usage: server audit <transcript.json>");
+    };
+    let json = std::fs::read_to_string(path).expect("could not read the transcript");
+    let report = zkp_chaum_pedersen::audit_transcript(&json).expect("invalid transcript");
+
+    let verdict = if report.passed { "PASS" } else { "FAIL" };
+    println!("{verdict} group={}", hex::encode_upper(report.fingerprint));
+    if !report.passed {
+        std::process::exit(1);
+    }
+}
+
+// server selftest [--secs N]: checks the protocol round-trips in every supported group and
+// measures verifications per second (single core) on this machine
+// server selftest --challenge-bits N [--rounds R] [--cheat]: the soundness demonstration instead,
+// R logins (default 1000) with N-bit challenges by an honest or a cheating prover
+fn selftest(args: &[String]) {
+    const USAGE: &str =
+        "usage: server selftest [--secs N] | --challenge-bits N [--rounds R] [--cheat]";
+    let (mut secs, mut challenge_bits, mut rounds, mut cheat) = (1, None, 1000, false);
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut number = || args.next().and_then(|n| n.parse().ok()).expect(USAGE);
+        match flag.as_str() {
+            "--secs" => secs = number(),
+            "--challenge-bits" => challenge_bits = Some(number() as u32),
+            "--rounds" => rounds = number(),
+            "--cheat" => cheat = true,
+            _ => panic!("{USAGE}"),
+        }
+    }
+
+    if let Some(bits) = challenge_bits {
+        let zkp = ZKP::default();
+        let rate = zkp.soundness_trial(bits, rounds, cheat);
+        let prover = if cheat { "cheating" } else { "honest" };
+        let expected = if cheat { 0.5f64.powi(bits as i32) } else { 1.0 };
+        println!(
+            "🎓 {bits}-bit challenges, {prover} prover: passed {:.2}% of {rounds} rounds \
+             (expected {:.2}%)",
+            rate * 100.0,
+            expected * 100.0
+        );
+        return;
+    }
+    assert!(!cheat, "--cheat goes with --challenge-bits");
+
+    for (bits, (alpha, beta, p, q)) in
+        [(1024, ZKP::get_constants()), (2048, ZKP::get_constants_2048())]
+    {
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = zkp.generate_challenge();
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).expect("x, k and c are drawn below q");
+        let ok = zkp.verify(&r1, &r2, &y1, &y2, &c, &s)
+            && !zkp.verify(&r1, &r2, &y1, &y2, &c, &(&s + 1u32));
+        assert!(ok, "❌ selftest failed for the {bits}-bit group");
+
+        let throughput = zkp.estimate_verify_throughput(Duration::from_secs(secs));
+        println!("✅ {bits}-bit group: {throughput:.0} verifications/s (single core)");
+    }
+}
+
+// ZKP_TLS_CERT / ZKP_TLS_KEY=<PEM file> serve over TLS, and ZKP_TLS_CLIENT_CA=<PEM file> on top
+// of them turns on mutual TLS: a connection without a client certificate signed by that CA is
+// refused during the handshake, before any RPC (the ZKP login still runs over accepted ones)
+#[cfg(feature = "tls")]
+fn tls_config() -> Option<tonic::transport::ServerTlsConfig> {
+    use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+    let read = |var: &str| {
+        std::env::var(var)
+            .ok()
+            .map(|path| std::fs::read(&path).unwrap_or_else(|e| panic!("{var}: {path}: {e}")))
+    };
+    let client_ca = read("ZKP_TLS_CLIENT_CA");
+    let (Some(cert), Some(key)) = (read("ZKP_TLS_CERT"), read("ZKP_TLS_KEY")) else {
+        assert!(client_ca.is_none(), "ZKP_TLS_CLIENT_CA needs ZKP_TLS_CERT and ZKP_TLS_KEY");
+        return None;
+    };
+
+    let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    if let Some(client_ca) = client_ca {
+        println!("✅ Requiring client certificates (mutual TLS)");
+        tls = tls.client_ca_root(Certificate::from_pem(client_ca));
+    }
+    Some(tls)
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().collect();
+    #[cfg(feature = "dump")]
+    let mut import = None;
+    match args.get(1).map(String::as_str) {
+        Some("gen-params") => return gen_params(&args[2..]),
+        Some("selftest") => return selftest(&args[2..]),
+        Some("loadtest") => return loadtest::run(&args[2..]).await,
+        #[cfg(feature = "audit")]
+        Some("audit") => return audit(&args[2..]),
+        #[cfg(not(feature = "audit"))]
+        Some("audit") => panic!("the audit subcommand needs the \"audit\" feature"),
+        #[cfg(feature = "dump")]
+        Some("export") => return dump::export(&args[2..]).await,
+        #[cfg(feature = "dump")]
+        Some("import") => match &args[2..] {
+            [path] => import = Some(path.clone()),
+            _ => panic!("usage: server import <file>"),
+        },
+        #[cfg(not(feature = "dump"))]
+        Some("export" | "import") => panic!("export and import need the \"dump\" feature"),
+        Some(other) => panic!("unknown subcommand: {other}"),
+        None => {}
+    }
+
+    // ZKP_ADDR=<ip:port> to listen somewhere else than the default gRPC port
+    let addr = std::env::var("ZKP_ADDR").unwrap_or_else(|_| "127.0.0.1:50051".to_string());
+
+    println!("✅ Running the server in {addr}");
+
+    // ZKP_VERIFY_JITTER_MS=<max ms> enables the random delay on verify_authentication (off by default)
+    let verify_jitter = std::env::var("ZKP_VERIFY_JITTER_MS").ok().map(|ms| {
+        Duration::from_millis(ms.parse().expect("ZKP_VERIFY_JITTER_MS must be a number"))
+    });
+
+    // ZKP_METRICS_ADDR=<ip:port> serves the latency histograms on GET /metrics (off by default)
+    let metrics_addr = std::env::var("ZKP_METRICS_ADDR").ok().map(|addr| {
+        addr.parse::<std::net::SocketAddr>().expect("could not convert ZKP_METRICS_ADDR")
+    });
+
+    // ZKP_VERIFY_THREADS=<n> sizes the verification thread pool (default: one per CPU)
+    let verify_threads = std::env::var("ZKP_VERIFY_THREADS")
+        .map(|threads| threads.parse().expect("ZKP_VERIFY_THREADS must be a number"))
+        .unwrap_or(0);
+
+    // ZKP_CHALLENGE_TTL_SECS / ZKP_SESSION_TTL_SECS / ZKP_ANSWER_RETRY_TTL_SECS /
+    // ZKP_REGISTRATION_POW_BITS / ZKP_CLOCK_SKEW_SECS override the default ProtocolPolicy
+    let mut policy = ProtocolPolicy::default();
+    if let Ok(secs) = std::env::var("ZKP_CHALLENGE_TTL_SECS") {
+        policy.challenge_ttl =
+            Duration::from_secs(secs.parse().expect("ZKP_CHALLENGE_TTL_SECS must be a number"));
+    }
+    if let Ok(secs) = std::env::var("ZKP_SESSION_TTL_SECS") {
+        policy.session_ttl =
+            Duration::from_secs(secs.parse().expect("ZKP_SESSION_TTL_SECS must be a number"));
+    }
+    if let Ok(secs) = std::env::var("ZKP_ANSWER_RETRY_TTL_SECS") {
+        policy.answer_retry_ttl =
+            Duration::from_secs(secs.parse().expect("ZKP_ANSWER_RETRY_TTL_SECS must be a number"));
+    }
+    if let Ok(secs) = std::env::var("ZKP_CLOCK_SKEW_SECS") {
+        policy.clock_skew =
+            Duration::from_secs(secs.parse().expect("ZKP_CLOCK_SKEW_SECS must be a number"));
+    }
+    if let Ok(bits) = std::env::var("ZKP_REGISTRATION_POW_BITS") {
+        policy.registration_pow_bits =
+            bits.parse().expect("ZKP_REGISTRATION_POW_BITS must be a number");
+    }
+
+    // ZKP_ADMIN_TOKEN=<secret> enables the admin RPCs (ListUsers) for callers sending it
+    let admin_token = std::env::var("ZKP_ADMIN_TOKEN").ok();
+
+    // ZKP_MAX_CHALLENGES / ZKP_MAX_SESSIONS cap the pending challenges and live sessions kept
+    let max_entries = |var: &str, default: usize| {
+        std::env::var(var).map_or(default, |n| n.parse().expect("capacities must be numbers"))
+    };
+    let max_challenges = max_entries("ZKP_MAX_CHALLENGES", MAX_CHALLENGES);
+    let max_sessions = max_entries("ZKP_MAX_SESSIONS", MAX_SESSIONS);
+
+    // ZKP_BACKEND picks the protocol behind the RPCs, only "chaum-pedersen" (default) so far
+    let backend = match std::env::var("ZKP_BACKEND").as_deref() {
+        Err(_) | Ok("chaum-pedersen") => ChaumPedersen,
+        Ok(other) => panic!("unknown ZKP_BACKEND: {other}"),
+    };
+
+    // ZKP_STATELESS_SECRET=<hex> switches to stateless challenges (nothing stored per challenge,
+    // no replay detection within challenge_ttl)
+    let stateless_secret = std::env::var("ZKP_STATELESS_SECRET")
+        .ok()
+        .map(|secret| hex::decode(secret).expect("ZKP_STATELESS_SECRET must be hex"));
+
+    // ZKP_RELAXED_CHECKS=<check>,... turns replay checks off, see VerificationPolicy
+    let verification = VerificationPolicy::default()
+        .relax(&std::env::var("ZKP_RELAXED_CHECKS").unwrap_or_default())
+        .unwrap_or_else(|e| panic!("ZKP_RELAXED_CHECKS: {e}"));
+
+    // ZKP_RECEIPT_KEY=<hex> signs a VerificationReceipt into every successful answer, its public
+    // key is in get_parameters (keep it across restarts so archived receipts stay checkable)
+    let receipt_key = std::env::var("ZKP_RECEIPT_KEY").ok().map(|key| {
+        let key = Zeroizing::new(hex::decode(key).expect("ZKP_RECEIPT_KEY must be hex"));
+        Secret::from_bytes_be(&key)
+    });
+
+    // ZKP_HASHED_COMMITMENTS=1 stores a digest of y1, y2 instead of the commitments themselves,
+    // clients then send them with every challenge request (CreateAuthenticationChallenge only)
+    let hashed_commitments = std::env::var("ZKP_HASHED_COMMITMENTS").is_ok_and(|v| v == "1");
+
+    // ZKP_DERIVED_Y2=1 stores y1 only where y2 = y1^ZKP::beta_exponent() (the built-in groups)
+    // and recomputes y2 with one exponentiation whenever it is needed
+    let derived_y2 = std::env::var("ZKP_DERIVED_Y2").is_ok_and(|v| v == "1");
+
+    // ZKP_COMMITMENT_HANDLES=1 gives every new user ZKP::commitment_handle of its y1, y2 as its
+    // user_id (stable, independent of the name) and refuses a commitment registered already
+    let commitment_handles = std::env::var("ZKP_COMMITMENT_HANDLES").is_ok_and(|v| v == "1");
+
+    // ZKP_SERVER_MODE=non-interactive replaces the challenge RPCs by SubmitProof, see ServerMode
+    let mode = std::env::var("ZKP_SERVER_MODE")
+        .map(|mode| mode.parse().unwrap_or_else(|e| panic!("ZKP_SERVER_MODE: {e}")))
+        .unwrap_or_default();
+
+    // ZKP_AUDIT_LOG=<path> appends every registration and login to a hash-chained JSONL file,
+    // checked at startup: a broken chain stops the server unless ZKP_AUDIT_LOG_ALLOW_BROKEN=1
+    #[cfg(feature = "audit")]
+    let audit_log = std::env::var("ZKP_AUDIT_LOG").ok().map(|path| {
+        let allow_broken = std::env::var("ZKP_AUDIT_LOG_ALLOW_BROKEN").is_ok_and(|v| v == "1");
+        AuditLog::open(&path, allow_broken).unwrap_or_else(|e| panic!("ZKP_AUDIT_LOG: {e}"))
+    });
+    #[cfg(not(feature = "audit"))]
+    assert!(std::env::var("ZKP_AUDIT_LOG").is_err(), "ZKP_AUDIT_LOG needs the \"audit\" feature");
+
+    // ZKP_SESSION_RECORDS=1 records every checked answer as a SessionRecord as well (challenge,
+    // answer, timestamps and outcome), to the audit log and the WatchEvents streams
+    #[cfg(feature = "audit")]
+    let session_records = std::env::var("ZKP_SESSION_RECORDS").is_ok_and(|v| v == "1");
+    #[cfg(not(feature = "audit"))]
+    assert!(
+        std::env::var("ZKP_SESSION_RECORDS").is_err(),
+        "ZKP_SESSION_RECORDS needs the \"audit\" feature"
+    );
+
+    let auth_impl = AuthImpl::builder()
+        .policy(policy)
+        .max_challenges(max_challenges)
+        .max_sessions(max_sessions)
+        .admin_token(admin_token)
+        .verify_jitter(verify_jitter)
+        .verify_threads(verify_threads)
+        .verification(verification)
+        .mode(mode)
+        .stateless_secret(stateless_secret)
+        .receipt_key(receipt_key)
+        .hashed_commitments(hashed_commitments)
+        .derived_y2(derived_y2)
+        .commitment_handles(commitment_handles)
+        .backend(backend);
+    #[cfg(feature = "audit")]
+    let auth_impl = auth_impl.audit_log(audit_log).session_records(session_records);
+    #[allow(unused_mut)]
+    let mut auth_impl = auth_impl.build_impl();
+
+    // ZKP_TEST_FIXED_CHALLENGE=<hex c> makes every challenge c (testutil feature, debug builds only)
+    #[cfg(feature = "testutil")]
+    if let Ok(c) = std::env::var("ZKP_TEST_FIXED_CHALLENGE") {
+        println!("⚠️  Fixed challenge {c}: authentication is NOT secure in this mode");
+        let c = hex::decode(c).expect("ZKP_TEST_FIXED_CHALLENGE must be hex");
+        auth_impl.challenge_provider =
+            Some(Box::new(zkp_chaum_pedersen::FixedChallenge::new(BigUint::from_bytes_be(&c))));
+    }
+
+    // server import <file>: starts with the registrations of a dump made by server export
+    #[cfg(feature = "dump")]
+    if let Some(path) = import {
+        let json = std::fs::read_to_string(&path).expect("could not read the dump");
+        let count = auth_impl.import_users(&json).expect("invalid dump");
+        println!("✅ Imported {count} users from {path}");
+    }
+
+    // shared by the gRPC server and the HTTP gateway
+    let auth_impl = Arc::new(auth_impl);
+
+    // ZKP_HTTP_ADDR=<ip:port> moves the HTTP+JSON gateway off its default port 8080
+    #[cfg(feature = "http")]
+    {
+        let http_addr =
+            std::env::var("ZKP_HTTP_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
+        let http_addr = http_addr.parse().expect("could not convert ZKP_HTTP_ADDR");
+        tokio::spawn(gateway::serve(http_addr, auth_impl.clone()));
+    }
+
+    if let Some(metrics_addr) = metrics_addr {
+        tokio::spawn(metrics::serve(metrics_addr, auth_impl.clone()));
+    }
+
+    let service = AuthServer::from_arc(auth_impl);
+
+    // grpc-web goes over HTTP/1.1, tonic_web::enable also answers the CORS preflight of browsers
+    #[cfg(feature = "grpc-web")]
+    let service = tonic_web::enable(service);
+
+    #[allow(unused_mut)]
+    let mut server = Server::builder();
+    #[cfg(feature = "tls")]
+    if let Some(tls) = tls_config() {
+        server = server.tls_config(tls).expect("invalid TLS configuration");
+    }
+
+    server
+        .accept_http1(cfg!(feature = "grpc-web"))
+        .add_service(service)
+        .serve(addr.parse().expect("could not convert address"))
+        .await
+        .unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use zkp_chaum_pedersen::{FiatShamirChallenge, FixedChallenge};
+    // only checked by the toy group test below
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    use zkp_chaum_pedersen::MIN_ID_LENGTH;
+
+    fn register_request(user: &str, y1: &BigUint, y2: &BigUint) -> Request<RegisterRequest> {
+        Request::new(RegisterRequest {
+            user: user.to_string(),
+            y1: y1.to_bytes_be(),
+            y2: y2.to_bytes_be(),
+            group_id: Vec::new(),
+            pow_nonce: 0,
+        })
+    }
+
+    // registers `user` with secret x and asks for a challenge with nonce k, returns (auth_id, c)
+    async fn register_and_challenge(
+        auth: &AuthImpl,
+        zkp: &ZKP,
+        user: &str,
+        x: &BigUint,
+        k: &BigUint,
+    ) -> (String, BigUint) {
+        let (y1, y2) = zkp.compute_pair(x);
+        auth.register(register_request(user, &y1, &y2)).await.unwrap();
+
+        let (r1, r2) = zkp.compute_pair(k);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: user.to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let response = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+
+        (response.auth_id, BigUint::from_bytes_be(&response.c))
+    }
+
+    fn answer_request(auth_id: &str, s: &BigUint) -> Request<AuthenticationAnswerRequest> {
+        Request::new(AuthenticationAnswerRequest {
+            auth_id: auth_id.to_string(),
+            s: s.to_bytes_be(),
+            binding: Vec::new(),
+        })
+    }
+
+    fn answer_failure_of(status: &Status) -> AnswerFailure {
+        AnswerFailure::decode(status.details()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_element_of_wrong_order() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p.clone(), q.clone());
+
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        assert!(auth.register(register_request("alice", &y1, &y2)).await.is_ok());
+
+        // p - 1 has order 2, it is not in the order-q subgroup
+        let p_minus_1 = &p - 1u32;
+        let status = auth.register(register_request("mallory", &y1, &p_minus_1)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(!auth.user_info.lock().unwrap().contains_key("mallory"));
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_weak_secrets() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q);
+
+        // x = 0 gives y1 = y2 = 1, x = 1 gives y1 = alpha, y2 = beta
+        for (user, x) in [("zero", 0u32), ("one", 1u32)] {
+            let (y1, y2) = zkp.compute_pair(&BigUint::from(x));
+            let status = auth.register(register_request(user, &y1, &y2)).await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+            assert!(!auth.user_info.lock().unwrap().contains_key(user));
+        }
+    }
+
+    // not a real protocol: any registration goes, c is always 7 and the answer is c itself
+    #[derive(Debug)]
+    struct EchoBackend;
+
+    impl AuthBackend for EchoBackend {
+        fn register(&self, _group: &ZKP, _y1: &[u8], _y2: &[u8]) -> Result<(), ZKPError> {
+            Ok(())
+        }
+
+        fn challenge(&self, _group: &ZKP, _r1: &[u8], _r2: &[u8]) -> Result<Vec<u8>, ZKPError> {
+            Ok(vec![7])
+        }
+
+        fn verify(
+            &self,
+            _group: &ZKP,
+            _y1: &[u8],
+            _y2: &[u8],
+            _r1: &[u8],
+            _r2: &[u8],
+            c: &[u8],
+            s: &[u8],
+        ) -> Result<bool, ZKPError> {
+            Ok(c == s)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_alternate_backend() {
+        let auth = AuthImpl::default().with_backend(EchoBackend);
+
+        // p - 1 is no Chaum-Pedersen commitment, this backend does not care
+        let (_, _, p, _) = ZKP::default_constants();
+        let p_minus_1 = &p - 1u32;
+        auth.register(register_request("alice", &p_minus_1, &p_minus_1)).await.unwrap();
+
+        // a fresh (r1, r2) per challenge, verification.fresh_commitment refuses a repeated one
+        let challenge = |r: u8| {
+            auth.create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: vec![r],
+                r2: vec![r],
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            }))
+        };
+        let first = challenge(1).await.unwrap().into_inner();
+        assert_eq!(first.c, vec![7]);
+
+        let wrong = BigUint::from(8u32);
+        let status = auth.verify_authentication(answer_request(&first.auth_id, &wrong)).await;
+        assert_eq!(status.unwrap_err().code(), Code::PermissionDenied);
+
+        // the wrong answer used the challenge up, the right one needs a new challenge
+        let right = BigUint::from(7u32);
+        let status = auth.verify_authentication(answer_request(&first.auth_id, &right)).await;
+        assert_eq!(status.unwrap_err().code(), Code::FailedPrecondition);
+        let second = challenge(2).await.unwrap().into_inner();
+        assert!(auth.verify_authentication(answer_request(&second.auth_id, &right)).await.is_ok());
+    }
+
+    // the service itself as AuthClient's transport, no network in between
+    #[derive(Clone)]
+    struct InProcess(Arc<AuthImpl>);
+
+    impl zkp_chaum_pedersen::AuthTransport for InProcess {
+        async fn register(&mut self, request: RegisterRequest) -> Result<RegisterResponse, Status> {
+            Ok(self.0.register(Request::new(request)).await?.into_inner())
+        }
+
+        async fn create_authentication_challenge(
+            &mut self,
+            request: AuthenticationChallengeRequest,
+        ) -> Result<AuthenticationChallengeResponse, Status> {
+            Ok(self.0.create_authentication_challenge(Request::new(request)).await?.into_inner())
+        }
+
+        async fn verify_authentication(
+            &mut self,
+            request: AuthenticationAnswerRequest,
+        ) -> Result<AuthenticationAnswerResponse, Status> {
+            Ok(self.0.verify_authentication(Request::new(request)).await?.into_inner())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_with_a_password_wider_than_q() {
+        let auth = Arc::new(AuthImpl::default());
+        let zkp = ZKP::default();
+        let client = zkp_chaum_pedersen::AuthClient::builder().transport(InProcess(auth.clone()));
+
+        // registered as the client binary does, from the whole password: 40 bytes, wider than
+        // the 160-bit q
+        let password = b"correct horse battery staple, but longer";
+        let (y1, y2) = zkp.compute_pair(Secret::from_bytes_be(password).expose());
+        client.register(register_request("alice", &y1, &y2).into_inner()).await.unwrap();
+
+        let x = Secret::from_bytes_be(password);
+        let session_id = client.authenticate(&zkp, "alice", x).await.unwrap();
+        assert!(auth.sessions.lock().unwrap().contains(&session_id));
+    }
+
+    #[tokio::test]
+    async fn test_on_verified_hook_provisions_the_session() {
+        // the hook stands in for a token issuer: one claim per new session
+        let claims = Arc::new(Mutex::new(HashMap::new()));
+        let issued = claims.clone();
+        let auth = AuthImpl::builder()
+            .on_verified(move |user, session_id| {
+                let issued = issued.clone();
+                async move {
+                    issued.lock().unwrap().insert(session_id, format!("sub={user}"));
+                    Ok(())
+                }
+            })
+            .build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let session_id = auth
+            .verify_authentication(answer_request(&auth_id, &s))
+            .await
+            .unwrap()
+            .into_inner()
+            .session_id;
+
+        assert_eq!(claims.lock().unwrap()[&session_id], "sub=alice");
+        assert!(auth.sessions.lock().unwrap().contains(&session_id));
+    }
+
+    #[tokio::test]
+    async fn test_on_verified_hook_aborts_the_session() {
+        let auth = AuthImpl::builder()
+            .on_verified(|_, _| async { Err("identity provider unreachable".to_string()) })
+            .build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+        assert!(status.message().contains("identity provider unreachable"));
+
+        // the proof was right, but no session is left and no retry hands one out
+        assert!(auth.sessions.lock().unwrap().is_empty());
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_err());
+        assert!(auth.sessions.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verification_receipt_is_signed() {
+        let auth = AuthImpl::builder()
+            .receipt_key(Some(Secret::from_bytes_be(b"receipt key")))
+            .build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let response =
+            auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap().into_inner();
+        let receipt = response.receipt.expect("the server has a receipt key");
+        assert_eq!((receipt.auth_id.as_str(), receipt.user.as_str()), (auth_id.as_str(), "alice"));
+        assert_eq!((receipt.c.clone(), receipt.s.clone()), (c.to_bytes_be(), s.to_bytes_be()));
+        assert!(receipt.valid);
+
+        // checked against the key published by get_parameters, in the default group
+        let parameters =
+            auth.get_parameters(Request::new(ParametersRequest {})).await.unwrap().into_inner();
+        let key = Commitment::new(
+            BigUint::from_bytes_be(&parameters.receipt_key_y1),
+            BigUint::from_bytes_be(&parameters.receipt_key_y2),
+        );
+        let default_group = ZKP::default();
+        assert!(default_group.verify_receipt(&key, &receipt));
+
+        // any field changed, or another key: the signature no longer holds
+        let forged = VerificationReceipt { user: "mallory".to_string(), ..receipt.clone() };
+        assert!(!default_group.verify_receipt(&key, &forged));
+        let forged = VerificationReceipt { timestamp: receipt.timestamp + 1, ..receipt.clone() };
+        assert!(!default_group.verify_receipt(&key, &forged));
+        let other_key = ReceiptSigner::new(Secret::from_bytes_be(b"other key"));
+        assert!(!default_group.verify_receipt(other_key.public_key(), &receipt));
+
+        // without a receipt key there is no receipt and no key
+        let auth = AuthImpl::default();
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let response =
+            auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap().into_inner();
+        assert!(response.receipt.is_none());
+        let parameters =
+            auth.get_parameters(Request::new(ParametersRequest {})).await.unwrap().into_inner();
+        assert!(parameters.receipt_key_y1.is_empty());
+    }
+
+    fn submit_proof_request(
+        zkp: &ZKP,
+        user: &str,
+        x: &BigUint,
+        timestamp: u64,
+    ) -> Request<SubmitProofRequest> {
+        let proof = zkp.prove_non_interactive(x, &ZKP::submit_proof_context(user, timestamp));
+        Request::new(SubmitProofRequest {
+            user: user.to_string(),
+            r1: proof.r1.to_bytes_be(),
+            r2: proof.r2.to_bytes_be(),
+            c: proof.c.to_bytes_be(),
+            s: proof.s.to_bytes_be(),
+            timestamp,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_non_interactive_mode() {
+        let auth = AuthImpl::builder().mode(ServerMode::NonInteractive).build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // no challenges in this mode
+        let k = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let status = auth.create_authentication_challenge(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+        let request = Request::new(CommittedChallengeRequest { user: "alice".to_string() });
+        let status = auth.create_committed_challenge(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+
+        // a fresh proof logs in, once
+        let request = submit_proof_request(&zkp, "alice", &x, ZKP::unix_timestamp());
+        let replay = Request::new(request.get_ref().clone());
+        let session_id = auth.submit_proof(request).await.unwrap().into_inner().session_id;
+        let who = auth
+            .who_am_i(Request::new(WhoAmIRequest { session_id, group_id: Vec::new() }))
+            .await
+            .unwrap();
+        assert_eq!(who.into_inner().user, "alice");
+        let status = auth.submit_proof(replay).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // from a client clock a few seconds ahead: within the default 5s of skew
+        let ahead = ZKP::unix_timestamp() + 3;
+        assert!(auth.submit_proof(submit_proof_request(&zkp, "alice", &x, ahead)).await.is_ok());
+        let ahead = ZKP::unix_timestamp() + 60;
+        let status = auth.submit_proof(submit_proof_request(&zkp, "alice", &x, ahead)).await;
+        assert_eq!(status.unwrap_err().code(), Code::DeadlineExceeded);
+
+        // too old, for someone else, with the wrong x, or for nobody
+        let old = ZKP::unix_timestamp() - 3600;
+        let status = auth.submit_proof(submit_proof_request(&zkp, "alice", &x, old)).await;
+        assert_eq!(status.unwrap_err().code(), Code::DeadlineExceeded);
+        let mut request = submit_proof_request(&zkp, "bob", &x, ZKP::unix_timestamp());
+        request.get_mut().user = "alice".to_string();
+        assert_eq!(auth.submit_proof(request).await.unwrap_err().code(), Code::PermissionDenied);
+        let wrong_x = &x + 1u32;
+        let request = submit_proof_request(&zkp, "alice", &wrong_x, ZKP::unix_timestamp());
+        assert_eq!(auth.submit_proof(request).await.unwrap_err().code(), Code::PermissionDenied);
+        let request = submit_proof_request(&zkp, "nobody", &x, ZKP::unix_timestamp());
+        assert_eq!(auth.submit_proof(request).await.unwrap_err().code(), Code::PermissionDenied);
+
+        // nobody is verified on the pool like a user, against the decoy commitment (x = 1),
+        // and even a proof for that one is refused
+        let jobs = auth.verify_pool.jobs();
+        let request = submit_proof_request(&zkp, "nobody", &1u32.into(), ZKP::unix_timestamp());
+        assert_eq!(auth.submit_proof(request).await.unwrap_err().code(), Code::PermissionDenied);
+        assert_eq!(auth.verify_pool.jobs(), jobs + 1);
+    }
+
+    #[tokio::test]
+    async fn test_interactive_mode_has_no_submit_proof() {
+        let auth = AuthImpl::default();
+        assert_eq!(auth.mode, ServerMode::Interactive);
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        // today's flow
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+
+        let request = submit_proof_request(&zkp, "alice", &x, ZKP::unix_timestamp());
+        assert_eq!(auth.submit_proof(request).await.unwrap_err().code(), Code::Unimplemented);
+
+        assert_eq!("non-interactive".parse(), Ok(ServerMode::NonInteractive));
+        assert_eq!("interactive".parse(), Ok(ServerMode::Interactive));
+        assert!("batch".parse::<ServerMode>().is_err());
+    }
+
+    // answers c itself like EchoBackend, but only once `parties` verifications are running at
+    // the same time (false after 10 s of waiting): it passes only if they really run in parallel
+    #[derive(Debug)]
+    struct RendezvousBackend {
+        parties: u64,
+        arrived: AtomicU64,
+    }
+
+    impl AuthBackend for RendezvousBackend {
+        fn register(&self, _group: &ZKP, _y1: &[u8], _y2: &[u8]) -> Result<(), ZKPError> {
+            Ok(())
+        }
+
+        fn challenge(&self, _group: &ZKP, _r1: &[u8], _r2: &[u8]) -> Result<Vec<u8>, ZKPError> {
+            Ok(vec![7])
+        }
+
+        fn verify(
+            &self,
+            _group: &ZKP,
+            _y1: &[u8],
+            _y2: &[u8],
+            _r1: &[u8],
+            _r2: &[u8],
+            c: &[u8],
+            s: &[u8],
+        ) -> Result<bool, ZKPError> {
+            self.arrived.fetch_add(1, Ordering::SeqCst);
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while self.arrived.load(Ordering::SeqCst) < self.parties {
+                if Instant::now() > deadline {
+                    return Ok(false);
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Ok(c == s)
+        }
+    }
+
+    // logins of different users are verified at the same time, one per verification thread
+    #[test]
+    fn test_independent_logins_verify_in_parallel() {
+        let parties = 4;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(parties)
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let backend = RendezvousBackend { parties: parties as u64, arrived: AtomicU64::new(0) };
+            let auth = AuthImpl::builder().verify_threads(parties).backend(backend).build_impl();
+            let auth = Arc::new(auth);
+
+            let mut auth_ids = Vec::new();
+            for i in 0..parties {
+                let user = format!("user-{i}");
+                auth.register(register_request(&user, &2u32.into(), &3u32.into())).await.unwrap();
+                // a fresh (r1, r2) each, fresh_commitment refuses a second challenge for one
+                let request = Request::new(AuthenticationChallengeRequest {
+                    user,
+                    r1: vec![i as u8 + 1],
+                    r2: vec![1],
+                    binding: Vec::new(),
+                    y1: Vec::new(),
+                    y2: Vec::new(),
+                });
+                let challenge = auth.create_authentication_challenge(request).await.unwrap();
+                auth_ids.push(challenge.into_inner().auth_id);
+            }
+
+            let logins: Vec<_> = auth_ids
+                .into_iter()
+                .map(|auth_id| {
+                    let auth = auth.clone();
+                    tokio::spawn(async move {
+                        let s = BigUint::from(7u32);
+                        auth.verify_authentication(answer_request(&auth_id, &s)).await
+                    })
+                })
+                .collect();
+            for login in logins {
+                assert!(login.await.unwrap().is_ok());
+            }
+            assert_eq!(auth.verify_pool.peak(), parties as u64);
+            assert_eq!(auth.answer_locks.in_use(), 0);
+        });
+    }
+
+    // an answer dropped halfway (the client went away) gives its lock back, and an auth_id that
+    // was never issued does not take one
+    #[tokio::test]
+    async fn test_cancelled_answer_releases_its_lock() {
+        let auth = Arc::new(AuthImpl::builder().verify_threads(1).build_impl());
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        // the only verification thread is busy, so the answer waits there under its lock
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let pool = auth.verify_pool.clone();
+        let busy = tokio::spawn(async move { pool.run(move || blocked.recv().unwrap()).await });
+        while auth.verify_pool.peak() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let answer = {
+            let auth = auth.clone();
+            tokio::spawn(
+                async move { auth.verify_authentication(answer_request(&auth_id, &s)).await },
+            )
+        };
+        while auth.verify_pool.jobs() < 2 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(auth.answer_locks.in_use(), 1);
+
+        answer.abort();
+        assert!(answer.await.unwrap_err().is_cancelled());
+        assert_eq!(auth.answer_locks.in_use(), 0);
+        release.send(()).unwrap();
+        busy.await.unwrap();
+
+        for _ in 0..10 {
+            let auth_id = ZKP::generate_random_string(12);
+            let status = auth.verify_authentication(answer_request(&auth_id, &q)).await;
+            assert_eq!(status.unwrap_err().code(), Code::NotFound);
+        }
+        assert_eq!(auth.answer_locks.in_use(), 0);
+    }
+
+    // a single runtime worker: a verification waiting on its thread must not take it, or the
+    // second login could never start its own and the rendezvous would fail
+    #[test]
+    fn test_verifications_do_not_hold_a_runtime_worker() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let backend = RendezvousBackend { parties: 2, arrived: AtomicU64::new(0) };
+            let auth = AuthImpl::builder().verify_threads(2).backend(backend).build_impl();
+            let auth = Arc::new(auth);
+
+            let mut logins = Vec::new();
+            for i in 0..2u8 {
+                let user = format!("user-{i}");
+                auth.register(register_request(&user, &2u32.into(), &3u32.into())).await.unwrap();
+                let request = Request::new(AuthenticationChallengeRequest {
+                    user,
+                    r1: vec![i + 1],
+                    r2: vec![1],
+                    binding: Vec::new(),
+                    y1: Vec::new(),
+                    y2: Vec::new(),
+                });
+                let challenge = auth.create_authentication_challenge(request).await.unwrap();
+                let auth_id = challenge.into_inner().auth_id;
+                let auth = auth.clone();
+                logins.push(tokio::spawn(async move {
+                    auth.verify_authentication(answer_request(&auth_id, &7u32.into())).await
+                }));
+            }
+            for login in logins {
+                assert!(login.await.unwrap().is_ok());
+            }
+            assert_eq!(auth.verify_pool.peak(), 2);
+        });
+    }
+
+    // throughput of concurrent logins on 1 thread and on up to 4 (one runtime worker and one
+    // verification thread each): close to linear on an idle machine with that many cores
+    #[test]
+    #[ignore = "load test, run it alone: cargo test --release -- --ignored --test-threads 1"]
+    fn test_login_throughput_scales_with_threads() {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get()).min(4);
+        let logins = 256;
+        let throughput = |threads: usize| {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(threads)
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let auth = Arc::new(AuthImpl::builder().verify_threads(threads).build_impl());
+                let (alpha, beta, p, q) = ZKP::default_constants();
+                let zkp = ZKP::new(alpha, beta, p, q.clone());
+                let mut answers = Vec::new();
+                for i in 0..logins {
+                    let x = ZKP::generate_random_number_below(&q);
+                    let k = ZKP::generate_random_number_below(&q);
+                    let user = format!("user-{i}");
+                    let (auth_id, c) = register_and_challenge(&auth, &zkp, &user, &x, &k).await;
+                    answers.push((auth_id, zkp.solve(&k, &c, &x).unwrap()));
+                }
+
+                let started = Instant::now();
+                let tasks: Vec<_> = answers
+                    .into_iter()
+                    .map(|(auth_id, s)| {
+                        let auth = auth.clone();
+                        tokio::spawn(async move {
+                            auth.verify_authentication(answer_request(&auth_id, &s)).await
+                        })
+                    })
+                    .collect();
+                for task in tasks {
+                    assert!(task.await.unwrap().is_ok());
+                }
+                logins as f64 / started.elapsed().as_secs_f64()
+            })
+        };
+
+        let single = throughput(1);
+        let parallel = throughput(cores);
+        let speedup = parallel / single;
+        println!("{single:.0} logins/s on 1 thread, {parallel:.0} on {cores}: x{speedup:.2}");
+        assert!(speedup >= 0.7 * cores as f64, "x{speedup:.2} on {cores} threads");
+    }
+
+    // tokio's blocking pool busy with other work (its only thread stuck, more work queued
+    // behind it): logins still go through, on the verification threads
+    #[test]
+    fn test_verify_pool_is_isolated_from_blocking_work() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .max_blocking_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let auth = AuthImpl::builder().verify_threads(2).build_impl();
+            let (alpha, beta, p, q) = ZKP::default_constants();
+            let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+            let queued_ran = Arc::new(AtomicU64::new(0));
+            let (release, stuck) = std::sync::mpsc::channel::<()>();
+            let busy = tokio::task::spawn_blocking(move || stuck.recv());
+            let queued = {
+                let queued_ran = queued_ran.clone();
+                tokio::task::spawn_blocking(move || queued_ran.fetch_add(1, Ordering::SeqCst))
+            };
+
+            let logins = 8;
+            for i in 0..logins {
+                let x = ZKP::generate_random_number_below(&q);
+                let k = ZKP::generate_random_number_below(&q);
+                let user = format!("user-{i}");
+                let (auth_id, c) = register_and_challenge(&auth, &zkp, &user, &x, &k).await;
+                let s = zkp.solve(&k, &c, &x).unwrap();
+                assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+            }
+            assert_eq!(auth.verify_pool.jobs(), logins);
+            // the blocking pool was busy the whole time
+            assert_eq!(queued_ran.load(Ordering::SeqCst), 0);
+
+            release.send(()).unwrap();
+            busy.await.unwrap().unwrap();
+            queued.await.unwrap();
+            assert_eq!(queued_ran.load(Ordering::SeqCst), 1);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_stateless_challenges() {
+        let secret = b"stateless secret".to_vec();
+        let auth = AuthImpl { stateless_secret: Some(secret.clone()), ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        // fresh: accepted, and nothing was stored for the challenge
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        assert!(auth.auth_id_to_user.lock().unwrap().is_empty());
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+
+        // stale: a challenge issued (with the right secret) long before the challenge ttl
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let old = ZKP::unix_timestamp() - 3600;
+        let c = zkp.stateless_challenge(&secret, &Commitment::new(y1, y2), &r1, &r2, old);
+        let auth_id = format!(
+            "{old}.{}.{}.alice",
+            hex::encode(r1.to_bytes_be()),
+            hex::encode(r2.to_bytes_be())
+        );
+        let status = auth
+            .verify_authentication(answer_request(&auth_id, &zkp.solve(&k, &c, &x).unwrap()))
+            .await;
+        assert_eq!(status.unwrap_err().code(), Code::DeadlineExceeded);
+
+        // an unknown user is checked against the decoy (x = 1) as alice is against her
+        // commitment: an old challenge expires the same way, a fresh one is a wrong answer,
+        // even with the s of x = 1
+        let one = BigUint::from(1u32);
+        let (y1, y2) = zkp.compute_pair(&one);
+        let c = zkp.stateless_challenge(&secret, &Commitment::new(y1, y2), &r1, &r2, old);
+        let auth_id = auth_id.replace(".alice", ".nobody");
+        let status = auth
+            .verify_authentication(answer_request(&auth_id, &zkp.solve(&k, &c, &one).unwrap()))
+            .await;
+        assert_eq!(status.unwrap_err().code(), Code::DeadlineExceeded);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "nobody".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let challenge = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+        let s_one = zkp.solve(&k, &BigUint::from_bytes_be(&challenge.c), &one).unwrap();
+        let status = auth.verify_authentication(answer_request(&challenge.auth_id, &s_one)).await;
+        assert_eq!(status.unwrap_err().code(), Code::PermissionDenied);
+
+        // a made-up auth_id is refused before any verification
+        let status = auth.verify_authentication(answer_request("abc", &s)).await;
+        assert_eq!(status.unwrap_err().code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_answer_must_carry_the_challenge_binding() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // a challenge bound to "channel A", answered over channel A or relayed over channel B
+        let challenge_request = |binding: &'static [u8], k: &BigUint| {
+            let (r1, r2) = zkp.compute_pair(k);
+            Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: binding.to_vec(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            })
+        };
+        for (answer_binding, expected) in
+            [(b"channel B", Err(Code::PermissionDenied)), (b"channel A", Ok(()))]
+        {
+            let k = ZKP::generate_random_number_below(&q);
+            let challenge = auth
+                .create_authentication_challenge(challenge_request(b"channel A", &k))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(challenge.binding, b"channel A");
+
+            let s = zkp.solve(&k, &BigUint::from_bytes_be(&challenge.c), &x).unwrap();
+            let request = Request::new(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s: s.to_bytes_be(),
+                binding: answer_binding.to_vec(),
+            });
+            let outcome =
+                auth.verify_authentication(request).await.map(|_| ()).map_err(|e| e.code());
+            assert_eq!(outcome, expected);
+        }
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[tokio::test]
+    async fn test_builder_with_toy_group_and_store() {
+        // x = 6, k = 7 in the toy group: y1 = 2, y2 = 3, r1 = 8, r2 = 4
+        let toy = || ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let alice = UserInfo {
+            user_name: "alice".to_string(),
+            group_id: toy().fingerprint(),
+            y1: 2u32.into(),
+            y2: 3u32.into(),
+            ..Default::default()
+        };
+        let auth = AuthImpl::builder()
+            .group(toy())
+            .store(HashMap::from([("alice".to_string(), alice)]))
+            .challenge_ttl(Duration::from_secs(5))
+            .max_challenges(10)
+            .build_impl();
+        assert_eq!(auth.policy.challenge_ttl, Duration::from_secs(5));
+
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: vec![8],
+            r2: vec![4],
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let challenge = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+        assert_eq!(challenge.group_id, toy().fingerprint().to_vec());
+        assert_eq!(challenge.auth_id.len(), MIN_ID_LENGTH);
+
+        let c = BigUint::from_bytes_be(&challenge.c);
+        let s = toy().solve(&7u32.into(), &c, &6u32.into()).unwrap();
+        let answer = auth.verify_authentication(answer_request(&challenge.auth_id, &s)).await;
+        assert_eq!(answer.unwrap().into_inner().session_id.len(), MIN_ID_LENGTH);
+
+        // and as a service for a tonic router
+        let _service: AuthServer<AuthImpl> = AuthImpl::builder().group(toy()).build();
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[test]
+    fn test_validate_answer_offline() {
+        // captured in the toy group: x = 6, k = 7, c = 4, s = 5
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let auth = AuthImpl::builder().group(toy.clone()).build_impl();
+        let registration = RegisterRequest {
+            user: "alice".to_string(),
+            y1: vec![0x02],
+            y2: vec![0x03],
+            group_id: toy.fingerprint().to_vec(),
+            pow_nonce: 0,
+        };
+        let request = AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: vec![0x08],
+            r2: vec![0x04],
+            binding: vec![0xb1, 0x4d],
+            y1: Vec::new(),
+            y2: Vec::new(),
+        };
+        let response = AuthenticationChallengeResponse {
+            auth_id: "0123456789ABCDEF".to_string(),
+            c: vec![0x04],
+            group_id: toy.fingerprint().to_vec(),
+            binding: vec![0xb1, 0x4d],
+        };
+        let answer = AuthenticationAnswerRequest {
+            auth_id: "0123456789ABCDEF".to_string(),
+            s: vec![0x05],
+            binding: vec![0xb1, 0x4d],
+        };
+        let validate = |registration: &RegisterRequest,
+                        response: &AuthenticationChallengeResponse,
+                        answer: &AuthenticationAnswerRequest| {
+            auth.validate_answer_offline(registration, (&request, response), answer)
+        };
+        assert_eq!(validate(&registration, &response, &answer).unwrap(), VerifyOutcome::Valid);
+        // nothing was stored
+        assert!(auth.user_info.lock().unwrap().is_empty());
+        assert!(auth.auth_id_to_user.lock().unwrap().is_empty());
+
+        // the pass/fail says why
+        let s = |s: u8| AuthenticationAnswerRequest { s: vec![s], ..answer.clone() };
+        assert_eq!(validate(&registration, &response, &s(6)).unwrap(), VerifyOutcome::R1Mismatch);
+        assert_eq!(
+            validate(&registration, &response, &s(11)).unwrap(),
+            VerifyOutcome::ResponseOutOfRange
+        );
+        let c = |c: u8| AuthenticationChallengeResponse { c: vec![c], ..response.clone() };
+        assert_eq!(
+            validate(&registration, &c(11), &answer).unwrap(),
+            VerifyOutcome::ChallengeOutOfRange
+        );
+        // y2 of x = 7: r1 still checks out, r2 does not
+        let other_y2 = RegisterRequest { y2: vec![0x04], ..registration.clone() };
+        assert_eq!(validate(&other_y2, &response, &answer).unwrap(), VerifyOutcome::R2Mismatch);
+
+        // not an answer to that challenge at all
+        let other_id =
+            AuthenticationAnswerRequest { auth_id: "other".to_string(), ..answer.clone() };
+        let other_binding = AuthenticationAnswerRequest { binding: Vec::new(), ..answer.clone() };
+        let zero_y1 = RegisterRequest { y1: vec![0x00], ..registration.clone() };
+        let unknown_group = RegisterRequest { group_id: vec![0xff; 32], ..registration.clone() };
+        for (registration, answer) in [
+            (&registration, &other_id),
+            (&registration, &other_binding),
+            (&zero_y1, &answer),
+            (&unknown_group, &answer),
+        ] {
+            let status = validate(registration, &response, answer).unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_derived_y2_storage() {
+        let auth = AuthImpl::builder()
+            .derived_y2(true)
+            .admin_token(Some("secret".to_string()))
+            .build_impl();
+        let zkp = ZKP::default();
+        let (_, _, _, q) = ZKP::default_constants();
+        let x = ZKP::generate_nonzero_below(&q);
+        let k = ZKP::generate_nonzero_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+
+        // only y1 is kept, the y2 derived from it is the registered one
+        let (y1, y2) = zkp.compute_pair(&x);
+        {
+            let user_info_hashmap = auth.user_info.lock().unwrap();
+            let alice = &user_info_hashmap["alice"];
+            assert!(alice.y2_derived);
+            assert_eq!((&alice.y1, &alice.y2), (&y1, &BigUint::ZERO));
+            assert_eq!(alice.registered_y2(&zkp), y2);
+        }
+
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+
+        let mut request = Request::new(ListUsersRequest {});
+        request.metadata_mut().insert("x-admin-token", "secret".parse().unwrap());
+        let users = auth.list_users(request).await.unwrap().into_inner().users;
+        assert_eq!(users[0].y2, zkp.encode_element(&y2));
+
+        // a y2 that is not y1^i proves nothing, it is kept as it is
+        let (y1_bob, _) = zkp.compute_pair(&ZKP::generate_nonzero_below(&q));
+        let (_, y2_other) = zkp.compute_pair(&ZKP::generate_nonzero_below(&q));
+        auth.register(register_request("bob", &y1_bob, &y2_other)).await.unwrap();
+        let user_info_hashmap = auth.user_info.lock().unwrap();
+        assert!(!user_info_hashmap["bob"].y2_derived);
+        assert_eq!(user_info_hashmap["bob"].y2, y2_other);
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[tokio::test]
+    async fn test_users_indexed_by_commitment_handle() {
+        let auth = AuthImpl::builder().commitment_handles(true).build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let handle = zkp.commitment_handle(&Commitment::new(y1.clone(), y2.clone()));
+
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+        assert_eq!(auth.user_info.lock().unwrap()["alice"].user_id, handle);
+        assert_eq!(auth.user_by_handle(&handle).as_deref(), Some("alice"));
+
+        // the same commitment under another name: one handle, one user
+        let status = auth.register(register_request("mallory", &y1, &y2)).await.unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+        assert!(!auth.user_info.lock().unwrap().contains_key("mallory"));
+
+        let (other1, other2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        auth.register(register_request("bob", &other1, &other2)).await.unwrap();
+        let bob = auth.user_info.lock().unwrap()["bob"].user_id.clone();
+        assert_ne!(bob, handle);
+        assert_eq!(auth.user_by_handle(&bob).as_deref(), Some("bob"));
+
+        // off by default: a random id, nothing indexed
+        let auth = AuthImpl::default();
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+        assert_ne!(auth.user_info.lock().unwrap()["alice"].user_id, handle);
+        assert_eq!(auth.user_by_handle(&handle), None);
+    }
+
+    #[cfg(feature = "dump")]
+    #[tokio::test]
+    async fn test_import_claims_commitment_handles() {
+        let auth = AuthImpl::builder().commitment_handles(true).build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let record = |user: &str, user_id: &str, y1: &BigUint, y2: &BigUint| dump::UserRecord {
+            user: user.to_string(),
+            user_id: user_id.to_string(),
+            y1: hex::encode_upper(y1.to_bytes_be()),
+            y2: hex::encode_upper(y2.to_bytes_be()),
+            group_id: String::new(),
+            commitment_digest: String::new(),
+        };
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        let handle = zkp.commitment_handle(&Commitment::new(y1.clone(), y2.clone()));
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // alice's commitment under another name
+        let json = serde_json::to_string(&[record("mallory", &handle, &y1, &y2)]).unwrap();
+        assert_eq!(auth.import_users(&json).unwrap_err().code(), Code::AlreadyExists);
+        assert!(!auth.user_info.lock().unwrap().contains_key("mallory"));
+
+        // one handle twice in a dump: nothing of it stays, the first handle included
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        let bob = zkp.commitment_handle(&Commitment::new(y1.clone(), y2.clone()));
+        let records = [record("bob", &bob, &y1, &y2), record("carol", &bob, &y1, &y2)];
+        let json = serde_json::to_string(&records).unwrap();
+        assert_eq!(auth.import_users(&json).unwrap_err().code(), Code::AlreadyExists);
+        assert!(!auth.user_info.lock().unwrap().contains_key("bob"));
+        assert_eq!(auth.user_by_handle(&bob), None);
+
+        let json = serde_json::to_string(&records[..1]).unwrap();
+        assert_eq!(auth.import_users(&json).unwrap(), 1);
+        assert_eq!(auth.user_by_handle(&bob).as_deref(), Some("bob"));
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[tokio::test]
+    async fn test_hashed_commitments_checked_at_challenge_time() {
+        // x = 6, k = 7 in the toy group: y1 = 2, y2 = 3, r1 = 8, r2 = 4
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let verification = VerificationPolicy::default().relax("fresh_commitment").unwrap();
+        let auth = AuthImpl::builder()
+            .group(toy.clone())
+            .verification(verification)
+            .hashed_commitments(true)
+            .build_impl();
+        let (y1, y2) = toy.compute_pair(&6u32.into());
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+        {
+            let user_info_hashmap = auth.user_info.lock().unwrap();
+            let alice = &user_info_hashmap["alice"];
+            assert_eq!((&alice.y1, &alice.y2), (&BigUint::ZERO, &BigUint::ZERO));
+            let digest = toy.commitment_digest(&Commitment::new(y1.clone(), y2.clone()));
+            assert_eq!(alice.commitment_digest, Some(digest));
+        }
+
+        let challenge = |y1: Vec<u8>, y2: Vec<u8>| {
+            Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: vec![8],
+                r2: vec![4],
+                binding: Vec::new(),
+                y1,
+                y2,
+            })
+        };
+        // nothing to check the answer against
+        let status = auth.create_authentication_challenge(challenge(vec![], vec![])).await;
+        assert_eq!(status.unwrap_err().code(), Code::FailedPrecondition);
+        // y2 of x = 5: not the registered commitment
+        let status = auth.create_authentication_challenge(challenge(vec![2], vec![8])).await;
+        assert_eq!(status.unwrap_err().code(), Code::PermissionDenied);
+
+        let challenge =
+            auth.create_authentication_challenge(challenge(vec![2], vec![3])).await.unwrap();
+        let challenge = challenge.into_inner();
+        let s =
+            toy.solve(&7u32.into(), &BigUint::from_bytes_be(&challenge.c), &6u32.into()).unwrap();
+        assert!(auth.verify_authentication(answer_request(&challenge.auth_id, &s)).await.is_ok());
+
+        // the flows without y1, y2 in the request cannot serve this user
+        let request = Request::new(CommittedChallengeRequest { user: "alice".to_string() });
+        let status = auth.create_committed_challenge(request).await;
+        assert_eq!(status.unwrap_err().code(), Code::FailedPrecondition);
+    }
+
+    // in the toy group q = 11, a uniform c in [0, q) would be 0 about once in 11 draws
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[tokio::test]
+    async fn test_issued_challenge_is_never_zero() {
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        // the toy group has too few (r1, r2) for fresh ones on every request
+        let verification = VerificationPolicy::default().relax("fresh_commitment").unwrap();
+        let auth = AuthImpl::builder().group(toy.clone()).verification(verification).build_impl();
+        let (y1, y2) = toy.compute_pair(&6u32.into());
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        let mut issued = HashSet::new();
+        for k in (1..11u32).cycle().take(500) {
+            let (r1, r2) = toy.compute_pair(&k.into());
+            let request = Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            });
+            let challenge =
+                auth.create_authentication_challenge(request).await.unwrap().into_inner();
+            issued.insert(BigUint::from_bytes_be(&challenge.c));
+        }
+        assert!(!issued.contains(&BigUint::ZERO));
+        // and every other value does come up
+        assert_eq!(issued.len(), 10);
+
+        // nor from the coin-flipping flow
+        for _ in 0..100 {
+            assert_ne!(toy.commit_challenge().0, BigUint::ZERO);
+        }
+    }
+
+    // worker threads, so the registrations really run at the same time
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_concurrent_registrations_of_one_name() {
+        let auth = Arc::new(AuthImpl::default());
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = Arc::new(ZKP::new(alpha, beta, p, q.clone()));
+
+        // 32 clients race to register "alice", each with its own secret
+        let tasks: Vec<_> = (0..32)
+            .map(|_| {
+                let (auth, zkp, q) = (auth.clone(), zkp.clone(), q.clone());
+                tokio::spawn(async move {
+                    let x = ZKP::generate_random_number_below(&q);
+                    let (y1, y2) = zkp.compute_pair(&x);
+                    let outcome = auth.register(register_request("alice", &y1, &y2)).await;
+                    outcome.map(|_| y1).map_err(|status| status.code())
+                })
+            })
+            .collect();
+        let mut winners = Vec::new();
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(y1) => winners.push(y1),
+                Err(code) => assert_eq!(code, Code::AlreadyExists),
+            }
+        }
+
+        // exactly one got in, and its commitment is the one kept
+        assert_eq!(winners.len(), 1);
+        assert_eq!(auth.user_info.lock().unwrap()["alice"].y1, winners[0]);
+    }
+
+    #[test]
+    fn test_user_info_debug_redacts_secrets() {
+        let user_info = UserInfo {
+            user_name: "alice".to_string(),
+            y1: 2u32.into(),
+            y2: 3u32.into(),
+            s: 987654321u32.into(),
+            session_id: "SeSsIoN".to_string(),
+            ..Default::default()
+        };
+        let shown = format!("{user_info:?}");
+
+        assert!(shown.contains("alice"));
+        assert!(shown.contains("y1: 2") && shown.contains("y2: 3"));
+        assert!(shown.contains("s: ***"));
+        assert!(!shown.contains("987654321"));
+        assert!(!shown.contains("SeSsIoN"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_gets_a_challenge_that_never_verifies() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (known_id, known_c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+
+        // (another k: a reused commitment would be refused before anything else)
+        let k_decoy = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k_decoy);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "nobody".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let unknown = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+        let unknown_c = BigUint::from_bytes_be(&unknown.c);
+
+        // nothing in the response tells the two apart
+        assert_eq!(unknown.auth_id.len(), known_id.len());
+        assert!(unknown_c < q && known_c < q);
+        let known_group = auth.user_info.lock().unwrap()["alice"].group_id;
+        assert_eq!(unknown.group_id, known_group.to_vec());
+
+        // x = 1 is the decoy commitment, and even the matching answer is a plain wrong answer
+        // (down to the machine code in the details)
+        let s = zkp.solve(&k_decoy, &unknown_c, &BigUint::from(1u32)).unwrap();
+        let status = auth.verify_authentication(answer_request(&unknown.auth_id, &s)).await;
+        let status = status.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(answer_failure_of(&status).code, "cond1_failed");
+
+        let wrong = zkp.solve(&k, &known_c, &(&x + 1u32)).unwrap();
+        let status = auth.verify_authentication(answer_request(&known_id, &wrong)).await;
+        let status = status.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(answer_failure_of(&status).code, VerifyOutcome::R1Mismatch.as_code());
+        assert!(!auth.user_info.lock().unwrap().contains_key("nobody"));
+    }
+
+    #[tokio::test]
+    async fn test_challenge_rejects_element_of_wrong_order() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p.clone(), q.clone());
+
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        let (r1, _) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: (&p - 1u32).to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let status = auth.create_authentication_challenge(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_answer_is_rejected() {
+        // past the retry window an identical answer is a replay
+        let policy = ProtocolPolicy { answer_retry_ttl: Duration::ZERO, ..Default::default() };
+        let auth = AuthImpl { policy, ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn test_verification_policy_checks_one_by_one() {
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        // the strictest policy with one check turned off
+        let without = |check: &str| VerificationPolicy::default().relax(check).unwrap();
+
+        // challenge_expiry: an answer after challenge_ttl
+        for (verification, expected) in [
+            (VerificationPolicy::default(), Err(Code::DeadlineExceeded)),
+            (without("challenge_expiry"), Ok(())),
+        ] {
+            let policy = ProtocolPolicy { challenge_ttl: Duration::ZERO, ..Default::default() };
+            let auth = AuthImpl { policy, verification, ..Default::default() };
+            let x = ZKP::generate_random_number_below(&q);
+            let k = ZKP::generate_random_number_below(&q);
+            let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            let s = zkp.solve(&k, &c, &x).unwrap();
+            let outcome = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+            assert_eq!(outcome.map(|_| ()).map_err(|e| e.code()), expected);
+        }
+
+        // single_use_challenge: the right answer after a wrong one
+        for (verification, expected) in [
+            (VerificationPolicy::default(), Err(Code::FailedPrecondition)),
+            (without("single_use_challenge"), Ok(())),
+        ] {
+            let auth = AuthImpl { verification, ..Default::default() };
+            let x = ZKP::generate_random_number_below(&q);
+            let k = ZKP::generate_random_number_below(&q);
+            let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+            let wrong = zkp.solve(&k, &c, &(&x + 1u32)).unwrap();
+            let status = auth.verify_authentication(answer_request(&auth_id, &wrong)).await;
+            assert_eq!(status.unwrap_err().code(), Code::PermissionDenied);
+            let s = zkp.solve(&k, &c, &x).unwrap();
+            let outcome = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+            assert_eq!(outcome.map(|_| ()).map_err(|e| e.code()), expected);
+        }
+
+        // fresh_commitment: a second challenge for the same (r1, r2)
+        for (verification, expected) in [
+            (VerificationPolicy::default(), Err(Code::InvalidArgument)),
+            (without("fresh_commitment"), Ok(())),
+        ] {
+            let auth = AuthImpl { verification, ..Default::default() };
+            let x = ZKP::generate_random_number_below(&q);
+            let k = ZKP::generate_random_number_below(&q);
+            register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+            let (r1, r2) = zkp.compute_pair(&k);
+            let request = Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            });
+            let outcome = auth.create_authentication_challenge(request).await;
+            assert_eq!(outcome.map(|_| ()).map_err(|e| e.code()), expected);
+        }
+
+        // unique_response: the same s again once the retry window is over (with the challenge
+        // used up, the single-use check is what catches it when this one is off)
+        for (verification, expected) in [
+            (VerificationPolicy::default(), Code::AlreadyExists),
+            (without("unique_response"), Code::FailedPrecondition),
+        ] {
+            let policy = ProtocolPolicy { answer_retry_ttl: Duration::ZERO, ..Default::default() };
+            let auth = AuthImpl { policy, verification, ..Default::default() };
+            let x = ZKP::generate_random_number_below(&q);
+            let k = ZKP::generate_random_number_below(&q);
+            let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+            let s = zkp.solve(&k, &c, &x).unwrap();
+            assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+            let status = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+            assert_eq!(status.unwrap_err().code(), expected);
+        }
+
+        assert!(VerificationPolicy::default().relax("no_such_check").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_answer_without_commitment_is_refused() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+
+        // a challenge record that lost (or never had) its r1, r2
+        {
+            let mut challenges = auth.auth_id_to_user.lock().unwrap();
+            let taken = challenges.get_mut(&auth_id).unwrap().prover_commitment.take();
+            assert_eq!(taken, Some(zkp.compute_pair(&k)));
+        }
+
+        // even the right answer: refused as such, not as a wrong answer against zeros
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        let failure = answer_failure_of(&status);
+        assert_eq!((failure.code.as_str(), failure.retryable), ("commitment_absent", true));
+        assert!(auth.user_info.lock().unwrap()["alice"].session_id.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refused_answers_say_whether_to_retry() {
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+
+        // a wrong answer: the same secret fails again on a new challenge
+        let auth = AuthImpl::default();
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let wrong = zkp.solve(&k, &c, &(&x + 1u32)).unwrap();
+        let status = auth.verify_authentication(answer_request(&auth_id, &wrong)).await;
+        let failure = answer_failure_of(&status.unwrap_err());
+        assert_eq!(failure.code, VerifyOutcome::R1Mismatch.as_code());
+        assert!(!failure.retryable);
+
+        // the right answer after that: the challenge is used up, a new one is not
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+        let failure = answer_failure_of(&status.unwrap_err());
+        assert_eq!((failure.code.as_str(), failure.retryable), ("challenge_answered", true));
+
+        // an unknown (or evicted) challenge
+        let status = auth.verify_authentication(answer_request("no-such-auth-id", &s)).await;
+        let failure = answer_failure_of(&status.unwrap_err());
+        assert_eq!((failure.code.as_str(), failure.retryable), ("challenge_not_found", true));
+
+        // an expired challenge
+        let policy = ProtocolPolicy { challenge_ttl: Duration::ZERO, ..Default::default() };
+        let auth = AuthImpl { policy, ..Default::default() };
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded);
+        let failure = answer_failure_of(&status);
+        assert_eq!((failure.code.as_str(), failure.retryable), ("challenge_expired", true));
+
+        // the same s again (with a leading zero byte) once the retry window is over
+        let policy = ProtocolPolicy { answer_retry_ttl: Duration::ZERO, ..Default::default() };
+        let auth = AuthImpl { policy, ..Default::default() };
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+        let mut replay = answer_request(&auth_id, &s);
+        replay.get_mut().s.insert(0, 0);
+        let status = auth.verify_authentication(replay).await;
+        let failure = answer_failure_of(&status.unwrap_err());
+        assert_eq!((failure.code.as_str(), failure.retryable), ("answer_replayed", false));
+    }
+
+    #[tokio::test]
+    async fn test_oldest_challenge_is_evicted_when_full() {
+        let auth = AuthImpl::default().with_capacities(2, 2);
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        // three users, each with a pending challenge: only the last two fit
+        let mut runs = Vec::new();
+        for user in ["alice", "bob", "carol"] {
+            let x = ZKP::generate_random_number_below(&q);
+            let k = ZKP::generate_random_number_below(&q);
+            let (auth_id, c) = register_and_challenge(&auth, &zkp, user, &x, &k).await;
+            runs.push((auth_id, zkp.solve(&k, &c, &x).unwrap()));
+        }
+        assert_eq!(auth.auth_id_to_user.lock().unwrap().len(), 2);
+
+        let (auth_id, s) = &runs[0];
+        let status = auth.verify_authentication(answer_request(auth_id, s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        for (auth_id, s) in &runs[1..] {
+            assert!(auth.verify_authentication(answer_request(auth_id, s)).await.is_ok());
+        }
+
+        // the answers seen are kept for as many auth_ids as there are challenges
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "dave", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+        let seen_responses = auth.seen_responses.lock().unwrap();
+        assert_eq!(seen_responses.len(), 2);
+        assert!(!seen_responses.contains(&runs[1].0));
+    }
+
+    #[tokio::test]
+    async fn test_verify_results_are_bounded_and_expire() {
+        let policy = ProtocolPolicy { answer_retry_ttl: Duration::ZERO, ..Default::default() };
+        let auth = AuthImpl { policy, ..Default::default() }.with_capacities(2, MAX_SESSIONS);
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // three wrong answers, each to its own challenge: only the last two outcomes are kept
+        let mut auth_ids = Vec::new();
+        for _ in 0..3 {
+            let (r1, r2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+            let request = Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            });
+            let auth_id =
+                auth.create_authentication_challenge(request).await.unwrap().into_inner().auth_id;
+            let status = auth.verify_authentication(answer_request(&auth_id, &q)).await;
+            assert_eq!(status.unwrap_err().code(), Code::PermissionDenied);
+            auth_ids.push(auth_id);
+        }
+        assert_eq!(auth.verify_results.lock().unwrap().len(), 2);
+
+        // past the (zero) TTL the retry finds no outcome, it is a replay of the same s
+        let status = auth.verify_authentication(answer_request(&auth_ids[2], &q)).await;
+        assert_eq!(status.unwrap_err().code(), Code::AlreadyExists);
+        assert_eq!(auth.verify_results.lock().unwrap().len(), 1);
+    }
+
+    fn register_and_login_request(
+        zkp: &ZKP,
+        user: &str,
+        x: &BigUint,
+        proof: Proof,
+    ) -> Request<RegisterAndLoginRequest> {
+        let (y1, y2) = zkp.compute_pair(x);
+        Request::new(RegisterAndLoginRequest {
+            registration: Some(register_request(user, &y1, &y2).into_inner()),
+            r1: proof.r1.to_bytes_be(),
+            r2: proof.r2.to_bytes_be(),
+            c: proof.c.to_bytes_be(),
+            s: proof.s.to_bytes_be(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_register_and_login_in_one_call() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+
+        // a proof made for another name, or of another secret, registers nothing
+        let proof = zkp.prove_non_interactive(&x, &ZKP::register_and_login_context("bob"));
+        let status = auth
+            .register_and_login(register_and_login_request(&zkp, "alice", &x, proof))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let other = ZKP::generate_random_number_below(&q);
+        let proof = zkp.prove_non_interactive(&other, &ZKP::register_and_login_context("alice"));
+        let mut request = register_and_login_request(&zkp, "alice", &x, proof);
+        let status = auth.register_and_login(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(auth.user_info.lock().unwrap().is_empty());
+        assert!(auth.sessions.lock().unwrap().is_empty());
+
+        let proof = zkp.prove_non_interactive(&x, &ZKP::register_and_login_context("alice"));
+        request = register_and_login_request(&zkp, "alice", &x, proof);
+        let replay = Request::new(request.get_ref().clone());
+        let session_id = auth.register_and_login(request).await.unwrap().into_inner().session_id;
+
+        let who_am_i = Request::new(WhoAmIRequest { session_id, group_id: Vec::new() });
+        assert_eq!(auth.who_am_i(who_am_i).await.unwrap().into_inner().user, "alice");
+
+        // the registration is a normal one: the interactive protocol works from now on
+        let k = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let response = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+        let c = zkp.challenge_from_bytes(&response.c).unwrap();
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&response.auth_id, &s)).await.is_ok());
+        assert_eq!(auth.user_info.lock().unwrap()["alice"].y1, y1);
+        assert_eq!(auth.user_info.lock().unwrap()["alice"].y2, y2);
+
+        // replaying the captured request gives no second session
+        let status = auth.register_and_login(replay).await.unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn test_retried_answer_gets_the_same_outcome() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        // a wrong answer stays wrong when retried
+        let wrong = &s + 1u32;
+        for _ in 0..2 {
+            let status =
+                auth.verify_authentication(answer_request(&auth_id, &wrong)).await.unwrap_err();
+            assert_eq!(status.code(), Code::PermissionDenied);
+        }
+        // and it used the challenge up (single_use_challenge): the right s needs a new one
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        let k = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let response = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+        let auth_id = response.auth_id;
+        let s = zkp.solve(&k, &BigUint::from_bytes_be(&response.c), &x).unwrap();
+
+        let first = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap();
+        let retry = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap();
+        assert_eq!(first.get_ref().session_id, retry.get_ref().session_id);
+        // the retry did not mint a second session
+        assert_eq!(auth.sessions.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_through_an_arc() {
+        let auth = Arc::new(AuthImpl::default());
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let auth = auth.clone();
+                tokio::spawn(async move {
+                    let (alpha, beta, p, q) = ZKP::default_constants();
+                    let zkp = ZKP::new(alpha, beta, p, q.clone());
+                    let x = ZKP::generate_random_number_below(&q);
+                    let k = ZKP::generate_random_number_below(&q);
+                    let user = format!("user{i}");
+
+                    let (auth_id, c) = register_and_challenge(&auth, &zkp, &user, &x, &k).await;
+                    let s = zkp.solve(&k, &c, &x).unwrap();
+                    auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap()
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(auth.user_info.lock().unwrap().len(), 8);
+        assert_eq!(auth.sessions.lock().unwrap().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_registration_requires_proof_of_work() {
+        let policy = ProtocolPolicy { registration_pow_bits: 6, ..Default::default() };
+        let auth = AuthImpl { policy, ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+
+        let params =
+            auth.get_parameters(Request::new(ParametersRequest {})).await.unwrap().into_inner();
+        assert_eq!(params.registration_pow_bits, 6);
+
+        let nonce = zkp_chaum_pedersen::solve_registration_pow("alice", 6);
+
+        // a nonce short of the required zero bits is refused before anything is stored
+        let mut request = register_request("alice", &y1, &y2);
+        request.get_mut().pow_nonce =
+            (0..).find(|n| !check_registration_pow("alice", *n, 6)).unwrap();
+        let status = auth.register(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(auth.user_info.lock().unwrap().is_empty());
+
+        let mut request = register_request("alice", &y1, &y2);
+        request.get_mut().pow_nonce = nonce;
+        assert!(auth.register(request).await.is_ok());
+        assert!(auth.user_info.lock().unwrap().contains_key("alice"));
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[tokio::test]
+    async fn test_parameters_of_a_custom_group() {
+        // beta = 9 is no power alpha^ZKP::beta_exponent() of alpha = 4 in the toy group
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let auth = AuthImpl::builder().group(toy.clone()).build_impl();
+
+        let params =
+            auth.get_parameters(Request::new(ParametersRequest {})).await.unwrap().into_inner();
+        let advertised = (
+            BigUint::from_bytes_be(&params.alpha),
+            BigUint::from_bytes_be(&params.beta),
+            BigUint::from_bytes_be(&params.p),
+            BigUint::from_bytes_be(&params.q),
+        );
+        let (alpha, beta, p, q) = toy.constants();
+        assert_eq!(advertised, (alpha.clone(), beta.clone(), p.clone(), q.clone()));
+        assert!(!toy.beta_is_derived());
+        assert!(!params.beta_derived);
+    }
+
+    #[tokio::test]
+    async fn test_advertised_ttls_are_enforced() {
+        let policy = ProtocolPolicy {
+            challenge_ttl: Duration::from_secs(1),
+            session_ttl: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let auth = AuthImpl { policy, ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let params =
+            auth.get_parameters(Request::new(ParametersRequest {})).await.unwrap().into_inner();
+        assert_eq!(params.challenge_ttl_secs, policy.challenge_ttl.as_secs());
+        assert_eq!(params.session_ttl_secs, policy.session_ttl.as_secs());
+        assert_eq!(BigUint::from_bytes_be(&params.q), q);
+        assert!(params.beta_derived);
+
+        let advertised_challenge_ttl = Duration::from_secs(params.challenge_ttl_secs);
+        let advertised_session_ttl = Duration::from_secs(params.session_ttl_secs);
+
+        // answered in time: accepted, and the session works until it expires
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let session_id = auth
+            .verify_authentication(answer_request(&auth_id, &s))
+            .await
+            .unwrap()
+            .into_inner()
+            .session_id;
+
+        let who_am_i =
+            || Request::new(WhoAmIRequest { session_id: session_id.clone(), group_id: Vec::new() });
+        assert_eq!(auth.who_am_i(who_am_i()).await.unwrap().into_inner().user, "alice");
+
+        tokio::time::sleep(advertised_session_ttl + Duration::from_millis(100)).await;
+        let status = auth.who_am_i(who_am_i()).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        // answered after the advertised challenge TTL: rejected even though s is correct
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "bob", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        tokio::time::sleep(advertised_challenge_ttl + Duration::from_millis(100)).await;
+        let status = auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_committed_challenge_four_moves() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        let k = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+
+        // 1. verifier commits to c
+        let request = Request::new(CommittedChallengeRequest { user: "alice".to_string() });
+        let response = auth.create_committed_challenge(request).await.unwrap().into_inner();
+        let auth_id = response.auth_id;
+
+        // 2. prover sends r1, r2 and 3. verifier reveals c, which the prover checks
+        let reveal_request = || {
+            Request::new(RevealChallengeRequest {
+                auth_id: auth_id.clone(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+            })
+        };
+        let revealed = auth.reveal_challenge(reveal_request()).await.unwrap().into_inner();
+        let c = BigUint::from_bytes_be(&revealed.c);
+        assert!(zkp.verify_challenge_commitment(
+            &response.challenge_commitment,
+            &c,
+            &revealed.nonce
+        ));
+
+        // the challenge is revealed once, r1/r2 cannot be replaced after c is known
+        let status = auth.reveal_challenge(reveal_request()).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        // 4. prover answers
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_users_are_not_told_apart_in_other_flows() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // committed challenge: committed, revealed and refused like a wrong answer of alice's
+        let mut failures = Vec::new();
+        for user in ["alice", "nobody"] {
+            let request = Request::new(CommittedChallengeRequest { user: user.to_string() });
+            let response = auth.create_committed_challenge(request).await.unwrap().into_inner();
+            assert_eq!(response.challenge_commitment.len(), 32);
+
+            let k = ZKP::generate_random_number_below(&q);
+            let (r1, r2) = zkp.compute_pair(&k);
+            let request = Request::new(RevealChallengeRequest {
+                auth_id: response.auth_id.clone(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+            });
+            let revealed = auth.reveal_challenge(request).await.unwrap().into_inner();
+            let c = BigUint::from_bytes_be(&revealed.c);
+            assert!(zkp.verify_challenge_commitment(
+                &response.challenge_commitment,
+                &c,
+                &revealed.nonce
+            ));
+
+            // the secret of alice, wrong for both
+            let s = (zkp.solve(&k, &c, &x).unwrap() + 1u32) % &q;
+            let status = auth
+                .verify_authentication(answer_request(&response.auth_id, &s))
+                .await
+                .unwrap_err();
+            failures.push((status.code(), answer_failure_of(&status).retryable));
+        }
+        assert_eq!(failures[0], failures[1]);
+        assert_eq!(failures[0].0, Code::PermissionDenied);
+
+        // VerifyComplete: an unknown user's run is not valid, but not NOT_FOUND either
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let verify_complete = |user: &str| {
+            auth.verify_complete(Request::new(VerifyCompleteRequest {
+                user: user.to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                c: c.to_bytes_be(),
+                s: s.to_bytes_be(),
+            }))
+        };
+        assert!(verify_complete("alice").await.unwrap().into_inner().valid);
+        assert!(!verify_complete("nobody").await.unwrap().into_inner().valid);
+    }
+
+    // Chaum-Pedersen without saying so: the server cannot precompute (y1^c, y2^c) and verifies
+    // with the c it kept for the challenge
+    #[derive(Debug)]
+    struct OpaqueChaumPedersen;
+
+    impl AuthBackend for OpaqueChaumPedersen {
+        fn register(&self, group: &ZKP, y1: &[u8], y2: &[u8]) -> Result<(), ZKPError> {
+            ChaumPedersen.register(group, y1, y2)
+        }
+
+        fn challenge(&self, group: &ZKP, r1: &[u8], r2: &[u8]) -> Result<Vec<u8>, ZKPError> {
+            ChaumPedersen.challenge(group, r1, r2)
+        }
+
+        fn verify(
+            &self,
+            group: &ZKP,
+            y1: &[u8],
+            y2: &[u8],
+            r1: &[u8],
+            r2: &[u8],
+            c: &[u8],
+            s: &[u8],
+        ) -> Result<bool, ZKPError> {
+            ChaumPedersen.verify(group, y1, y2, r1, r2, c, s)
+        }
+    }
+
+    // two challenges open for one user, answered in the order they were issued: each answer is
+    // checked (and its receipt signed) with the c of its own challenge
+    #[tokio::test]
+    async fn test_interleaved_challenges_of_one_user() {
+        let receipt_key = || Some(Secret::from_bytes_be(b"receipt key"));
+        let precomputed = AuthImpl::builder().receipt_key(receipt_key()).build_impl();
+        let opaque = AuthImpl::builder()
+            .receipt_key(receipt_key())
+            .backend(OpaqueChaumPedersen)
+            .build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        for auth in [precomputed, opaque] {
+            let x = ZKP::generate_random_number_below(&q);
+            let (k1, k2) =
+                (ZKP::generate_random_number_below(&q), ZKP::generate_random_number_below(&q));
+            let (first, c1) = register_and_challenge(&auth, &zkp, "alice", &x, &k1).await;
+
+            let (r1, r2) = zkp.compute_pair(&k2);
+            let request = Request::new(AuthenticationChallengeRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            });
+            let response =
+                auth.create_authentication_challenge(request).await.unwrap().into_inner();
+            let (second, c2) = (response.auth_id, BigUint::from_bytes_be(&response.c));
+
+            for (auth_id, k, c) in [(first, k1, c1), (second, k2, c2)] {
+                let s = zkp.solve(&k, &c, &x).unwrap();
+                let response = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+                let receipt = response.unwrap().into_inner().receipt.unwrap();
+                assert_eq!(receipt.c, c.to_bytes_be());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_committed_challenge_mismatch_is_detected() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // answering before the reveal is refused
+        let request = Request::new(CommittedChallengeRequest { user: "alice".to_string() });
+        let response = auth.create_committed_challenge(request).await.unwrap().into_inner();
+        let status = auth
+            .verify_authentication(answer_request(&response.auth_id, &BigUint::from(1u32)))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        // a verifier changing c between commitment and reveal is caught by the prover
+        let k = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let request = Request::new(CommittedChallengeRequest { user: "alice".to_string() });
+        let response = auth.create_committed_challenge(request).await.unwrap().into_inner();
+        auth.auth_id_to_user.lock().unwrap().get_mut(&response.auth_id).unwrap().c += 1u32;
+
+        let request = Request::new(RevealChallengeRequest {
+            auth_id: response.auth_id,
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+        });
+        let revealed = auth.reveal_challenge(request).await.unwrap().into_inner();
+        let c = BigUint::from_bytes_be(&revealed.c);
+        assert!(!zkp.verify_challenge_commitment(
+            &response.challenge_commitment,
+            &c,
+            &revealed.nonce
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_users_requires_admin_token() {
+        let auth = AuthImpl { admin_token: Some("s3cret".to_string()), ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        auth.verify_authentication(answer_request(&auth_id, &s)).await.unwrap();
+
+        let list_users = |token: Option<&str>| {
+            let mut request = Request::new(ListUsersRequest {});
+            if let Some(token) = token {
+                request.metadata_mut().insert("x-admin-token", token.parse().unwrap());
+            }
+            auth.list_users(request)
+        };
+
+        let status = list_users(None).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        let status = list_users(Some("guess")).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+
+        let users = list_users(Some("s3cret")).await.unwrap().into_inner().users;
+        let (y1, y2) = zkp.compute_pair(&x);
+        let user_id = auth.user_info.lock().unwrap()["alice"].user_id.clone();
+        // RegisteredUser has no field for c, s, r1/r2 or the session: the whole entry is public
+        assert_eq!(
+            users,
+            vec![RegisteredUser {
+                user: "alice".to_string(),
+                user_id,
+                y1: y1.to_bytes_be(),
+                y2: y2.to_bytes_be(),
+                group_id: zkp.fingerprint().to_vec(),
+                commitment_digest: Vec::new(),
+            }]
+        );
+
+        // without a configured token the endpoint is closed to everyone
+        let auth = AuthImpl::default();
+        let mut request = Request::new(ListUsersRequest {});
+        request.metadata_mut().insert("x-admin-token", "s3cret".parse().unwrap());
+        let status = auth.list_users(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+
+    #[cfg(feature = "dump")]
+    #[tokio::test]
+    async fn test_export_then_import_keeps_users_able_to_authenticate() {
+        let old = AuthImpl { admin_token: Some("s3cret".to_string()), ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        old.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // what server export writes
+        let mut request = Request::new(ListUsersRequest {});
+        request.metadata_mut().insert("x-admin-token", "s3cret".parse().unwrap());
+        let users = old.list_users(request).await.unwrap().into_inner().users;
+        let json = dump::to_json(&users);
+
+        let new = AuthImpl::default();
+        assert_eq!(new.import_users(&json).unwrap(), 1);
+        assert_eq!(
+            new.user_info.lock().unwrap()["alice"].user_id,
+            old.user_info.lock().unwrap()["alice"].user_id
+        );
+
+        let k = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let response = new.create_authentication_challenge(request).await.unwrap().into_inner();
+        let c = zkp.challenge_from_bytes(&response.c).unwrap();
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(new.verify_authentication(answer_request(&response.auth_id, &s)).await.is_ok());
+
+        // a tampered dump is refused as a whole
+        let tampered = json.replace(&hex::encode_upper(y1.to_bytes_be()), "02");
+        assert!(AuthImpl::default().import_users(&tampered).is_err());
+
+        // registered users are not overwritten, and neither a dump naming alice twice nor one
+        // clashing with a registration imports anyone
+        let status = new.import_users(&json).unwrap_err();
+        assert_eq!(status.code(), Code::AlreadyExists);
+        let mut records: Vec<dump::UserRecord> = serde_json::from_str(&json).unwrap();
+        records.push(dump::UserRecord { user: "bob".to_string(), ..records[0].clone() });
+        records.push(records[0].clone());
+        let twice = serde_json::to_string(&records).unwrap();
+        let fresh = AuthImpl::default();
+        assert_eq!(fresh.import_users(&twice).unwrap_err().code(), Code::AlreadyExists);
+        assert!(fresh.user_info.lock().unwrap().is_empty());
+        fresh.register(register_request("bob", &y1, &y2)).await.unwrap();
+        let clashing = serde_json::to_string(&records[..2]).unwrap();
+        assert_eq!(fresh.import_users(&clashing).unwrap_err().code(), Code::AlreadyExists);
+        assert_eq!(fresh.user_info.lock().unwrap().len(), 1);
+    }
+
+    #[cfg(not(feature = "group-rfc5114-2048"))]
+    #[tokio::test]
+    async fn test_users_on_two_groups_share_one_server() {
+        let auth = AuthImpl::default();
+
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let old_group = ZKP::new(alpha, beta, p, q.clone());
+        let (alpha, beta, p, q_2048) = ZKP::get_constants_2048();
+        let new_group = ZKP::new(alpha, beta, p, q_2048.clone());
+
+        // an old client sends no group_id and lands in the default 1024-bit group
+        let x_old = ZKP::generate_random_number_below(&q);
+        let k_old = ZKP::generate_random_number_below(&q);
+        let (auth_id_old, c_old) =
+            register_and_challenge(&auth, &old_group, "old", &x_old, &k_old).await;
+
+        // a new client registers in the 2048-bit group
+        let x_new = ZKP::generate_random_number_below(&q_2048);
+        let (y1, y2) = new_group.compute_pair(&x_new);
+        let request = Request::new(RegisterRequest {
+            user: "new".to_string(),
+            y1: y1.to_bytes_be(),
+            y2: y2.to_bytes_be(),
+            group_id: new_group.fingerprint().to_vec(),
+            pow_nonce: 0,
+        });
+        auth.register(request).await.unwrap();
+
+        let k_new = ZKP::generate_random_number_below(&q_2048);
+        let (r1, r2) = new_group.compute_pair(&k_new);
+        let request = Request::new(AuthenticationChallengeRequest {
+            user: "new".to_string(),
+            r1: r1.to_bytes_be(),
+            r2: r2.to_bytes_be(),
+            binding: Vec::new(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        });
+        let response = auth.create_authentication_challenge(request).await.unwrap().into_inner();
+        assert_eq!(response.group_id, new_group.fingerprint());
+        let c_new = new_group.challenge_from_bytes(&response.c).unwrap();
+
+        let s_old = old_group.solve(&k_old, &c_old, &x_old).unwrap();
+        let answer = auth.verify_authentication(answer_request(&auth_id_old, &s_old)).await;
+        let session_old = answer.unwrap().into_inner().session_id;
+        let s_new = new_group.solve(&k_new, &c_new, &x_new).unwrap();
+        let answer = auth.verify_authentication(answer_request(&response.auth_id, &s_new)).await;
+        let session_new = answer.unwrap().into_inner().session_id;
+
+        // each session holds in its own group (or when no group is asked for), not in the other
+        let who_am_i = |session_id: &str, group: &ZKP| {
+            auth.who_am_i(Request::new(WhoAmIRequest {
+                session_id: session_id.to_string(),
+                group_id: group.fingerprint().to_vec(),
+            }))
+        };
+        let who = who_am_i(&session_old, &old_group).await.unwrap().into_inner();
+        assert_eq!((who.user.as_str(), who.group_id), ("old", old_group.fingerprint().to_vec()));
+        assert_eq!(who_am_i(&session_new, &new_group).await.unwrap().into_inner().user, "new");
+        let status = who_am_i(&session_old, &new_group).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let status = who_am_i(&session_new, &old_group).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let request = WhoAmIRequest { session_id: session_new.clone(), group_id: Vec::new() };
+        assert!(auth.who_am_i(Request::new(request)).await.is_ok());
+
+        // a group the server does not know is refused at registration
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let request = Request::new(RegisterRequest {
+            user: "toy".to_string(),
+            y1: vec![2],
+            y2: vec![3],
+            group_id: toy.fingerprint().to_vec(),
+            pow_nonce: 0,
+        });
+        let status = auth.register(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_verify_complete() {
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        let verify_complete = |c: &BigUint, s: &BigUint| {
+            auth.verify_complete(Request::new(VerifyCompleteRequest {
+                user: "alice".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                c: c.to_bytes_be(),
+                s: s.to_bytes_be(),
+            }))
+        };
+
+        // no stored challenge: the same complete proof verifies every time
+        assert!(verify_complete(&c, &s).await.unwrap().into_inner().valid);
+        assert!(verify_complete(&c, &s).await.unwrap().into_inner().valid);
+
+        let wrong_s = (&s + 1u32) % &q;
+        assert!(!verify_complete(&c, &wrong_s).await.unwrap().into_inner().valid);
+
+        // s = q is out of range, not merely wrong
+        let status = verify_complete(&c, &q).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(auth.sessions.lock().unwrap().is_empty());
+        // the three decoded runs were verified on the pool
+        assert_eq!(auth.verify_pool.jobs(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_verify_blob() {
+        let auth = AuthImpl::default();
+        let zkp = ZKP::default();
+        let (_, _, _, q) = ZKP::default_constants();
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let commitment = Commitment::new(y1.clone(), y2.clone());
+        let verify_blob =
+            |blob: Vec<u8>| auth.verify_blob(Request::new(VerifyBlobRequest { blob }));
+
+        let proof = zkp.prove_non_interactive_in(&x, Some(PROOF_BLOB_DOMAIN), b"");
+        let blob = zkp.proof_blob(&commitment, &proof).unwrap();
+        assert!(verify_blob(blob.clone()).await.unwrap().into_inner().valid);
+        let Proof { r1, r2, c, s } = proof;
+        let wrong = Proof::new(r1, r2, c, (&s + 1u32) % &q);
+        let wrong_blob = zkp.proof_blob(&commitment, &wrong).unwrap();
+        assert!(!verify_blob(wrong_blob).await.unwrap().into_inner().valid);
+
+        // an honest run with a c of its own, and one forged without x: any s and c, then
+        // r1 = alpha^s * y1^c, r2 = beta^s * y2^c
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        let chosen = Proof::new(r1, r2, c.clone(), s.clone());
+        assert!(zkp.verify_proof(&commitment, &chosen, ChallengeSource::Given(&c)));
+        let chosen_blob = zkp.proof_blob(&commitment, &chosen).unwrap();
+        assert!(!verify_blob(chosen_blob).await.unwrap().into_inner().valid);
+        let (alpha, beta, p, _) = ZKP::default_constants();
+        let r1 = alpha.modpow(&s, &p) * y1.modpow(&c, &p) % &p;
+        let r2 = beta.modpow(&s, &p) * y2.modpow(&c, &p) % &p;
+        let forged = Proof::new(r1, r2, c.clone(), s);
+        assert!(zkp.verify_proof(&commitment, &forged, ChallengeSource::Given(&c)));
+        let forged_blob = zkp.proof_blob(&commitment, &forged).unwrap();
+        assert!(!verify_blob(forged_blob).await.unwrap().into_inner().valid);
+
+        // a group the server does not have, and a fingerprint that is not the fields' group
+        let toy = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        let toy_proof = Proof::new(8u32.into(), 4u32.into(), 4u32.into(), 5u32.into());
+        let toy_commitment = Commitment::new(2u32.into(), 3u32.into());
+        let toy_blob = toy.proof_blob(&toy_commitment, &toy_proof).unwrap();
+        let status = verify_blob(toy_blob.clone()).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let mut relabelled = toy_blob;
+        relabelled[1..33].copy_from_slice(&zkp.fingerprint());
+        let status = verify_blob(relabelled).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // truncated in the header and in the fields
+        for len in [10, blob.len() - 1] {
+            let status = verify_blob(blob[..len].to_vec()).await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_bulk_streams_results_as_they_come() {
+        let auth = AuthImpl { admin_token: Some("secret".to_string()), ..Default::default() };
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        auth.register(register_request("alice", &y1, &y2)).await.unwrap();
+
+        // more runs than the buffer: every third one is wrong, the last one has no user
+        let count = 3 * VERIFY_BULK_BUFFER;
+        let mut runs: Vec<VerifyCompleteRequest> = (0..count)
+            .map(|i| {
+                let k = ZKP::generate_random_number_below(&q);
+                let c = ZKP::generate_random_number_below(&q);
+                let (r1, r2) = zkp.compute_pair(&k);
+                let s = zkp.solve(&k, &c, &x).unwrap() + u32::from(i % 3 == 0);
+                VerifyCompleteRequest {
+                    user: "alice".to_string(),
+                    r1: r1.to_bytes_be(),
+                    r2: r2.to_bytes_be(),
+                    c: c.to_bytes_be(),
+                    s: (s % &q).to_bytes_be(),
+                }
+            })
+            .collect();
+        runs.last_mut().unwrap().user = "nobody".to_string();
+
+        let mut request = Request::new(VerifyBulkRequest { runs: runs.clone() });
+        let status = auth.verify_bulk(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        request = Request::new(VerifyBulkRequest { runs });
+        request.metadata_mut().insert("x-admin-token", "secret".parse().unwrap());
+        let mut stream = auth.verify_bulk(request).await.unwrap().into_inner().into_inner();
+
+        // the first result is there while the rest is still being verified: what is ready
+        // behind it is about a buffer of results, not the whole batch
+        let mut results = vec![stream.recv().await.unwrap().unwrap()];
+        while let Ok(result) = stream.try_recv() {
+            results.push(result.unwrap());
+        }
+        assert!(results.len() < count);
+
+        while let Some(result) = stream.recv().await {
+            results.push(result.unwrap());
+        }
+        assert_eq!(results.len(), count);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.index as usize, i);
+            if i == count - 1 {
+                assert!(!result.valid && result.error.contains("nobody"));
+            } else {
+                assert_eq!(result.valid, i % 3 != 0, "run {i}");
+                assert!(result.error.is_empty());
+            }
+        }
+        // verified on the verify_pool, like the answers of verify_authentication
+        assert_eq!(auth.verify_pool.jobs(), count as u64 - 1);
+    }
+
+    #[cfg(feature = "audit")]
+    #[tokio::test]
+    async fn test_session_records_replay_to_the_same_outcome() {
+        let auth = AuthImpl::builder().session_records(true).build_impl();
+        let mut events = auth.event_watchers.subscribe();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_nonzero_below(&q);
+
+        // a wrong answer to one challenge, then the right one to another
+        let mut expected = Vec::new();
+        for (user, wrong) in [("alice", true), ("bob", false)] {
+            let k = ZKP::generate_nonzero_below(&q);
+            let (auth_id, c) = register_and_challenge(&auth, &zkp, user, &x, &k).await;
+            let s = (zkp.solve(&k, &c, &x).unwrap() + u32::from(wrong)) % &q;
+            let outcome = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+            assert_eq!(outcome.is_err(), wrong);
+            expected.push(if wrong { VerifyOutcome::R1Mismatch } else { VerifyOutcome::Valid });
+        }
+
+        let mut records = Vec::new();
+        while let Ok((_, event)) = events.try_recv() {
+            if let AuthEvent::Session(record) = event {
+                records.push(*record);
+            }
+        }
+        assert_eq!(records.len(), 2);
+        for (record, expected) in records.iter().zip(expected) {
+            assert_eq!(record.outcome, expected.as_code());
+            assert!(record.challenged_at <= record.answered_at);
+
+            let json =
+                serde_json::to_string(&AuthEvent::Session(Box::new(record.clone()))).unwrap();
+            assert!(json.contains("\"type\":\"session\""));
+            let AuthEvent::Session(parsed) = serde_json::from_str(&json).unwrap() else {
+                panic!("not a session record: {json}");
+            };
+            assert_eq!(*parsed, *record);
+            // on a server that never saw the login
+            assert_eq!(parsed.replay(&AuthImpl::default()).unwrap(), expected);
+        }
+
+        // off by default: the logins alone
+        let auth = AuthImpl::default();
+        let mut events = auth.event_watchers.subscribe();
+        let k = ZKP::generate_nonzero_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+        while let Ok((_, event)) = events.try_recv() {
+            assert_ne!(event.kind(), "session");
+        }
+    }
+
+    #[cfg(feature = "audit")]
+    #[tokio::test]
+    async fn test_watch_events_streams_concurrent_logins() {
+        let auth =
+            Arc::new(AuthImpl { admin_token: Some("secret".to_string()), ..Default::default() });
+        let status = auth.watch_events(Request::new(WatchEventsRequest {})).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let mut request = Request::new(WatchEventsRequest {});
+        request.metadata_mut().insert("x-admin-token", "secret".parse().unwrap());
+        let mut events = auth.watch_events(request).await.unwrap().into_inner().into_inner();
+
+        // alice registers and logs in on another task, then fails once
+        let login = tokio::spawn({
+            let auth = auth.clone();
+            async move {
+                let (alpha, beta, p, q) = ZKP::default_constants();
+                let zkp = ZKP::new(alpha, beta, p, q.clone());
+                let x = ZKP::generate_nonzero_below(&q);
+                for wrong in [false, true] {
+                    let k = ZKP::generate_nonzero_below(&q);
+                    let (auth_id, c) = if wrong {
+                        let (r1, r2) = zkp.compute_pair(&k);
+                        let request = Request::new(AuthenticationChallengeRequest {
+                            user: "alice".to_string(),
+                            r1: r1.to_bytes_be(),
+                            r2: r2.to_bytes_be(),
+                            binding: Vec::new(),
+                            y1: Vec::new(),
+                            y2: Vec::new(),
+                        });
+                        let response = auth
+                            .create_authentication_challenge(request)
+                            .await
+                            .unwrap()
+                            .into_inner();
+                        (response.auth_id, BigUint::from_bytes_be(&response.c))
+                    } else {
+                        register_and_challenge(&auth, &zkp, "alice", &x, &k).await
+                    };
+                    let s = (zkp.solve(&k, &c, &x).unwrap() + u32::from(wrong)) % &q;
+                    let _ = auth.verify_authentication(answer_request(&auth_id, &s)).await;
+                }
+            }
+        });
+
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            let event = events.recv().await.unwrap().unwrap();
+            assert!(event.timestamp > 0);
+            received.push((event.kind, event.user));
+        }
+        login.await.unwrap();
+        let alice = || "alice".to_string();
+        assert_eq!(
+            received,
+            [
+                ("registered".to_string(), alice()),
+                ("login_succeeded".to_string(), alice()),
+                ("login_failed".to_string(), alice()),
+            ]
+        );
+        // nothing else happened
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fixed_challenge_gives_predictable_response() {
+        let auth =
+            AuthImpl::builder().challenge_provider(FixedChallenge::new(4u32.into())).build_impl();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+        let x = BigUint::from(6u32);
+        let k = BigUint::from(100u32);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+        assert_eq!(c, BigUint::from(4u32));
+
+        // s = k - c * x = 100 - 24, known before the run started
+        let s = zkp.solve(&k, &c, &x).unwrap();
+        assert_eq!(s, BigUint::from(76u32));
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_challenge_provider_is_swappable() {
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_nonzero_below(&q);
+        let commitment = {
+            let (y1, y2) = zkp.compute_pair(&x);
+            Commitment::new(y1, y2)
+        };
+
+        // Fiat-Shamir: the client derives the c it is sent from its own transcript
+        let auth = AuthImpl::builder()
+            .challenge_provider(FiatShamirChallenge::new(b"login".to_vec()))
+            .build_impl();
+        let k = zkp.generate_nonce();
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, k.expose()).await;
+        let (r1, r2) = zkp.compute_pair(k.expose());
+        assert_eq!(c, zkp.fiat_shamir_challenge(&commitment, &r1, &r2, b"login"));
+        let s = zkp.solve(k.expose(), &c, &x).unwrap();
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_ok());
+
+        // the same verification refuses a wrong answer, whichever provider issued c
+        let providers: [Option<Box<dyn ChallengeProvider>>; 3] = [
+            None,
+            Some(Box::new(FiatShamirChallenge::new(b"login".to_vec()))),
+            Some(Box::new(FixedChallenge::new(4u32.into()))),
+        ];
+        for challenge_provider in providers {
+            let auth = AuthImpl { challenge_provider, ..Default::default() };
+            let k = zkp.generate_nonce();
+            let (auth_id, c) = register_and_challenge(&auth, &zkp, "bob", &x, k.expose()).await;
+            let s = zkp.solve(k.expose(), &c, &x).unwrap();
+            let wrong = (&s + 1u32) % &q;
+            let error = auth.verify_authentication(answer_request(&auth_id, &wrong)).await;
+            assert_eq!(error.unwrap_err().code(), Code::PermissionDenied);
+
+            let k = zkp.generate_nonce();
+            let (r1, r2) = zkp.compute_pair(k.expose());
+            let request = Request::new(AuthenticationChallengeRequest {
+                user: "bob".to_string(),
+                r1: r1.to_bytes_be(),
+                r2: r2.to_bytes_be(),
+                binding: Vec::new(),
+                y1: Vec::new(),
+                y2: Vec::new(),
+            });
+            let response =
+                auth.create_authentication_challenge(request).await.unwrap().into_inner();
+            let s = zkp.solve(k.expose(), &BigUint::from_bytes_be(&response.c), &x).unwrap();
+            assert!(
+                auth.verify_authentication(answer_request(&response.auth_id, &s)).await.is_ok()
+            );
+        }
+    }
+
+    // collects the request_id field of every span created while it is the default subscriber
+    #[derive(Clone, Default)]
+    struct RequestIds(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for RequestIds {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct RequestIdVisitor(Option<String>);
+
+            impl tracing::field::Visit for RequestIdVisitor {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+                    if field.name() == "request_id" {
+                        self.0 = Some(format!("{value:?}"));
+                    }
+                }
+            }
+
+            let mut visitor = RequestIdVisitor(None);
+            attrs.record(&mut visitor);
+            if let Some(id) = visitor.0 {
+                self.0.lock().unwrap().push((attrs.metadata().name().to_string(), id));
+            }
+        }
+    }
+
+    // collects the message of every warning logged while it is the default subscriber
+    #[derive(Clone, Default)]
+    struct Warnings(Arc<Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Warnings {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct MessageVisitor(String);
+
+            impl tracing::field::Visit for MessageVisitor {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+                    if field.name() == "message" {
+                        self.0 = format!("{value:?}");
+                    }
+                }
+            }
+
+            if *event.metadata().level() == tracing::Level::WARN {
+                let mut visitor = MessageVisitor(String::new());
+                event.record(&mut visitor);
+                self.0.lock().unwrap().push(visitor.0);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_swapped_c_and_s_are_flagged() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let warnings = Warnings::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(warnings.clone()));
+        let swap_warnings =
+            || warnings.0.lock().unwrap().iter().filter(|w| w.contains("c/s swap")).count();
+
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "alice", &x, &k).await;
+
+        // the issued c sent back as s: the normal refusal, plus the warning
+        let status = auth.verify_authentication(answer_request(&auth_id, &c)).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(!answer_failure_of(&status).retryable);
+        assert_eq!(swap_warnings(), 1);
+
+        // any other wrong answer is not a swap (a new k: the same r1, r2 would be refused)
+        let k = ZKP::generate_random_number_below(&q);
+        let (auth_id, c) = register_and_challenge(&auth, &zkp, "bob", &x, &k).await;
+        let s = (zkp.solve(&k, &c, &x).unwrap() + 1u32) % &q;
+        assert!(auth.verify_authentication(answer_request(&auth_id, &s)).await.is_err());
+        assert_eq!(swap_warnings(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_flows_into_span_and_back() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let ids = RequestIds::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(ids.clone()));
+
+        let auth = AuthImpl::default();
+        let (alpha, beta, p, q) = ZKP::default_constants();
+        let zkp = ZKP::new(alpha, beta, p, q.clone());
+        let (y1, y2) = zkp.compute_pair(&ZKP::generate_random_number_below(&q));
+
+        let mut request = register_request("alice", &y1, &y2);
+        request.metadata_mut().insert("x-request-id", "abc-123".parse().unwrap());
+        let response = auth.register(request).await.unwrap();
+
+        assert_eq!(response.metadata().get("x-request-id").unwrap().to_str().unwrap(), "abc-123");
+        assert!(ids.0.lock().unwrap().contains(&("register".to_string(), "abc-123".to_string())));
+
+        // without x-request-id the server makes one up, logs it and echoes it, errors included
+        let status = auth.verify_authentication(answer_request("unknown", &q)).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        let echoed = status.metadata().get("x-request-id").unwrap().to_str().unwrap().to_string();
+        assert!(!echoed.is_empty());
+        assert!(ids.0.lock().unwrap().contains(&("verify_authentication".to_string(), echoed)));
+
+        // the committed challenge flow is tagged the same way
+        let mut request = Request::new(RevealChallengeRequest {
+            auth_id: "unknown".to_string(),
+            r1: Vec::new(),
+            r2: Vec::new(),
+        });
+        request.metadata_mut().insert("x-request-id", "def-456".parse().unwrap());
+        let status = auth.reveal_challenge(request).await.unwrap_err();
+        assert_eq!(status.metadata().get("x-request-id").unwrap().to_str().unwrap(), "def-456");
+        assert!(
+            ids.0.lock().unwrap().contains(&("reveal_challenge".to_string(), "def-456".into()))
+        );
+
+        // an overlong id, or one with other than visible ASCII characters, is replaced
+        let overlong = "a".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        for sent in [overlong.as_str(), "def 456", "def\t456"] {
+            let mut request = Request::new(RevealChallengeRequest {
+                auth_id: "unknown".to_string(),
+                r1: Vec::new(),
+                r2: Vec::new(),
+            });
+            request.metadata_mut().insert("x-request-id", sent.parse().unwrap());
+            let status = auth.reveal_challenge(request).await.unwrap_err();
+            let echoed = status.metadata().get("x-request-id").unwrap().to_str().unwrap();
+            assert_ne!(echoed, sent);
+            assert_eq!(echoed.len(), 16);
+        }
+    }
 }
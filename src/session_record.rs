@@ -0,0 +1,117 @@
+// One login attempt as verify_authentication saw it: the registration the answer was checked
+// against, the challenge, the answer, when they happened and the outcome, every byte string in
+// hex (as in the dump). Emitted as AuthEvent::Session with ZKP_SESSION_RECORDS=1, and replayed
+// through AuthImpl::validate_answer_offline by whoever has one
+use serde::{Deserialize, Serialize};
+use tonic::{Code, Status};
+
+use crate::{AuthImpl, ChallengeInfo, UserInfo};
+use zkp_chaum_pedersen::{
+    Commitment, GroupEncoding, VerifyOutcome, ZKP,
+    zkp_auth::{
+        AuthenticationAnswerRequest, AuthenticationChallengeRequest,
+        AuthenticationChallengeResponse, RegisterRequest,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub user: String,
+    pub group_id: String,
+    // the commitment the answer was checked against (sent with the challenge, or registered)
+    pub y1: String,
+    pub y2: String,
+    pub auth_id: String,
+    pub r1: String,
+    pub r2: String,
+    pub c: String,
+    pub binding: String,
+    // as it was sent
+    pub s: String,
+    // unix seconds
+    pub challenged_at: u64,
+    pub answered_at: u64,
+    // VerifyOutcome::as_code, wrong_answer from a backend that only says yes or no
+    pub outcome: String,
+}
+
+impl SessionRecord {
+    pub fn new(
+        zkp: &ZKP,
+        auth_id: &str,
+        user_info: &UserInfo,
+        challenge: &ChallengeInfo,
+        s: &[u8],
+    ) -> Self {
+        let Commitment { y1, y2 } = match &challenge.commitment {
+            Some(commitment) => commitment.clone(),
+            None => Commitment::new(user_info.y1.clone(), user_info.registered_y2(zkp)),
+        };
+        let (r1, r2) = challenge.prover_commitment.as_ref().expect("a checked answer has r1, r2");
+        // when the answer arrived, before it was verified
+        let answered_at = ZKP::unix_timestamp();
+        SessionRecord {
+            user: user_info.user_name.clone(),
+            group_id: hex::encode_upper(user_info.group_id),
+            y1: hex::encode_upper(zkp.encode_element(&y1)),
+            y2: hex::encode_upper(zkp.encode_element(&y2)),
+            auth_id: auth_id.to_string(),
+            r1: hex::encode_upper(zkp.encode_element(r1)),
+            r2: hex::encode_upper(zkp.encode_element(r2)),
+            c: hex::encode_upper(zkp.encode_scalar(&challenge.c)),
+            binding: hex::encode_upper(&challenge.binding),
+            s: hex::encode_upper(s),
+            challenged_at: answered_at.saturating_sub(challenge.issued_at.elapsed().as_secs()),
+            answered_at,
+            outcome: String::new(),
+        }
+    }
+
+    // taken before the verification, completed with its outcome
+    pub fn with_outcome(mut self, outcome: Option<VerifyOutcome>) -> Self {
+        self.outcome = outcome.map_or("wrong_answer", |outcome| outcome.as_code()).to_string();
+        self
+    }
+
+    // the outcome of the recorded answer checked again in this server's groups, nothing stored
+    pub fn replay(&self, auth: &AuthImpl) -> Result<VerifyOutcome, Status> {
+        let bytes = |field: &str, value: &str| {
+            hex::decode(value).map_err(|_| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("{field} of the record is not valid hex"),
+                )
+            })
+        };
+        let binding = bytes("binding", &self.binding)?;
+        let group_id = bytes("group_id", &self.group_id)?;
+
+        let registration = RegisterRequest {
+            user: self.user.clone(),
+            y1: bytes("y1", &self.y1)?,
+            y2: bytes("y2", &self.y2)?,
+            group_id: group_id.clone(),
+            pow_nonce: 0,
+        };
+        let request = AuthenticationChallengeRequest {
+            user: self.user.clone(),
+            r1: bytes("r1", &self.r1)?,
+            r2: bytes("r2", &self.r2)?,
+            binding: binding.clone(),
+            y1: Vec::new(),
+            y2: Vec::new(),
+        };
+        let response = AuthenticationChallengeResponse {
+            auth_id: self.auth_id.clone(),
+            c: bytes("c", &self.c)?,
+            group_id,
+            binding: binding.clone(),
+        };
+        let answer = AuthenticationAnswerRequest {
+            auth_id: self.auth_id.clone(),
+            s: bytes("s", &self.s)?,
+            binding,
+        };
+        auth.validate_answer_offline(&registration, (&request, &response), &answer)
+    }
+}
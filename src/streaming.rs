@@ -0,0 +1,149 @@
+use num_bigint::BigUint;
+
+use crate::{Commitment, ZKP};
+
+// One message of an interactive run, in the order they are exchanged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptMessage {
+    // prover: r1 = alpha^k, r2 = beta^k
+    Commitment { r1: BigUint, r2: BigUint },
+    // verifier: c
+    Challenge(BigUint),
+    // prover: s = k - c * x mod q
+    Response(BigUint),
+}
+
+// Where a StreamingVerifier stands after a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamVerdict {
+    // fine so far, more messages needed
+    Pending,
+    Valid,
+    // a failed verification, or a message out of order (or after the verdict)
+    Invalid,
+}
+
+#[derive(Debug)]
+enum Stage {
+    AwaitingCommitment,
+    AwaitingChallenge { r1: BigUint, r2: BigUint },
+    AwaitingResponse { r1: BigUint, r2: BigUint, c: BigUint },
+    Done(StreamVerdict),
+}
+
+// Verifies a recorded run fed message by message (e.g. read off a stream) against the user's
+// registration (y1, y2): Commitment, then Challenge, then Response, the verdict comes with the
+// Response. Anything out of order is Invalid, a message after the verdict included
+#[derive(Debug)]
+pub struct StreamingVerifier<'a> {
+    zkp: &'a ZKP,
+    registration: &'a Commitment,
+    stage: Stage,
+}
+
+impl<'a> StreamingVerifier<'a> {
+    pub fn new(zkp: &'a ZKP, registration: &'a Commitment) -> Self {
+        StreamingVerifier { zkp, registration, stage: Stage::AwaitingCommitment }
+    }
+
+    pub fn feed(&mut self, message: TranscriptMessage) -> StreamVerdict {
+        let stage = std::mem::replace(&mut self.stage, Stage::Done(StreamVerdict::Invalid));
+        self.stage = match (stage, message) {
+            (Stage::AwaitingCommitment, TranscriptMessage::Commitment { r1, r2 }) => {
+                Stage::AwaitingChallenge { r1, r2 }
+            }
+            // a c out of range is refused now rather than with the response
+            (Stage::AwaitingChallenge { r1, r2 }, TranscriptMessage::Challenge(c))
+                if self.zkp.is_scalar(&c) =>
+            {
+                Stage::AwaitingResponse { r1, r2, c }
+            }
+            (Stage::AwaitingResponse { r1, r2, c }, TranscriptMessage::Response(s)) => {
+                let Commitment { y1, y2 } = self.registration;
+                if self.zkp.verify(&r1, &r2, y1, y2, &c, &s) {
+                    Stage::Done(StreamVerdict::Valid)
+                } else {
+                    Stage::Done(StreamVerdict::Invalid)
+                }
+            }
+            _ => Stage::Done(StreamVerdict::Invalid),
+        };
+        self.verdict()
+    }
+
+    // takes messages until there is a verdict, the rest of the iterator is not read;
+    // Pending if it ends first
+    pub fn feed_all(
+        &mut self,
+        messages: impl IntoIterator<Item = TranscriptMessage>,
+    ) -> StreamVerdict {
+        for message in messages {
+            let verdict = self.feed(message);
+            if verdict != StreamVerdict::Pending {
+                return verdict;
+            }
+        }
+        self.verdict()
+    }
+
+    pub fn verdict(&self) -> StreamVerdict {
+        match self.stage {
+            Stage::Done(verdict) => verdict,
+            _ => StreamVerdict::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // x = 6, k = 7, c = 4 in the toy group: y1 = 2, y2 = 3, r1 = 8, r2 = 4, s = 5
+    fn toy() -> (ZKP, Commitment) {
+        let zkp = ZKP::new(4u32.into(), 9u32.into(), 23u32.into(), 11u32.into());
+        (zkp, Commitment::new(2u32.into(), 3u32.into()))
+    }
+
+    fn run(s: u32) -> Vec<TranscriptMessage> {
+        vec![
+            TranscriptMessage::Commitment { r1: 8u32.into(), r2: 4u32.into() },
+            TranscriptMessage::Challenge(4u32.into()),
+            TranscriptMessage::Response(s.into()),
+        ]
+    }
+
+    #[test]
+    fn test_streaming_verifier_message_by_message() {
+        let (zkp, registration) = toy();
+
+        let mut verifier = StreamingVerifier::new(&zkp, &registration);
+        assert_eq!(verifier.verdict(), StreamVerdict::Pending);
+        let [commitment, challenge, response] = run(5).try_into().unwrap();
+        assert_eq!(verifier.feed(commitment), StreamVerdict::Pending);
+        assert_eq!(verifier.feed(challenge), StreamVerdict::Pending);
+        assert_eq!(verifier.feed(response), StreamVerdict::Valid);
+        // nothing comes after the response
+        assert_eq!(verifier.feed(TranscriptMessage::Response(5u32.into())), StreamVerdict::Invalid);
+
+        // a wrong s
+        let mut verifier = StreamingVerifier::new(&zkp, &registration);
+        assert_eq!(verifier.feed_all(run(6)), StreamVerdict::Invalid);
+
+        // out of order, or c not below q
+        let mut verifier = StreamingVerifier::new(&zkp, &registration);
+        assert_eq!(
+            verifier.feed(TranscriptMessage::Challenge(4u32.into())),
+            StreamVerdict::Invalid
+        );
+        let mut verifier = StreamingVerifier::new(&zkp, &registration);
+        let mut messages = run(5);
+        messages[1] = TranscriptMessage::Challenge(11u32.into());
+        assert_eq!(verifier.feed_all(messages), StreamVerdict::Invalid);
+
+        // a stream that ends early is still pending, and the rest can come later
+        let mut verifier = StreamingVerifier::new(&zkp, &registration);
+        let mut messages = run(5).into_iter();
+        assert_eq!(verifier.feed_all(messages.by_ref().take(2)), StreamVerdict::Pending);
+        assert_eq!(verifier.feed_all(messages), StreamVerdict::Valid);
+    }
+}
@@ -0,0 +1,117 @@
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChallengeSource, Commitment, Proof, ZKP, ZKPError};
+
+// Public parameters of the group a transcript was produced in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    #[serde(with = "crate::hex_biguint")]
+    pub p: BigUint,
+    #[serde(with = "crate::hex_biguint")]
+    pub q: BigUint,
+    #[serde(with = "crate::hex_biguint")]
+    pub alpha: BigUint,
+    #[serde(with = "crate::hex_biguint")]
+    pub beta: BigUint,
+}
+
+// A recorded protocol run, everything an auditor needs to re-check it offline:
+// {"group": {"p", "q", "alpha", "beta"}, "commitment": {"y1", "y2"}, "r1", "r2", "c", "s"}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub group: Group,
+    pub commitment: Commitment,
+    #[serde(flatten)]
+    pub proof: Proof,
+}
+
+// Outcome of audit_transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    pub passed: bool,
+    pub fingerprint: [u8; 32],
+}
+
+impl Transcript {
+    pub fn from_json(json: &str) -> Result<Transcript, ZKPError> {
+        serde_json::from_str(json).map_err(|e| ZKPError::MalformedEncoding(e.to_string()))
+    }
+
+    pub fn zkp(&self) -> ZKP {
+        let Group { p, q, alpha, beta } = self.group.clone();
+        ZKP::new(alpha, beta, p, q)
+    }
+}
+
+//re-runs verify on a JSON transcript using only the public values it contains
+pub fn audit_transcript(json: &str) -> Result<AuditReport, ZKPError> {
+    let transcript = Transcript::from_json(json)?;
+    let zkp = transcript.zkp();
+    let passed = zkp.verify_proof(
+        &transcript.commitment,
+        &transcript.proof,
+        ChallengeSource::Given(&transcript.proof.c),
+    );
+
+    Ok(AuditReport { passed, fingerprint: zkp.fingerprint() })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // toy example: p = 23, q = 11, alpha = 4, beta = 9, x = 6, k = 7, c = 4, s = 5
+    fn toy_transcript(s: &str) -> String {
+        format!(
+            r#"{{
+                "group": {{"p": "17", "q": "0B", "alpha": "04", "beta": "09"}},
+                "commitment": {{"y1": "02", "y2": "03"}},
+                "r1": "08", "r2": "04", "c": "04", "s": "{s}"
+            }}"#
+        )
+    }
+
+    #[test]
+    fn test_audit_known_good_and_bad_transcripts() {
+        let good = audit_transcript(&toy_transcript("05")).unwrap();
+        assert!(good.passed);
+
+        let bad = audit_transcript(&toy_transcript("06")).unwrap();
+        assert!(!bad.passed);
+
+        // both runs were in the same group
+        assert_eq!(good.fingerprint, bad.fingerprint);
+        assert_eq!(
+            good.fingerprint,
+            Transcript::from_json(&toy_transcript("05")).unwrap().zkp().fingerprint()
+        );
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_audit_round_trip_and_malformed() {
+        let (alpha, beta, p, q) = ZKP::get_constants();
+        let zkp = ZKP::new(alpha.clone(), beta.clone(), p.clone(), q.clone());
+        let x = ZKP::generate_random_number_below(&q);
+        let (y1, y2) = zkp.compute_pair(&x);
+        let k = ZKP::generate_random_number_below(&q);
+        let c = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let s = zkp.solve(&k, &c, &x).unwrap();
+
+        let transcript = Transcript {
+            group: Group { p, q, alpha, beta },
+            commitment: Commitment::new(y1, y2),
+            proof: Proof::new(r1, r2, c, s),
+        };
+        let json = serde_json::to_string(&transcript).unwrap();
+        assert_eq!(Transcript::from_json(&json).unwrap(), transcript);
+        assert_eq!(
+            audit_transcript(&json).unwrap(),
+            AuditReport { passed: true, fingerprint: zkp.fingerprint() }
+        );
+
+        assert!(matches!(audit_transcript("{}"), Err(ZKPError::MalformedEncoding(_))));
+    }
+}
@@ -0,0 +1,93 @@
+// the Auth service reached through grpc-web (HTTP/1.1), as a browser would
+#![cfg(feature = "grpc-web")]
+
+use std::{
+    process::{Child, Command},
+    time::Duration,
+};
+
+use tonic_web::GrpcWebClientLayer;
+use zkp_chaum_pedersen::{
+    ZKP,
+    zkp_auth::{
+        AuthenticationAnswerRequest, AuthenticationChallengeRequest, ParametersRequest,
+        RegisterRequest, auth_client::AuthClient,
+    },
+};
+
+const GRPC_ADDR: &str = "127.0.0.1:50152";
+
+// kills the server when the test ends, whatever the outcome
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_web_client() {
+    let _server = ServerProcess(
+        Command::new(env!("CARGO_BIN_EXE_server"))
+            .env("ZKP_ADDR", GRPC_ADDR)
+            .spawn()
+            .expect("could not start the server"),
+    );
+
+    // plain HTTP/1.1 client: only a grpc-web aware server can answer it
+    let http = hyper::Client::builder().build_http();
+    let service = tower::ServiceBuilder::new().layer(GrpcWebClientLayer::new()).service(http);
+    let mut client =
+        AuthClient::with_origin(service, format!("http://{GRPC_ADDR}").try_into().unwrap());
+
+    // the server needs a moment to bind its port
+    let mut attempts = 0;
+    let parameters = loop {
+        match client.get_parameters(ParametersRequest {}).await {
+            Ok(response) => break response.into_inner(),
+            Err(_) if attempts < 50 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(status) => panic!("server did not come up: {status}"),
+        }
+    };
+
+    let (alpha, beta, p, q) = ZKP::get_constants();
+    assert_eq!(parameters.p, p.to_bytes_be());
+    let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+    let x = ZKP::generate_random_number_below(&q);
+    let (y1, y2) = zkp.compute_pair(&x);
+    let request = RegisterRequest {
+        user: "alice".to_string(),
+        y1: y1.to_bytes_be(),
+        y2: y2.to_bytes_be(),
+        group_id: Vec::new(),
+        pow_nonce: 0,
+    };
+    client.register(request).await.unwrap();
+
+    let k = ZKP::generate_random_number_below(&q);
+    let (r1, r2) = zkp.compute_pair(&k);
+    let request = AuthenticationChallengeRequest {
+        user: "alice".to_string(),
+        r1: r1.to_bytes_be(),
+        r2: r2.to_bytes_be(),
+        binding: Vec::new(),
+        y1: Vec::new(),
+        y2: Vec::new(),
+    };
+    let challenge = client.create_authentication_challenge(request).await.unwrap().into_inner();
+    let c = zkp.challenge_from_bytes(&challenge.c).unwrap();
+
+    let s = zkp.solve(&k, &c, &x).unwrap();
+    let request = AuthenticationAnswerRequest {
+        auth_id: challenge.auth_id,
+        s: s.to_bytes_be(),
+        binding: Vec::new(),
+    };
+    let response = client.verify_authentication(request).await.unwrap().into_inner();
+    assert!(!response.session_id.is_empty());
+}
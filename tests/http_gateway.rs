@@ -0,0 +1,99 @@
+// end-to-end run of the protocol through the HTTP+JSON gateway of the server binary
+#![cfg(feature = "http")]
+
+use std::{
+    process::{Child, Command},
+    time::Duration,
+};
+
+use num_bigint::BigUint;
+use serde_json::{Value, json};
+use zkp_chaum_pedersen::ZKP;
+
+const GRPC_ADDR: &str = "127.0.0.1:50151";
+const HTTP_ADDR: &str = "127.0.0.1:58080";
+
+// kills the server when the test ends, whatever the outcome
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+fn hex(value: &BigUint) -> String {
+    hex::encode_upper(value.to_bytes_be())
+}
+
+async fn post(client: &reqwest::Client, path: &str, body: Value) -> reqwest::Response {
+    client.post(format!("http://{HTTP_ADDR}{path}")).json(&body).send().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_http_gateway_end_to_end() {
+    let _server = ServerProcess(
+        Command::new(env!("CARGO_BIN_EXE_server"))
+            .env("ZKP_ADDR", GRPC_ADDR)
+            .env("ZKP_HTTP_ADDR", HTTP_ADDR)
+            .spawn()
+            .expect("could not start the server"),
+    );
+
+    let client = reqwest::Client::new();
+    let (alpha, beta, p, q) = ZKP::get_constants();
+    let zkp = ZKP::new(alpha, beta, p, q.clone());
+
+    let x = ZKP::generate_random_number_below(&q);
+    let (y1, y2) = zkp.compute_pair(&x);
+    let register = json!({ "user": "alice", "y1": hex(&y1), "y2": hex(&y2) });
+
+    // the server needs a moment to bind its ports
+    let mut attempts = 0;
+    let response = loop {
+        let sent = client.post(format!("http://{HTTP_ADDR}/register")).json(&register).send().await;
+        match sent {
+            Ok(response) => break response,
+            Err(_) if attempts < 50 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(e) => panic!("gateway did not come up: {e}"),
+        }
+    };
+    assert!(response.status().is_success());
+
+    // a fresh k (and so a fresh (r1, r2)) for every challenge, returns (auth_id, k, c)
+    let challenge = async |client: &reqwest::Client| {
+        let k = ZKP::generate_random_number_below(&q);
+        let (r1, r2) = zkp.compute_pair(&k);
+        let body = json!({ "user": "alice", "r1": hex(&r1), "r2": hex(&r2) });
+        let challenge: Value = post(client, "/challenge", body).await.json().await.unwrap();
+        let auth_id = challenge["auth_id"].as_str().unwrap().to_string();
+        let c = hex::decode(challenge["c"].as_str().unwrap()).unwrap();
+        (auth_id, k, zkp.challenge_from_bytes(&c).unwrap())
+    };
+
+    // a wrong answer maps to 403 with the gRPC message
+    let (auth_id, k, c) = challenge(&client).await;
+    let body = json!({ "auth_id": auth_id, "s": hex(&(zkp.solve(&k, &c, &x).unwrap() + 1u32)) });
+    let response = post(&client, "/verify", body).await;
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let error: Value = response.json().await.unwrap();
+    assert!(error["error"].as_str().unwrap().contains("bad solution"));
+
+    // and uses the challenge up: the right answer needs a new one
+    let body = json!({ "auth_id": auth_id, "s": hex(&zkp.solve(&k, &c, &x).unwrap()) });
+    assert_eq!(post(&client, "/verify", body).await.status(), reqwest::StatusCode::CONFLICT);
+
+    let (auth_id, k, c) = challenge(&client).await;
+    let body = json!({ "auth_id": auth_id, "s": hex(&zkp.solve(&k, &c, &x).unwrap()) });
+    let response = post(&client, "/verify", body).await;
+    assert!(response.status().is_success());
+    let session: Value = response.json().await.unwrap();
+    assert!(!session["session_id"].as_str().unwrap().is_empty());
+
+    // malformed hex never reaches the protocol
+    let body = json!({ "user": "bob", "y1": "not hex", "y2": "00" });
+    assert_eq!(post(&client, "/register", body).await.status(), reqwest::StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,103 @@
+// the latency histograms of the server binary, scraped from ZKP_METRICS_ADDR after one login
+#![cfg(feature = "server")]
+use std::{
+    process::{Child, Command},
+    time::Duration,
+};
+
+use zkp_chaum_pedersen::{
+    ZKP,
+    zkp_auth::{
+        AuthenticationAnswerRequest, AuthenticationChallengeRequest, RegisterRequest,
+        auth_client::AuthClient,
+    },
+};
+
+const GRPC_ADDR: &str = "127.0.0.1:50154";
+const METRICS_ADDR: &str = "127.0.0.1:59154";
+
+// kills the server when the test ends, whatever the outcome
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_has_the_histograms() {
+    let _server = ServerProcess(
+        Command::new(env!("CARGO_BIN_EXE_server"))
+            .env("ZKP_ADDR", GRPC_ADDR)
+            .env("ZKP_METRICS_ADDR", METRICS_ADDR)
+            .spawn()
+            .expect("could not start the server"),
+    );
+
+    // the server needs a moment to bind its port
+    let mut attempts = 0;
+    let mut client = loop {
+        match AuthClient::connect(format!("http://{GRPC_ADDR}")).await {
+            Ok(client) => break client,
+            Err(_) if attempts < 50 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(e) => panic!("server did not come up: {e}"),
+        }
+    };
+
+    let zkp = ZKP::default();
+    let q = ZKP::default_constants().3;
+    let x = ZKP::generate_random_number_below(&q);
+    let (y1, y2) = zkp.compute_pair(&x);
+    let request = RegisterRequest {
+        user: "alice".to_string(),
+        y1: y1.to_bytes_be(),
+        y2: y2.to_bytes_be(),
+        group_id: Vec::new(),
+        pow_nonce: 0,
+    };
+    client.register(request).await.unwrap();
+
+    let k = ZKP::generate_random_number_below(&q);
+    let (r1, r2) = zkp.compute_pair(&k);
+    let request = AuthenticationChallengeRequest {
+        user: "alice".to_string(),
+        r1: r1.to_bytes_be(),
+        r2: r2.to_bytes_be(),
+        binding: Vec::new(),
+        y1: Vec::new(),
+        y2: Vec::new(),
+    };
+    let challenge = client.create_authentication_challenge(request).await.unwrap().into_inner();
+    let c = zkp.challenge_from_bytes(&challenge.c).unwrap();
+    let request = AuthenticationAnswerRequest {
+        auth_id: challenge.auth_id,
+        s: zkp.solve(&k, &c, &x).unwrap().to_bytes_be(),
+        binding: Vec::new(),
+    };
+    client.verify_authentication(request).await.unwrap();
+
+    let response = reqwest::get(format!("http://{METRICS_ADDR}/metrics")).await.unwrap();
+    assert!(response.status().is_success());
+    let text = response.text().await.unwrap();
+    assert!(text.contains("# TYPE zkp_rpc_duration_seconds histogram"));
+
+    let bits = zkp.group_bits();
+    for operation in ["register", "challenge", "verify"] {
+        let labels = format!("operation=\"{operation}\",group_bits=\"{bits}\"");
+        assert!(
+            text.contains(&format!("zkp_rpc_duration_seconds_bucket{{{labels},le=\"0.005\"}}"))
+        );
+        assert!(
+            text.contains(&format!("zkp_rpc_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 1"))
+        );
+        assert!(text.contains(&format!("zkp_rpc_duration_seconds_sum{{{labels}}}")));
+        assert!(text.contains(&format!("zkp_rpc_duration_seconds_count{{{labels}}} 1")));
+    }
+
+    let response = reqwest::get(format!("http://{METRICS_ADDR}/other")).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
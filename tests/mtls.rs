@@ -0,0 +1,108 @@
+// the server with ZKP_TLS_CLIENT_CA: only clients holding a certificate of that CA get through
+#![cfg(feature = "tls")]
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    time::Duration,
+};
+
+use rcgen::{BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa};
+use tonic::transport::{self, Channel, ClientTlsConfig, Endpoint, Identity};
+use zkp_chaum_pedersen::zkp_auth::{ParametersRequest, auth_client::AuthClient};
+
+const GRPC_ADDR: &str = "127.0.0.1:50153";
+
+// kills the server when the test ends, whatever the outcome
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+fn new_ca() -> Certificate {
+    let mut params = CertificateParams::new(Vec::new());
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    Certificate::from_params(params).unwrap()
+}
+
+// (certificate, key) in PEM, signed by ca
+fn leaf(ca: &Certificate, name: &str, usage: ExtendedKeyUsagePurpose) -> (String, String) {
+    let mut params = CertificateParams::new(vec![name.to_string()]);
+    params.extended_key_usages = vec![usage];
+    let cert = Certificate::from_params(params).unwrap();
+    (cert.serialize_pem_with_signer(ca).unwrap(), cert.serialize_private_key_pem())
+}
+
+fn write(dir: &Path, name: &str, pem: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, pem).unwrap();
+    path
+}
+
+// a TLS endpoint trusting the test CA, with or without a client certificate
+fn endpoint(ca_pem: &str, client: Option<&(String, String)>) -> Endpoint {
+    let mut tls = ClientTlsConfig::new()
+        .ca_certificate(transport::Certificate::from_pem(ca_pem))
+        .domain_name("localhost");
+    if let Some((cert, key)) = client {
+        tls = tls.identity(Identity::from_pem(cert, key));
+    }
+    Channel::from_shared(format!("https://{GRPC_ADDR}")).unwrap().tls_config(tls).unwrap()
+}
+
+// refused at the handshake, or at the first call when TLS 1.3 reports it that late
+async fn is_refused(endpoint: Endpoint) -> bool {
+    match endpoint.connect().await {
+        Err(_) => true,
+        Ok(channel) => AuthClient::new(channel).get_parameters(ParametersRequest {}).await.is_err(),
+    }
+}
+
+#[tokio::test]
+async fn test_client_certificate_is_required() {
+    let dir = std::env::temp_dir().join(format!("zkp-mtls-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let ca = new_ca();
+    let ca_pem = ca.serialize_pem().unwrap();
+    let (server_cert, server_key) = leaf(&ca, "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+    let client = leaf(&ca, "client", ExtendedKeyUsagePurpose::ClientAuth);
+    // a certificate from a CA the server does not know
+    let stranger = leaf(&new_ca(), "stranger", ExtendedKeyUsagePurpose::ClientAuth);
+
+    let _server = ServerProcess(
+        Command::new(env!("CARGO_BIN_EXE_server"))
+            .env("ZKP_ADDR", GRPC_ADDR)
+            .env("ZKP_TLS_CERT", write(&dir, "server.pem", &server_cert))
+            .env("ZKP_TLS_KEY", write(&dir, "server.key", &server_key))
+            .env("ZKP_TLS_CLIENT_CA", write(&dir, "ca.pem", &ca_pem))
+            .spawn()
+            .expect("could not start the server"),
+    );
+
+    // with a valid client certificate, once the server has bound its port
+    let mut attempts = 0;
+    let parameters = loop {
+        let response = match endpoint(&ca_pem, Some(&client)).connect().await {
+            Ok(channel) => AuthClient::new(channel).get_parameters(ParametersRequest {}).await,
+            Err(e) => Err(tonic::Status::unavailable(e.to_string())),
+        };
+        match response {
+            Ok(response) => break response.into_inner(),
+            Err(_) if attempts < 50 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(status) => panic!("server did not come up: {status}"),
+        }
+    };
+    assert!(!parameters.p.is_empty());
+
+    assert!(is_refused(endpoint(&ca_pem, None)).await, "no client certificate");
+    assert!(is_refused(endpoint(&ca_pem, Some(&stranger))).await, "unknown CA");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,6 @@
+// dropping the result of a verification must not compile silently
+#[test]
+fn unused_verification_results_are_rejected() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,14 @@
+#![deny(unused_must_use)]
+
+use zkp_chaum_pedersen::{ChallengeSource, Commitment, ZKP};
+
+fn main() {
+    let (alpha, beta, p, q) = ZKP::get_constants();
+    let zkp = ZKP::new(alpha, beta, p, q);
+    let x = 1u32.into();
+    let (y1, y2) = zkp.compute_pair(&x);
+    let proof = zkp.prove_non_interactive(&x, b"");
+
+    zkp.verify(&proof.r1, &proof.r2, &y1, &y2, &proof.c, &proof.s);
+    zkp.verify_proof(&Commitment::new(y1, y2), &proof, ChallengeSource::FiatShamir(b""));
+}